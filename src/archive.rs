@@ -0,0 +1,181 @@
+// the tree as an rkyv archive, searched in place wherever the bytes are
+// mapped without building anything in ram
+
+extern crate alloc;
+
+use super::{
+    distance::distance,
+    read::{logged, sort_by_distance, walk_tree, LeafCheck, SearchStats, Tree},
+    Suggestion,
+};
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt,
+    ops::{ControlFlow, RangeInclusive},
+};
+use rkyv::{vec::ArchivedVec, Archive, Serialize};
+
+// what the writer archives, nodes in `Node::index` order
+#[derive(Archive, Serialize)]
+#[cfg_attr(not(feature = "write"), allow(dead_code))]
+pub(crate) struct TreeArchive {
+    pub(crate) nodes: Vec<ArchiveNode>,
+}
+
+#[derive(Archive, Serialize)]
+#[rkyv(derive(Debug))]
+#[cfg_attr(not(feature = "write"), allow(dead_code))]
+pub(crate) struct ArchiveNode {
+    pub(crate) word: String,
+    // (bucket, node) in ascending bucket order
+    pub(crate) children: Vec<(u8, u32)>,
+}
+
+/// why bytes couldn't be used as an [`ArchivedBkTree`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// not an archived tree, truncated, corrupt or not 4 byte aligned
+    Malformed,
+    /// no root node
+    Empty,
+    /// `node` has a child that isn't a later node of the archive, so a
+    /// search could loop or run off the end
+    BadChild { node: u32 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "not an archived tree or not 4 byte aligned"),
+            Self::Empty => write!(f, "the archive has no nodes"),
+            Self::BadChild { node } => write!(f, "node {node} has a child that isn't after it"),
+        }
+    }
+}
+
+std_error!(ValidationError);
+
+/// a tree archived by `BkTreeBuilder::build_archive`, searched directly in
+/// the archive's bytes
+#[derive(Debug, Clone, Copy)]
+pub struct ArchivedBkTree {
+    nodes: &'static ArchivedVec<ArchivedArchiveNode>,
+}
+
+impl ArchivedBkTree {
+    /// checks `bytes` all the way through before the first search, which
+    /// reads every node once
+    pub fn new(bytes: &'static [u8]) -> Result<Self, ValidationError> {
+        let archive = rkyv::api::low::access::<ArchivedTreeArchive, rkyv::rancor::Failure>(bytes)
+            .map_err(|_| ValidationError::Malformed)?;
+        let nodes = &archive.nodes;
+        if nodes.is_empty() {
+            return Err(ValidationError::Empty);
+        }
+        // children always come after their parent, so there are no cycles
+        for (i, node) in nodes.iter().enumerate() {
+            let bad = |child: u32| child as usize <= i || child as usize >= nodes.len();
+            if node.children.iter().any(|c| bad(c.1.to_native())) {
+                return Err(ValidationError::BadChild { node: i as u32 });
+            }
+        }
+        Ok(Self { nodes })
+    }
+
+    /// how many words the archive holds
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let mut found = false;
+        self.walk(word, 0, &mut |_| {
+            found = true;
+            ControlFlow::Break(())
+        });
+        found
+    }
+
+    /// every word within `tolerance` edits of `word`, sorted by distance
+    /// (ties keep traversal order)
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.walk(word, tolerance, &mut |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    /// the closest word within `tolerance`, on equal distance the one
+    /// found first in traversal order
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<Suggestion<'static>> {
+        let mut best: Option<Suggestion> = None;
+        self.walk(word, tolerance, &mut |s| {
+            if best.map_or(true, |b| s.distance < b.distance) {
+                best = Some(s);
+            }
+            match s.distance {
+                0 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        });
+        best
+    }
+
+    fn walk(
+        &self,
+        word: &str,
+        tolerance: u8,
+        visit: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let nodes = self.nodes;
+        let tolerance = tolerance as usize;
+        let _ = walk_tree(
+            self,
+            0,
+            logged(&word),
+            &|w| distance(w, word),
+            &LeafCheck::default(),
+            tolerance,
+            0,
+            &mut SearchStats::default(),
+            &mut |node, distance| match distance <= tolerance {
+                true => visit(Suggestion::new(
+                    word,
+                    nodes[node].word.as_str(),
+                    distance as u8,
+                )),
+                false => ControlFlow::Continue(()),
+            },
+        );
+    }
+}
+
+impl Tree for ArchivedBkTree {
+    type Node = usize;
+
+    fn word(&self, node: usize) -> &str {
+        self.nodes[node].word.as_str()
+    }
+
+    fn children(
+        &self,
+        node: usize,
+        range: RangeInclusive<usize>,
+        visit: &mut dyn FnMut(usize) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        for child in self.nodes[node].children.iter() {
+            if range.contains(&(child.0 as usize)) {
+                visit(child.1.to_native() as usize)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
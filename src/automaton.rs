@@ -0,0 +1,97 @@
+// a levenshtein automaton for a query, built once and then run over each
+// word instead of the dp. it only says whether a word is within tolerance
+// and how far when it is, nodes that pick buckets still need the dp
+
+/// longest query, in chars, an automaton is built for. it's on the stack
+/// for every search, about 12 bytes a char, so 16 bit targets with a few
+/// kilobytes of ram keep it to short queries
+pub(crate) const MAX_AUTOMATON_LEN: usize = match cfg!(target_pointer_width = "16") {
+    true => 15,
+    false => 63,
+};
+/// highest tolerance an automaton is built for, past it the dp is as quick
+pub(crate) const MAX_AUTOMATON_TOLERANCE: usize = 2;
+
+// the levenshtein nfa run bit parallel. row `e` has bit `i` set while the
+// first `i` chars of the query are within `e` edits of the chars read so
+// far, so the whole state is one u64 per row, 24 bytes at most
+pub(crate) struct Automaton {
+    // the query's distinct chars, each with bit `i + 1` set for every
+    // position `i` it's at
+    chars: [char; MAX_AUTOMATON_LEN],
+    positions: [u64; MAX_AUTOMATON_LEN],
+    distinct: usize,
+    len: usize,
+    tolerance: usize,
+}
+
+impl Automaton {
+    // `None` when the query or tolerance is past the limits above
+    pub(crate) fn new(word: &str, tolerance: usize) -> Option<Self> {
+        if tolerance > MAX_AUTOMATON_TOLERANCE {
+            return None;
+        }
+        let mut automaton = Self {
+            chars: ['\0'; MAX_AUTOMATON_LEN],
+            positions: [0; MAX_AUTOMATON_LEN],
+            distinct: 0,
+            len: 0,
+            tolerance,
+        };
+        for c in word.chars() {
+            if automaton.len == MAX_AUTOMATON_LEN {
+                return None;
+            }
+            automaton.len += 1;
+            let bit = 1 << automaton.len;
+            match automaton.chars[..automaton.distinct]
+                .iter()
+                .position(|&known| known == c)
+            {
+                Some(at) => automaton.positions[at] |= bit,
+                None => {
+                    automaton.chars[automaton.distinct] = c;
+                    automaton.positions[automaton.distinct] = bit;
+                    automaton.distinct += 1;
+                }
+            }
+        }
+        Some(automaton)
+    }
+
+    fn positions(&self, c: char) -> u64 {
+        self.chars[..self.distinct]
+            .iter()
+            .position(|&known| known == c)
+            .map_or(0, |at| self.positions[at])
+    }
+
+    // the char distance from the query to `word`, `None` past tolerance.
+    // gives up as soon as no state is left
+    pub(crate) fn distance(&self, word: &str) -> Option<usize> {
+        let states = u64::MAX >> (63 - self.len);
+        // the first `e` query chars deleted
+        let mut rows = [1, 3, 7];
+        let rows = &mut rows[..=self.tolerance];
+        for row in rows.iter_mut() {
+            *row &= states;
+        }
+        for c in word.chars() {
+            let matches = self.positions(c);
+            let mut before = rows[0];
+            rows[0] = (rows[0] << 1) & matches;
+            for e in 1..rows.len() {
+                let row = rows[e];
+                // a match, an inserted char, a substitution or a deleted
+                // query char after the one just read
+                rows[e] = ((row << 1) & matches | before | before << 1 | rows[e - 1] << 1) & states;
+                before = row;
+            }
+            if rows[rows.len() - 1] == 0 {
+                return None;
+            }
+        }
+        let accept = 1 << self.len;
+        rows.iter().position(|row| row & accept != 0)
+    }
+}
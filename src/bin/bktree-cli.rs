@@ -0,0 +1,234 @@
+// try a word list out on the host before baking it into firmware
+//
+//     cargo run --features cli --bin bktree-cli -- words.txt
+//     cargo run --features cli --bin bktree-cli -- words.txt --query cars --tolerance 2
+//
+// a word list is one word per line. a generated tree.rs is loaded word by
+// word in its own order, which rebuilds the same shape, so the suggestions
+// and visited counts are the ones the firmware would see
+
+use embedded_bktree::{generated_words, RuntimeBkTree};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    process::ExitCode,
+    time::Instant,
+};
+
+const USAGE: &str = "usage: bktree-cli <words.txt | tree.rs> [--query WORD] [--tolerance N]";
+
+struct Args {
+    path: String,
+    query: Option<String>,
+    tolerance: u8,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut path = None;
+    let mut query = None;
+    let mut tolerance = 2;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--query" => query = Some(args.next().ok_or("--query needs a word")?),
+            "--tolerance" => {
+                let value = args.next().ok_or("--tolerance needs a number")?;
+                tolerance = value
+                    .parse()
+                    .map_err(|_| format!("bad tolerance {value}"))?;
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}\n{USAGE}")),
+        }
+    }
+    Ok(Args {
+        path: path.ok_or(USAGE)?,
+        query,
+        tolerance,
+    })
+}
+
+fn load(path: &str, log: &mut impl Write) -> Result<RuntimeBkTree, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}"))?;
+    let started = Instant::now();
+    let mut tree = RuntimeBkTree::new();
+    if path.ends_with(".rs") || path.ends_with(".test") {
+        let words = generated_words(&contents).map_err(|e| format!("can't read {path}: {e:?}"))?;
+        for word in words {
+            tree.insert(word);
+        }
+    } else {
+        let stats = tree.bulk_load(contents.lines().map(str::trim));
+        if stats.skipped > 0 {
+            let _ = writeln!(log, "skipped {} empty or duplicate lines", stats.skipped);
+        }
+    }
+    let stats = tree.stats();
+    let _ = writeln!(
+        log,
+        "loaded {} words, depth {}, in {:?}",
+        tree.len(),
+        stats.depth,
+        started.elapsed()
+    );
+    Ok(tree)
+}
+
+// every suggestion closest first, then how much the search cost
+fn query(tree: &RuntimeBkTree, word: &str, tolerance: u8, out: &mut impl Write) -> io::Result<()> {
+    let started = Instant::now();
+    let corrections = tree.corrections(word, tolerance);
+    let elapsed = started.elapsed();
+    let stats = tree.search_stats(word, tolerance);
+    for suggestion in &corrections {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            suggestion.word, suggestion.distance, suggestion.confidence
+        )?;
+    }
+    if corrections.is_empty() {
+        writeln!(out, "no words within {tolerance}")?;
+    }
+    writeln!(
+        out,
+        "visited {} nodes, {} distance calls, in {elapsed:?}",
+        stats.nodes_visited, stats.distance_calls
+    )
+}
+
+// a query per line, optionally followed by a tolerance for just that line
+fn interactive(
+    tree: &RuntimeBkTree,
+    tolerance: u8,
+    input: impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    write!(out, "> ")?;
+    out.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let mut parts = line.split_whitespace();
+        if let Some(word) = parts.next() {
+            match parts.next().map(str::parse) {
+                None => query(tree, word, tolerance, out)?,
+                Some(Ok(tolerance)) => query(tree, word, tolerance, out)?,
+                Some(Err(_)) => writeln!(out, "tolerance has to be 0 to 255")?,
+            }
+        }
+        write!(out, "> ")?;
+        out.flush()?;
+    }
+    writeln!(out)
+}
+
+fn run(
+    args: impl Iterator<Item = String>,
+    input: impl BufRead,
+    out: &mut impl Write,
+    log: &mut impl Write,
+) -> Result<(), String> {
+    let args = parse_args(args)?;
+    let tree = load(&args.path, log)?;
+    let result = match &args.query {
+        Some(word) => query(&tree, word, args.tolerance, out),
+        None => interactive(&tree, args.tolerance, input, out),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let stdin = io::stdin();
+    match run(
+        env::args().skip(1),
+        stdin.lock(),
+        &mut io::stdout(),
+        &mut io::stderr(),
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::run;
+    use std::{fs, io};
+
+    fn one_shot(path: &str, word: &str, tolerance: &str) -> Vec<String> {
+        let args = [path, "--query", word, "--tolerance", tolerance].map(String::from);
+        let mut out = Vec::new();
+        run(args.into_iter(), io::empty(), &mut out, &mut io::sink()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        // the timing changes every run
+        out.lines()
+            .map(|line| line.split(", in ").next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn word_list() {
+        let path = std::env::temp_dir().join("embedded-bktree-cli.txt");
+        fs::write(&path, "the\nthem\nshe\nhe\ncar\ncare\ncard\ncake\n\ncar\n").unwrap();
+        let out = one_shot(path.to_str().unwrap(), "cars", "1");
+        assert_eq!(out.len(), 4);
+        let mut found: Vec<_> = out[..3].iter().map(String::as_str).collect();
+        found.sort_unstable();
+        assert_eq!(found, ["car\t1\t191", "card\t1\t191", "care\t1\t191"]);
+        assert!(out[3].starts_with("visited "));
+
+        let out = one_shot(path.to_str().unwrap(), "zzzzzz", "1");
+        assert_eq!(out[0], "no words within 1");
+    }
+
+    #[test]
+    fn generated_tree() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tree.test");
+        let out = one_shot(path, "cars", "1");
+        // the fixture's own shape and traversal order
+        assert_eq!(
+            out,
+            [
+                "car\t1\t191",
+                "care\t1\t191",
+                "card\t1\t191",
+                "visited 5 nodes, 5 distance calls"
+            ]
+        );
+        // the same tree laid out depth first
+        let dfs = concat!(env!("CARGO_MANIFEST_DIR"), "/tree_dfs.test");
+        assert_eq!(one_shot(dfs, "cars", "1"), out);
+    }
+
+    #[test]
+    fn interactive() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tree.test");
+        let args = [path.to_string()];
+        let mut out = Vec::new();
+        let input = "he\nshe 0\nshe x\n".as_bytes();
+        run(args.into_iter(), input, &mut out, &mut io::sink()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("> he\t0\t255\n"));
+        assert!(out.contains("> she\t0\t255\nvisited "));
+        assert!(out.ends_with("> tolerance has to be 0 to 255\n> \n"));
+    }
+
+    #[test]
+    fn bad_args() {
+        let run = |args: &[&str]| {
+            let args = args.iter().map(|a| a.to_string());
+            run(args, io::empty(), &mut io::sink(), &mut io::sink())
+        };
+        assert!(run(&[]).unwrap_err().starts_with("usage:"));
+        assert_eq!(
+            run(&["words.txt", "--tolerance", "x"]).unwrap_err(),
+            "bad tolerance x"
+        );
+        assert!(run(&["/no/such/file"])
+            .unwrap_err()
+            .starts_with("can't read"));
+    }
+}
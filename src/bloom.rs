@@ -0,0 +1,62 @@
+// a bloom filter over the words of a tree, so a lookup of a word that isn't
+// there is usually answered without measuring anything. the writer sets
+// the bits and the reader tests them, both through `bits`
+
+use super::hash::{fnv1a, mix};
+#[cfg(feature = "read")]
+use super::{Node, StaticBkTree};
+
+// the bits `word` sets in a filter of `len` bits, by double hashing
+pub(crate) fn bits(word: &str, hashes: u32, len: u32) -> impl Iterator<Item = u32> {
+    let first = fnv1a(word.as_bytes());
+    let step = mix(first);
+    (0..hashes).map(move |i| first.wrapping_add(i.wrapping_mul(step)) % len)
+}
+
+/// the generated `BLOOM` of `BkTreeBuilder::bloom_filter`, every word of
+/// the tree in a bit array
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, Copy)]
+pub struct BloomFilter {
+    bits: &'static [u32],
+    hashes: u32,
+}
+
+#[cfg(feature = "read")]
+impl BloomFilter {
+    pub const fn new(bits: &'static [u32], hashes: u32) -> Self {
+        Self { bits, hashes }
+    }
+
+    /// false when `word` is certainly not in the tree. true for every word
+    /// that is and now and then for one that isn't
+    pub fn may_contain(&self, word: &str) -> bool {
+        let len = self.bits.len() as u32 * 32;
+        len == 0
+            || bits(word, self.hashes, len)
+                .all(|bit| self.bits[(bit / 32) as usize] >> (bit % 32) & 1 == 1)
+    }
+
+    /// the size of the bit array
+    pub const fn bytes(&self) -> usize {
+        self.bits.len() * 4
+    }
+}
+
+#[cfg(feature = "read")]
+impl Node {
+    /// [`Node::contains`] asking `filter` first, the generated `BLOOM` of
+    /// this tree. most words that aren't there never get to the tree, the
+    /// rest are looked for as usual, so the answer is always the same
+    pub fn contains_fast(&'static self, filter: &BloomFilter, word: &str) -> bool {
+        filter.may_contain(word) && self.contains(word)
+    }
+}
+
+#[cfg(feature = "read")]
+impl StaticBkTree {
+    /// see [`Node::contains_fast`]
+    pub fn contains_fast(&self, filter: &BloomFilter, word: &str) -> bool {
+        self.root().contains_fast(filter, word)
+    }
+}
@@ -0,0 +1,133 @@
+// remembers the last few searches, autocorrect tends to ask for the same
+// word again on every keystroke
+
+use super::{hash::fnv1a, Node, Suggestion};
+
+/// longest query the cache will hold, longer ones are always searched
+pub const MAX_CACHED_QUERY: usize = 32;
+
+const EMPTY: Suggestion<'static> = Suggestion {
+    word: "",
+    distance: 0,
+    confidence: 0,
+};
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<const M: usize> {
+    hash: u32,
+    tolerance: u8,
+    query: [u8; MAX_CACHED_QUERY],
+    query_len: u8,
+    last_used: u32,
+    results: [Suggestion<'static>; M],
+    len: u8,
+}
+
+impl<const M: usize> Entry<M> {
+    fn matches(&self, hash: u32, word: &str, tolerance: u8) -> bool {
+        // the hash only shortcuts the comparison, the query itself decides
+        self.hash == hash
+            && self.tolerance == tolerance
+            && &self.query[..self.query_len as usize] == word.as_bytes()
+    }
+}
+
+/// least recently used cache of up to `N` searches, each keeping the `M`
+/// closest words (the same as `best_k_into::<M>`).
+///
+/// a hit always returns exactly what searching again would, entries are
+/// matched on the full query and tolerance rather than just their hash
+#[derive(Debug, Clone)]
+pub struct CachedSearcher<const N: usize, const M: usize> {
+    root: &'static Node,
+    entries: [Option<Entry<M>>; N],
+    // scratch for queries too long to cache
+    uncached: [Suggestion<'static>; M],
+    clock: u32,
+    hits: u32,
+    misses: u32,
+}
+
+impl<const N: usize, const M: usize> CachedSearcher<N, M> {
+    pub const fn new(root: &'static Node) -> Self {
+        Self {
+            root,
+            entries: [None; N],
+            uncached: [EMPTY; M],
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// the `M` closest words within `tolerance` of `word`, sorted by distance
+    pub fn corrections(&mut self, word: &str, tolerance: u8) -> &[Suggestion<'static>] {
+        self.clock = self.clock.wrapping_add(1);
+        let hash = hash(word, tolerance);
+        if let Some(i) = self
+            .entries
+            .iter()
+            .position(|e| e.is_some_and(|e| e.matches(hash, word, tolerance)))
+        {
+            self.hits += 1;
+            let entry = self.entries[i].as_mut().unwrap();
+            entry.last_used = self.clock;
+            return &entry.results[..entry.len as usize];
+        }
+
+        self.misses += 1;
+        let mut results = [EMPTY; M];
+        let len = search(self.root, word, tolerance, &mut results);
+        if word.len() > MAX_CACHED_QUERY || N == 0 {
+            self.uncached = results;
+            return &self.uncached[..len];
+        }
+        let mut query = [0; MAX_CACHED_QUERY];
+        query[..word.len()].copy_from_slice(word.as_bytes());
+        let entry = Entry {
+            hash,
+            tolerance,
+            query,
+            query_len: word.len() as u8,
+            last_used: self.clock,
+            results,
+            len: len as u8,
+        };
+        // an empty slot if there is one, otherwise the least recently used
+        let clock = self.clock;
+        let age = |e: &Option<Entry<M>>| e.map_or(u32::MAX, |e| clock.wrapping_sub(e.last_used));
+        let slot = (0..N).max_by_key(|i| age(&self.entries[*i])).unwrap();
+        let entry = self.entries[slot].insert(entry);
+        &entry.results[..entry.len as usize]
+    }
+
+    /// forgets every cached search
+    pub fn clear(&mut self) {
+        self.entries = [None; N];
+    }
+
+    pub fn hits(&self) -> u32 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u32 {
+        self.misses
+    }
+}
+
+fn hash(word: &str, tolerance: u8) -> u32 {
+    fnv1a(word.as_bytes()) ^ tolerance as u32
+}
+
+fn search<const M: usize>(
+    root: &'static Node,
+    query: &str,
+    tolerance: u8,
+    out: &mut [Suggestion<'static>; M],
+) -> usize {
+    let (best, len) = root.best_k_into::<M>(query, tolerance);
+    for (slot, (word, distance)) in out.iter_mut().zip(best.into_iter().flatten()) {
+        *slot = Suggestion::new(query, word, distance);
+    }
+    len
+}
@@ -0,0 +1,97 @@
+// carries the way the user capitalized a word over to the suggestion
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+
+/// `out` can't hold the recased suggestion, `needed` bytes would
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufTooSmall {
+    pub needed: usize,
+}
+
+impl fmt::Display for BufTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the buffer is too small, {} bytes are needed",
+            self.needed
+        )
+    }
+}
+
+std_error!(BufTooSmall);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    AsStored,
+    Title,
+    Upper,
+}
+
+fn case_of(query: &str) -> Case {
+    let mut cased = query
+        .chars()
+        .filter(|c| c.is_uppercase() || c.is_lowercase());
+    let Some(first) = cased.next() else {
+        return Case::AsStored;
+    };
+    let (mut upper, mut lower) = (0, 0);
+    for c in cased {
+        match c.is_uppercase() {
+            true => upper += 1,
+            false => lower += 1,
+        }
+    }
+    match (first.is_uppercase(), upper, lower) {
+        (true, 0, _) => Case::Title,
+        (true, _, 0) => Case::Upper,
+        _ => Case::AsStored,
+    }
+}
+
+// the chars of `suggestion` capitalized like `query`
+fn recased<'a>(query: &str, suggestion: &'a str) -> impl Iterator<Item = char> + Clone + 'a {
+    let case = case_of(query);
+    suggestion.chars().enumerate().flat_map(move |(i, c)| {
+        let upper = case == Case::Upper || (case == Case::Title && i == 0);
+        // either the uppercase mapping or the char itself, never both
+        let (mapped, kept) = match upper {
+            true => (Some(c.to_uppercase()), None),
+            false => (None, Some(c)),
+        };
+        mapped.into_iter().flatten().chain(kept)
+    })
+}
+
+/// `suggestion` capitalized like `query` was, written into `out`: Title
+/// case queries title case the first character, ALL CAPS ones uppercase
+/// everything and anything else returns the suggestion as stored.
+///
+/// uppercasing is the full unicode mapping so it can grow the word, "ß"
+/// becomes "SS", and `out` has to be sized for that
+pub fn apply_case<'o>(
+    query: &str,
+    suggestion: &str,
+    out: &'o mut [u8],
+) -> Result<&'o str, BufTooSmall> {
+    let chars = recased(query, suggestion);
+    let needed: usize = chars.clone().map(char::len_utf8).sum();
+    if needed > out.len() {
+        return Err(BufTooSmall { needed });
+    }
+    let mut len = 0;
+    for c in chars {
+        len += c.encode_utf8(&mut out[len..]).len();
+    }
+    // only whole chars were written
+    Ok(core::str::from_utf8(&out[..len]).unwrap())
+}
+
+/// [`apply_case`] into a new string
+#[cfg(feature = "alloc")]
+pub fn apply_case_string(query: &str, suggestion: &str) -> String {
+    recased(query, suggestion).collect()
+}
@@ -0,0 +1,48 @@
+// completions for an `embedded-cli` shell whose command names are the words
+// of a tree. the shell only appends to what was typed, so the hook fills in
+// the names the input is the start of, and the names a typo is 1 edit from
+// are handed out separately for the unknown command message
+
+use super::Node;
+use core::ops::ControlFlow;
+use embedded_cli::autocomplete::{Autocompletion, Request};
+
+/// the body of `Autocomplete::autocomplete` for a command type named by
+/// the words of `tree`. every name starting with what was typed is merged
+/// in, so the shell fills in as far as they agree and marks it partial
+/// when they go different ways
+pub fn autocomplete(
+    tree: &'static Node,
+    request: Request<'_>,
+    autocompletion: &mut Autocompletion<'_>,
+) {
+    if let Request::CommandName(name) = request {
+        tree.for_each_completion(name, |word| {
+            autocompletion.merge_autocompletion(&word[name.len()..]);
+            ControlFlow::Continue(())
+        });
+    }
+}
+
+/// the command names `input` could have meant, written to the front of
+/// `out` and how many there are. the names it's the start of come first,
+/// then the ones it's 1 edit from, each in traversal order until `out`
+/// is full
+pub fn command_suggestions(tree: &'static Node, input: &str, out: &mut [&'static str]) -> usize {
+    let mut found = 0;
+    let mut push = |word| {
+        if found == out.len() {
+            return ControlFlow::Break(());
+        }
+        out[found] = word;
+        found += 1;
+        ControlFlow::Continue(())
+    };
+    tree.for_each_completion(input, &mut push);
+    tree.for_each_correction(input, 1, |s| match s.word.starts_with(input) {
+        // a completion already
+        true => ControlFlow::Continue(()),
+        false => push(s.word),
+    });
+    found
+}
@@ -0,0 +1,115 @@
+// folds letters that look like ascii but aren't, cyrillic "а" or a
+// fullwidth "ａ" pasted in from the web, onto the ascii letter so they
+// don't cost an edit. one char at a time, never allocating. shared by the
+// writer, for dictionary words, and the reader, for queries
+
+#[cfg(feature = "read")]
+use super::BufTooSmall;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+// the common homoglyphs of latin letters, sorted by char. fullwidth forms
+// are a range and are worked out instead
+const CONFUSABLES: &[(char, char)] = &[
+    ('\u{131}', 'i'), // dotless i
+    // greek
+    ('\u{391}', 'A'),
+    ('\u{392}', 'B'),
+    ('\u{395}', 'E'),
+    ('\u{396}', 'Z'),
+    ('\u{397}', 'H'),
+    ('\u{399}', 'I'),
+    ('\u{39a}', 'K'),
+    ('\u{39c}', 'M'),
+    ('\u{39d}', 'N'),
+    ('\u{39f}', 'O'),
+    ('\u{3a1}', 'P'),
+    ('\u{3a4}', 'T'),
+    ('\u{3a5}', 'Y'),
+    ('\u{3a7}', 'X'),
+    ('\u{3b1}', 'a'),
+    ('\u{3b9}', 'i'),
+    ('\u{3ba}', 'k'),
+    ('\u{3bd}', 'v'),
+    ('\u{3bf}', 'o'),
+    ('\u{3c1}', 'p'),
+    ('\u{3c5}', 'u'),
+    ('\u{3c7}', 'x'),
+    // cyrillic
+    ('\u{405}', 'S'),
+    ('\u{406}', 'I'),
+    ('\u{408}', 'J'),
+    ('\u{410}', 'A'),
+    ('\u{412}', 'B'),
+    ('\u{415}', 'E'),
+    ('\u{41a}', 'K'),
+    ('\u{41c}', 'M'),
+    ('\u{41d}', 'H'),
+    ('\u{41e}', 'O'),
+    ('\u{420}', 'P'),
+    ('\u{421}', 'C'),
+    ('\u{422}', 'T'),
+    ('\u{423}', 'Y'),
+    ('\u{425}', 'X'),
+    ('\u{430}', 'a'),
+    ('\u{435}', 'e'),
+    ('\u{43e}', 'o'),
+    ('\u{440}', 'p'),
+    ('\u{441}', 'c'),
+    ('\u{443}', 'y'),
+    ('\u{445}', 'x'),
+    ('\u{455}', 's'),
+    ('\u{456}', 'i'),
+    ('\u{458}', 'j'),
+    ('\u{4bb}', 'h'),
+    ('\u{501}', 'd'),
+    ('\u{51b}', 'q'),
+    ('\u{51d}', 'w'),
+];
+
+/// the ascii char `c` is mistaken for, or `c` itself. case is kept,
+/// cyrillic "А" is "A", so fold before any case folding
+pub const fn fold_confusable(c: char) -> char {
+    // fullwidth ! to ~
+    if c >= '\u{ff01}' && c <= '\u{ff5e}' {
+        return (c as u8).wrapping_add(0x20) as char;
+    }
+    let (mut low, mut high) = (0, CONFUSABLES.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        let (from, to) = CONFUSABLES[mid];
+        if from == c {
+            return to;
+        }
+        match (from as u32) < (c as u32) {
+            true => low = mid + 1,
+            false => high = mid,
+        }
+    }
+    c
+}
+
+/// `word` with [`fold_confusable`] applied to every char, written into
+/// `out`. the folded word is never longer, `out` the size of `word` fits
+#[cfg(feature = "read")]
+pub fn fold_confusables<'o>(word: &str, out: &'o mut [u8]) -> Result<&'o str, BufTooSmall> {
+    let chars = word.chars().map(fold_confusable);
+    let needed: usize = chars.clone().map(char::len_utf8).sum();
+    if needed > out.len() {
+        return Err(BufTooSmall { needed });
+    }
+    let mut len = 0;
+    for c in chars {
+        len += c.encode_utf8(&mut out[len..]).len();
+    }
+    // only whole chars were written
+    Ok(core::str::from_utf8(&out[..len]).unwrap())
+}
+
+/// [`fold_confusables`] into a new string
+#[cfg(feature = "alloc")]
+pub fn fold_confusables_string(word: &str) -> String {
+    word.chars().map(fold_confusable).collect()
+}
@@ -0,0 +1,194 @@
+// what changed between two versions of a dictionary, for reviewing a bump
+// before it's baked in
+
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::{self, Write},
+    string::String,
+    vec::Vec,
+};
+
+/// the words in only the old list, only the new one and both, each sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DictDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub common: Vec<String>,
+}
+
+impl DictDiff {
+    /// whether both lists hold the same words
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// a summary line followed by every added word as `+ word` and every
+    /// removed one as `- word`
+    pub fn write_report(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{} added, {} removed, {} unchanged",
+            self.added.len(),
+            self.removed.len(),
+            self.common.len()
+        )?;
+        for word in &self.added {
+            writeln!(out, "+ {word}")?;
+        }
+        for word in &self.removed {
+            writeln!(out, "- {word}")?;
+        }
+        Ok(())
+    }
+}
+
+/// the words added and removed going from `old` to `new`. order and
+/// duplicates don't matter
+pub fn diff_word_lists<'a>(
+    old: impl IntoIterator<Item = &'a str>,
+    new: impl IntoIterator<Item = &'a str>,
+) -> DictDiff {
+    diff_word_lists_by(old, new, |word| word.into())
+}
+
+/// [`diff_word_lists`] comparing the form `normalize` gives each word, the
+/// one the dictionary is built from, so `str::to_lowercase` diffs a
+/// case folded dictionary on its folded words
+pub fn diff_word_lists_by<'a>(
+    old: impl IntoIterator<Item = &'a str>,
+    new: impl IntoIterator<Item = &'a str>,
+    normalize: impl Fn(&str) -> String,
+) -> DictDiff {
+    let old: BTreeSet<_> = old.into_iter().map(&normalize).collect();
+    let new: BTreeSet<_> = new.into_iter().map(&normalize).collect();
+    DictDiff {
+        added: new.difference(&old).cloned().collect(),
+        removed: old.difference(&new).cloned().collect(),
+        common: old.intersection(&new).cloned().collect(),
+    }
+}
+
+/// [`diff_word_lists`] of two generated trees' source, see
+/// [`generated_words`]. a json export is a `RuntimeBkTree` once
+/// deserialized, diff its `words()`
+pub fn diff_generated(old: &str, new: &str) -> Result<DictDiff, SourceError> {
+    let (old, new) = (generated_words(old)?, generated_words(new)?);
+    Ok(diff_word_lists(
+        old.iter().map(String::as_str),
+        new.iter().map(String::as_str),
+    ))
+}
+
+/// why [`generated_words`] couldn't read a generated tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SourceError {
+    /// no nodes anywhere, it isn't a generated tree or it's a progmem blob
+    NoNodes,
+    /// the word of the node at this byte offset didn't parse
+    BadWord { offset: usize },
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoNodes => write!(f, "no generated nodes in the source"),
+            Self::BadWord { offset } => write!(f, "can't parse the word at byte {offset}"),
+        }
+    }
+}
+
+std_error!(SourceError);
+
+/// the words of a tree [`BkTreeBuilder`] generated, in the order their
+/// nodes appear. that's the root first and every parent before its
+/// children, so inserting them in order rebuilds the same shape
+///
+/// [`BkTreeBuilder`]: crate::BkTreeBuilder
+pub fn generated_words(source: &str) -> Result<Vec<String>, SourceError> {
+    const NODE: &str = "Node::new(";
+    // with `dfs_order` the words are slices of one byte string
+    let interned = match source.find("_WORDS: [u8; ") {
+        Some(at) => {
+            let start = at
+                + source[at..]
+                    .find("*b")
+                    .ok_or(SourceError::BadWord { offset: at })?;
+            Some(byte_string(&source[start + 2..]).ok_or(SourceError::BadWord { offset: at })?)
+        }
+        None => None,
+    };
+    let mut words = Vec::new();
+    let mut offset = 0;
+    while let Some(at) = source[offset..].find(NODE) {
+        offset += at + NODE.len();
+        let bad = SourceError::BadWord { offset };
+        let rest = &source[offset..];
+        let word = match (rest.strip_prefix("Node::interned("), &interned) {
+            (Some(rest), Some(bytes)) => {
+                let mut args = rest
+                    .split([',', ')'])
+                    .skip(1)
+                    .map(|a| a.trim().parse::<usize>());
+                let (Some(Ok(start)), Some(Ok(len))) = (args.next(), args.next()) else {
+                    return Err(bad);
+                };
+                let word = bytes.get(start..start + len).ok_or(bad)?;
+                String::from_utf8(word.to_vec()).map_err(|_| bad)?
+            }
+            _ => string_literal(rest).ok_or(bad)?,
+        };
+        words.push(word);
+    }
+    match words.is_empty() {
+        true => Err(SourceError::NoNodes),
+        false => Ok(words),
+    }
+}
+
+// the `{:?}` formatted str at the start of `source`
+fn string_literal(source: &str) -> Option<String> {
+    let mut chars = source.strip_prefix('"')?.chars();
+    let mut word = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(word),
+            '\\' => word.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'u' => {
+                    let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                escaped => escaped,
+            }),
+            c => word.push(c),
+        }
+    }
+}
+
+// the `ascii::escape_default` escaped byte string at the start of `source`
+fn byte_string(source: &str) -> Option<Vec<u8>> {
+    let mut chars = source.strip_prefix('"')?.chars();
+    let mut bytes = Vec::new();
+    loop {
+        let byte = match chars.next()? {
+            '"' => return Some(bytes),
+            '\\' => match chars.next()? {
+                'n' => b'\n',
+                'r' => b'\r',
+                't' => b'\t',
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    u8::from_str_radix(&hex, 16).ok()?
+                }
+                escaped => escaped as u8,
+            },
+            c => c as u8,
+        };
+        bytes.push(byte);
+    }
+}
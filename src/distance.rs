@@ -0,0 +1,624 @@
+// the edit distance shared by the writer and the reader, both sides have to
+// agree on it or the bucket a word was stored under means nothing
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+// dp row kept on the stack, anything longer needs alloc
+const STACK_ROW: usize = 64;
+
+/// edit distance over chars
+#[cfg_attr(feature = "grapheme", allow(unused))]
+#[cfg(not(feature = "small-code"))]
+pub(crate) fn char_distance(a: &str, b: &str) -> usize {
+    if a == b {
+        return 0;
+    }
+    levenshtein_by(a.chars(), b.chars())
+}
+
+/// [`char_distance`] in one loop over chars for `small-code`, the row
+/// along `b` and along `a` when `b` doesn't fit on the stack. it's
+/// neither generic nor inlined, every search shares the one copy
+#[cfg_attr(feature = "grapheme", allow(unused))]
+#[cfg(feature = "small-code")]
+#[inline(never)]
+pub(crate) fn char_distance(a: &str, b: &str) -> usize {
+    if a == b {
+        return 0;
+    }
+    let mut row = [0; STACK_ROW];
+    if let Some(distance) = row_distance(a, b, &mut row).or_else(|| row_distance(b, a, &mut row)) {
+        return distance;
+    }
+    // the byte length is at least the chars
+    #[cfg(feature = "alloc")]
+    return row_distance(a, b, &mut vec![0; b.len() + 1]).unwrap();
+    #[cfg(not(feature = "alloc"))]
+    {
+        let count = |s: &str| s.chars().fold(0, |len, _| len + 1);
+        let (a_len, b_len) = (count(a), count(b));
+        match a_len < b_len {
+            true => banded(b.chars(), a.chars(), b_len, a_len),
+            false => banded(a.chars(), b.chars(), a_len, b_len),
+        }
+    }
+}
+
+// levenshtein with `b` along `row`, none when it doesn't fit
+#[cfg(feature = "small-code")]
+fn row_distance(a: &str, b: &str, row: &mut [usize]) -> Option<usize> {
+    let mut len = 0;
+    row[0] = 0;
+    for _ in b.chars() {
+        len += 1;
+        *row.get_mut(len)? = len;
+    }
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.chars().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = match x == y {
+                true => diagonal,
+                false => 1 + diagonal.min(above).min(row[j]),
+            };
+            diagonal = above;
+        }
+    }
+    Some(row[len])
+}
+
+/// edit distance over extended grapheme clusters, so "e\u{301}" or a flag
+/// emoji counts as a single character
+#[cfg(feature = "grapheme")]
+pub(crate) fn grapheme_distance(a: &str, b: &str) -> usize {
+    use unicode_segmentation::UnicodeSegmentation;
+    if a == b {
+        return 0;
+    }
+    levenshtein_by(a.graphemes(true), b.graphemes(true))
+}
+
+/// the units `distance` counts edits of, each char or grapheme cluster
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub(crate) fn units(s: &str) -> alloc::vec::Vec<&str> {
+    #[cfg(feature = "grapheme")]
+    return unicode_segmentation::UnicodeSegmentation::graphemes(s, true).collect();
+    #[cfg(not(feature = "grapheme"))]
+    return s
+        .char_indices()
+        .map(|(i, c)| &s[i..i + c.len_utf8()])
+        .collect();
+}
+
+// length in the units `distance` counts, chars or grapheme clusters
+#[cfg(any(feature = "read", feature = "write"))]
+pub(crate) fn length(s: &str) -> usize {
+    #[cfg(feature = "grapheme")]
+    return unicode_segmentation::UnicodeSegmentation::graphemes(s, true).count();
+    // a plain loop, `count` is fast but takes a few hundred bytes
+    #[cfg(all(not(feature = "grapheme"), feature = "small-code"))]
+    return s.chars().fold(0, |len, _| len + 1);
+    #[cfg(all(not(feature = "grapheme"), not(feature = "small-code")))]
+    return s.chars().count();
+}
+
+/// the edit distance the tree is built and searched with, levenshtein over
+/// chars or over grapheme clusters with the `grapheme` feature.
+///
+/// use this rather than another levenshtein implementation when showing or
+/// checking distances so they always agree with the search.
+///
+/// without the `alloc` feature the working row lives on the stack, so when
+/// both strings are longer than 63 characters only the edits that stay
+/// within 31 of the diagonal are tried. distances up to 31 are exact and
+/// anything further is reported as the longer string's length, which no
+/// pair is further apart than. the tree's buckets only go to 14, so a
+/// search with a tolerance up to 17 finds the same words either way
+pub fn distance(a: &str, b: &str) -> usize {
+    #[cfg(feature = "grapheme")]
+    return grapheme_distance(a, b);
+    #[cfg(not(feature = "grapheme"))]
+    return char_distance(a, b);
+}
+
+/// [`char_distance`] from a query of chars that may not be in a str
+#[cfg(all(feature = "read", not(feature = "grapheme")))]
+pub(crate) fn chars_distance(a: impl Iterator<Item = char> + Clone, b: &str) -> usize {
+    levenshtein_by(a, b.chars())
+}
+
+/// which of 32 buckets of characters `word` has, the letters case
+/// insensitively, the digits, `'` and `-`, the rest of ascii and three
+/// buckets for everything else. never 0 for a word with any characters
+pub(crate) fn char_mask(word: &str) -> u32 {
+    word.chars().fold(0, |mask, c| {
+        let bucket = match c {
+            'a'..='z' => c as u32 - 'a' as u32,
+            'A'..='Z' => c as u32 - 'A' as u32,
+            '0'..='9' => 26,
+            '\'' | '-' => 27,
+            _ if c.is_ascii() => 28,
+            _ => 29 + c as u32 % 3,
+        };
+        mask | 1 << bucket
+    })
+}
+
+/// the fewest edits that can turn a word with chars `a` into one with
+/// chars `b`. each edit adds at most one bucket and takes away at most one,
+/// so `popcount(a ^ b) <= 2 * distance` for any mapping of chars onto
+/// buckets and the distance is at least half the differing bits, rounded
+/// up. it isn't true of grapheme distance, a cluster holds several chars
+#[cfg(feature = "read")]
+pub(crate) const fn mask_bound(a: u32, b: u32) -> usize {
+    (a ^ b).count_ones().div_ceil(2) as usize
+}
+
+// a query unit that may stand for more than one character
+#[cfg(feature = "read")]
+#[derive(Clone, Copy)]
+enum Unit<'s, T> {
+    Any,
+    OneOf(&'s [char]),
+    Is(T),
+}
+
+// the one char a unit of a word is, if it is exactly one
+#[cfg(feature = "read")]
+trait OneChar {
+    fn one_char(&self) -> Option<char>;
+}
+
+#[cfg(feature = "read")]
+impl OneChar for char {
+    fn one_char(&self) -> Option<char> {
+        Some(*self)
+    }
+}
+
+#[cfg(feature = "read")]
+impl OneChar for &str {
+    fn one_char(&self) -> Option<char> {
+        let mut chars = self.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    }
+}
+
+#[cfg(feature = "read")]
+impl<T: PartialEq + OneChar> PartialEq for Unit<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Unit::Is(a), Unit::Is(b)) => a == b,
+            (Unit::OneOf(set), Unit::Is(x)) | (Unit::Is(x), Unit::OneOf(set)) => {
+                x.one_char().is_some_and(|c| set.contains(&c))
+            }
+            (Unit::OneOf(a), Unit::OneOf(b)) => a.iter().any(|c| b.contains(c)),
+            _ => true,
+        }
+    }
+}
+
+/// [`distance`] where each position of `query` is a set of characters and
+/// substituting any one of them is free
+#[cfg(feature = "read")]
+pub(crate) fn ambiguous_distance(query: &[&[char]], word: &str) -> usize {
+    let query = query.iter().map(|set| Unit::OneOf(set));
+    #[cfg(feature = "grapheme")]
+    return levenshtein_by(
+        query,
+        unicode_segmentation::UnicodeSegmentation::graphemes(word, true).map(Unit::Is),
+    );
+    #[cfg(not(feature = "grapheme"))]
+    return levenshtein_by(query, word.chars().map(Unit::Is));
+}
+
+/// [`distance`] where `wildcard` in `pattern` substitutes for any one
+/// character of `word` for free
+#[cfg(feature = "read")]
+pub(crate) fn wildcard_distance(pattern: &str, word: &str, wildcard: char) -> usize {
+    #[cfg(feature = "grapheme")]
+    {
+        use unicode_segmentation::UnicodeSegmentation;
+        let wild = wildcard.encode_utf8(&mut [0; 4]).len();
+        let is_wild = |g: &str| g.len() == wild && g.starts_with(wildcard);
+        levenshtein_by(
+            pattern
+                .graphemes(true)
+                .map(|g| if is_wild(g) { Unit::Any } else { Unit::Is(g) }),
+            word.graphemes(true).map(Unit::Is),
+        )
+    }
+    #[cfg(not(feature = "grapheme"))]
+    levenshtein_by(
+        pattern.chars().map(|c| {
+            if c == wildcard {
+                Unit::Any
+            } else {
+                Unit::Is(c)
+            }
+        }),
+        word.chars().map(Unit::Is),
+    )
+}
+
+/// [`distance`] from `query` to the closest stretch of `word`, the
+/// characters of `word` before and after it cost nothing. "volts" is 0
+/// from "millivolts"
+#[cfg(feature = "read")]
+pub(crate) fn partial_distance(query: &str, word: &str) -> usize {
+    #[cfg(feature = "grapheme")]
+    {
+        use unicode_segmentation::UnicodeSegmentation;
+        substring_by(query.graphemes(true), word.graphemes(true))
+    }
+    #[cfg(not(feature = "grapheme"))]
+    substring_by(query.chars(), word.chars())
+}
+
+/// the units `distance` counts of `bytes` read as utf-8, each invalid
+/// sequence one U+FFFD like `String::from_utf8_lossy` has it. it decodes a
+/// valid run at a time as it goes, nothing is copied out
+#[cfg(feature = "read")]
+#[derive(Debug, Clone)]
+pub(crate) struct LossyUnits<'a> {
+    // the run being handed out, then whether a bad sequence ended it
+    valid: &'a str,
+    replaced: bool,
+    rest: &'a [u8],
+}
+
+#[cfg(feature = "read")]
+impl<'a> LossyUnits<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            valid: "",
+            replaced: false,
+            rest: bytes,
+        }
+    }
+}
+
+#[cfg(feature = "read")]
+impl<'a> Iterator for LossyUnits<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        #[cfg(feature = "grapheme")]
+        let unit = unicode_segmentation::UnicodeSegmentation::graphemes(self.valid, true).next();
+        #[cfg(not(feature = "grapheme"))]
+        let unit = self
+            .valid
+            .chars()
+            .next()
+            .map(|c| &self.valid[..c.len_utf8()]);
+        if let Some(unit) = unit {
+            self.valid = &self.valid[unit.len()..];
+            return Some(unit);
+        }
+        if self.replaced {
+            self.replaced = false;
+            return Some("\u{fffd}");
+        }
+        if self.rest.is_empty() {
+            return None;
+        }
+        (self.valid, self.rest) = match core::str::from_utf8(self.rest) {
+            Ok(valid) => (valid, &[][..]),
+            Err(e) => {
+                let (valid, bad) = self.rest.split_at(e.valid_up_to());
+                self.replaced = true;
+                // a sequence cut off by the end of the input is one too
+                let skip = e.error_len().unwrap_or(bad.len());
+                (
+                    core::str::from_utf8(valid).unwrap_or_default(),
+                    &bad[skip..],
+                )
+            }
+        };
+        self.next()
+    }
+}
+
+/// [`distance`] from `query` decoded by [`LossyUnits`]
+#[cfg(feature = "read")]
+pub(crate) fn lossy_distance(query: &[u8], word: &str) -> usize {
+    #[cfg(feature = "grapheme")]
+    let word = unicode_segmentation::UnicodeSegmentation::graphemes(word, true);
+    #[cfg(not(feature = "grapheme"))]
+    let word = word.char_indices().map(|(i, c)| &word[i..i + c.len_utf8()]);
+    levenshtein_by(LossyUnits::new(query), word)
+}
+
+// `levenshtein_by` with the row along `word` starting at 0, so any prefix
+// of it is skipped for free, and the best of the last row taken, so any
+// suffix is too. a word too long for the stack row is gone through with a
+// column along the query instead, only a query that doesn't fit either
+// needs alloc
+#[cfg(feature = "read")]
+fn substring_by<T: PartialEq>(
+    query: impl Iterator<Item = T> + Clone,
+    word: impl Iterator<Item = T> + Clone,
+) -> usize {
+    let len = word.clone().count();
+    if len < STACK_ROW {
+        return substring_in(query, word, &mut [0; STACK_ROW][..=len]);
+    }
+    let query_len = query.clone().count();
+    if query_len < STACK_ROW {
+        return substring_across(query, word, &mut [0; STACK_ROW][..=query_len]);
+    }
+    #[cfg(feature = "alloc")]
+    return substring_in(query, word, &mut vec![0; len + 1]);
+    // every unit of the query substituted or deleted, the most it can be
+    #[cfg(not(feature = "alloc"))]
+    return query_len;
+}
+
+// `substring_in` a word position at a time, `column` along the query. the
+// top is 0 at every position so the word can start anywhere, and the best
+// bottom of any position is taken so it can end anywhere
+#[cfg(feature = "read")]
+fn substring_across<T: PartialEq>(
+    query: impl Iterator<Item = T> + Clone,
+    word: impl Iterator<Item = T>,
+    column: &mut [usize],
+) -> usize {
+    for (i, cell) in column.iter_mut().enumerate() {
+        *cell = i;
+    }
+    let last = column.len() - 1;
+    let mut best = column[last];
+    for y in word {
+        let mut diagonal = column[0];
+        for (i, x) in query.clone().enumerate() {
+            let left = column[i + 1];
+            column[i + 1] = if x == y {
+                diagonal
+            } else {
+                1 + diagonal.min(left).min(column[i])
+            };
+            diagonal = left;
+        }
+        best = best.min(column[last]);
+    }
+    best
+}
+
+#[cfg(feature = "read")]
+fn substring_in<T: PartialEq>(
+    query: impl Iterator<Item = T>,
+    word: impl Iterator<Item = T> + Clone,
+    row: &mut [usize],
+) -> usize {
+    for (i, x) in query.enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in word.clone().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row.iter().copied().min().unwrap_or(0)
+}
+
+// single row levenshtein, the row runs along the shorter input
+fn levenshtein_by<T, A, B>(a: A, b: B) -> usize
+where
+    T: PartialEq,
+    A: Iterator<Item = T> + Clone,
+    B: Iterator<Item = T> + Clone,
+{
+    let (a_len, b_len) = (a.clone().count(), b.clone().count());
+    if a_len < b_len {
+        return levenshtein_by(b, a);
+    }
+    if b_len < STACK_ROW {
+        return levenshtein_in(a, b, &mut [0; STACK_ROW][..=b_len]);
+    }
+    #[cfg(feature = "alloc")]
+    return levenshtein_in(a, b, &mut vec![0; b_len + 1]);
+    #[cfg(not(feature = "alloc"))]
+    return banded(a, b, a_len, b_len);
+}
+
+// `levenshtein_in` over the cells at most `BAND` from the diagonal, which
+// fit the stack row. exact up to `BAND`, past it the most it can be, `a_len`
+#[cfg(not(feature = "alloc"))]
+fn banded<T: PartialEq>(
+    a: impl Iterator<Item = T>,
+    b: impl Iterator<Item = T> + Clone,
+    a_len: usize,
+    b_len: usize,
+) -> usize {
+    const BAND: usize = (STACK_ROW - 1) / 2;
+    // the length difference alone is more than the band
+    if a_len - b_len > BAND {
+        return a_len;
+    }
+    // band[o] is column `i + o - BAND` of row `i`, anything past the band
+    // or the strings is `BAND + 1`
+    let far = BAND + 1;
+    let mut band = [far; 2 * BAND + 1];
+    for (o, cell) in band.iter_mut().enumerate().skip(BAND) {
+        if o - BAND <= b_len {
+            *cell = o - BAND;
+        }
+    }
+    // `b` from the first column row `i`'s band reaches
+    let mut from = b;
+    for (i, x) in a.enumerate() {
+        if i > BAND {
+            from.next();
+        }
+        let mut ys = from.clone();
+        let mut left = far;
+        for o in 0..band.len() {
+            // column `i + 1 + o - BAND` of row `i + 1`
+            let cell = match (i + 1 + o).checked_sub(BAND) {
+                None => far,
+                Some(0) => i + 1,
+                Some(_) => match ys.next() {
+                    None => far,
+                    Some(y) if y == x => band[o],
+                    Some(_) => {
+                        let above = band.get(o + 1).copied().unwrap_or(far);
+                        1 + band[o].min(above).min(left)
+                    }
+                },
+            };
+            band[o] = cell.min(far);
+            left = band[o];
+        }
+    }
+    match band.get(b_len + BAND - a_len) {
+        Some(&distance) if distance <= BAND => distance,
+        _ => a_len,
+    }
+}
+
+fn levenshtein_in<T: PartialEq>(
+    a: impl Iterator<Item = T>,
+    b: impl Iterator<Item = T> + Clone,
+    row: &mut [usize],
+) -> usize {
+    for (j, cell) in row.iter_mut().enumerate() {
+        *cell = j;
+    }
+    for (i, x) in a.enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.clone().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[row.len() - 1]
+}
+
+/// longest word, in chars, [`levenshtein_const`] can take as the shorter of
+/// its two inputs
+#[cfg(feature = "read")]
+pub const MAX_CONST_LEN: usize = 32;
+
+/// levenshtein over chars usable in const items, e.g. for tables built at
+/// compile time.
+///
+/// the dp row is a fixed `[usize; MAX_CONST_LEN + 1]`, so the shorter of the
+/// two strings can be at most [`MAX_CONST_LEN`] chars. past that it panics,
+/// which in a const item is a compile error. this is always the char
+/// distance, even with the `grapheme` feature
+#[cfg(feature = "read")]
+pub const fn levenshtein_const(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (long, short) = match char_count(a) < char_count(b) {
+        true => (b, a),
+        false => (a, b),
+    };
+    let mut chars = [0; MAX_CONST_LEN];
+    let mut len = 0;
+    let mut i = 0;
+    while i < short.len() {
+        assert!(len < MAX_CONST_LEN, "word too long for levenshtein_const");
+        let (c, width) = char_at(short, i);
+        chars[len] = c;
+        len += 1;
+        i += width;
+    }
+
+    let mut row = [0; MAX_CONST_LEN + 1];
+    let mut j = 0;
+    while j <= len {
+        row[j] = j;
+        j += 1;
+    }
+    let (mut i, mut n) = (0, 0);
+    while i < long.len() {
+        let (x, width) = char_at(long, i);
+        let mut diagonal = row[0];
+        n += 1;
+        row[0] = n;
+        let mut j = 0;
+        while j < len {
+            let above = row[j + 1];
+            row[j + 1] = if x == chars[j] {
+                diagonal
+            } else {
+                1 + min(diagonal, min(above, row[j]))
+            };
+            diagonal = above;
+            j += 1;
+        }
+        i += width;
+    }
+    row[len]
+}
+
+#[cfg(feature = "read")]
+const fn min(a: usize, b: usize) -> usize {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(feature = "read")]
+const fn char_count(s: &[u8]) -> usize {
+    let (mut i, mut count) = (0, 0);
+    while i < s.len() {
+        // every byte but a continuation byte starts a char
+        if s[i] & 0xc0 != 0x80 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+// the code point starting at byte `i` of valid utf-8 and its width
+#[cfg(feature = "read")]
+const fn char_at(s: &[u8], i: usize) -> (u32, usize) {
+    let (width, mut c) = match s[i] {
+        b @ 0..=0x7f => return (b as u32, 1),
+        b @ 0xc0..=0xdf => (2, (b & 0x1f) as u32),
+        b @ 0xe0..=0xef => (3, (b & 0x0f) as u32),
+        b => (4, (b & 0x07) as u32),
+    };
+    let mut k = 1;
+    while k < width {
+        c = (c << 6) | (s[i + k] & 0x3f) as u32;
+        k += 1;
+    }
+    (c, width)
+}
+
+// `==` on strs isn't const yet
+#[cfg(feature = "read")]
+pub(crate) const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
@@ -0,0 +1,99 @@
+// alignment between a query and a suggestion, for highlighting what changed
+
+extern crate alloc;
+use super::{distance::units, Node, Suggestion};
+use alloc::{vec, vec::Vec};
+
+/// one step turning the query into the suggestion. the strings are single
+/// characters, or grapheme clusters with the `grapheme` feature, borrowed
+/// from the inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op<'a> {
+    /// the next `n` characters are the same in both
+    Keep(usize),
+    /// a character only in the suggestion
+    Insert(&'a str),
+    /// a character only in the query
+    Delete(&'a str),
+    /// query character replaced by the suggestion's
+    Substitute(&'a str, &'a str),
+}
+
+/// the ops from [`edit_script`], one edit op per unit of distance
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditScript<'a> {
+    pub ops: Vec<Op<'a>>,
+}
+
+impl EditScript<'_> {
+    /// number of non `Keep` ops, always the distance between the inputs
+    pub fn edits(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| !matches!(op, Op::Keep(_)))
+            .count()
+    }
+}
+
+/// a minimal sequence of edits turning `a` into `b`, units are the same
+/// ones [`distance`](crate::distance) counts. on equal cost a substitution
+/// is preferred over a delete, and a delete over an insert
+pub fn edit_script<'a>(a: &'a str, b: &'a str) -> EditScript<'a> {
+    let (a, b) = (units(a), units(b));
+    let width = b.len() + 1;
+    // full matrix, the traceback needs every cell
+    let mut cost = vec![0usize; (a.len() + 1) * width];
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            cost[i * width + j] = match (i, j) {
+                (0, j) => j,
+                (i, 0) => i,
+                (i, j) => {
+                    let diagonal = cost[(i - 1) * width + j - 1] + (a[i - 1] != b[j - 1]) as usize;
+                    let delete = cost[(i - 1) * width + j] + 1;
+                    let insert = cost[i * width + j - 1] + 1;
+                    diagonal.min(delete).min(insert)
+                }
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 || j > 0 {
+        let here = cost[i * width + j];
+        if i > 0 && j > 0 && a[i - 1] == b[j - 1] && here == cost[(i - 1) * width + j - 1] {
+            match ops.last_mut() {
+                Some(Op::Keep(n)) => *n += 1,
+                _ => ops.push(Op::Keep(1)),
+            }
+            (i, j) = (i - 1, j - 1);
+        } else if i > 0 && j > 0 && here == cost[(i - 1) * width + j - 1] + 1 {
+            ops.push(Op::Substitute(a[i - 1], b[j - 1]));
+            (i, j) = (i - 1, j - 1);
+        } else if i > 0 && here == cost[(i - 1) * width + j] + 1 {
+            ops.push(Op::Delete(a[i - 1]));
+            i -= 1;
+        } else {
+            ops.push(Op::Insert(b[j - 1]));
+            j -= 1;
+        }
+    }
+    ops.reverse();
+    EditScript { ops }
+}
+
+impl Node {
+    /// [`Node::corrections`] with the edit script from `word` to each
+    /// suggestion
+    pub fn corrections_with_edits<'a>(
+        &'static self,
+        word: &'a str,
+        tolerance: u8,
+    ) -> Vec<(Suggestion<'static>, EditScript<'a>)> {
+        self.corrections(word, tolerance)
+            .into_iter()
+            .map(|s| (s, edit_script(word, s.word)))
+            .collect()
+    }
+}
@@ -0,0 +1,115 @@
+// the search for c callers. `Node` stays opaque on the c side, the
+// generated `TREE` is handed over as `&TREE`.
+//
+// for cbindgen the header is
+//
+//     typedef struct Node Node;
+//     typedef struct BkSuggestion {
+//         const char *word;
+//         uintptr_t len;
+//         uint8_t distance;
+//     } BkSuggestion;
+//     bool bktree_contains(const Node *root, const char *word);
+//     uintptr_t bktree_corrections(const Node *root, const char *word,
+//                                  uint8_t tolerance, BkSuggestion *out,
+//                                  uintptr_t cap);
+
+use super::Node;
+use core::{
+    ffi::{c_char, CStr},
+    mem::MaybeUninit,
+    ops::ControlFlow,
+    ptr, slice,
+};
+
+/// a correction as c sees it. `word` points into the tree and is not nul
+/// terminated, it's `len` bytes of utf-8
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BkSuggestion {
+    pub word: *const c_char,
+    pub len: usize,
+    pub distance: u8,
+}
+
+impl Default for BkSuggestion {
+    fn default() -> Self {
+        Self {
+            word: ptr::null(),
+            len: 0,
+            distance: 0,
+        }
+    }
+}
+
+// null or not utf-8 is no word
+unsafe fn arguments<'a>(root: *const Node, word: *const c_char) -> Option<(&'a Node, &'a str)> {
+    if root.is_null() || word.is_null() {
+        return None;
+    }
+    let word = unsafe { CStr::from_ptr(word) }.to_str().ok()?;
+    Some((unsafe { &*root }, word))
+}
+
+/// whether `word` is in the tree, false for a null or non utf-8 argument
+///
+/// # Safety
+///
+/// `root` is null or points to a generated tree, `word` is null or a nul
+/// terminated string
+#[no_mangle]
+pub unsafe extern "C" fn bktree_contains(root: *const Node, word: *const c_char) -> bool {
+    match unsafe { arguments(root, word) } {
+        Some((root, word)) => root.contains(word),
+        None => false,
+    }
+}
+
+/// fills `out` with at most `cap` of the closest words within `tolerance`
+/// of `word` sorted by distance, returning how many were written, the same
+/// as truncating `Node::corrections`. 0 for a null or non utf-8 argument
+///
+/// # Safety
+///
+/// as [`bktree_contains`], and `out` is null or has room for `cap`
+/// suggestions
+#[no_mangle]
+pub unsafe extern "C" fn bktree_corrections(
+    root: *const Node,
+    word: *const c_char,
+    tolerance: u8,
+    out: *mut BkSuggestion,
+    cap: usize,
+) -> usize {
+    let Some((root, word)) = (unsafe { arguments(root, word) }) else {
+        return 0;
+    };
+    if out.is_null() || cap == 0 {
+        return 0;
+    }
+    // c hands over uninitialised arrays, only `out[..filled]` is read
+    let out = unsafe { slice::from_raw_parts_mut(out.cast::<MaybeUninit<BkSuggestion>>(), cap) };
+    let mut filled = 0;
+    root.for_each_correction(word, tolerance, |s| {
+        // after every equal distance, dropping the last when full
+        if filled == cap {
+            if s.distance >= unsafe { out[cap - 1].assume_init_ref() }.distance {
+                return ControlFlow::Continue(());
+            }
+            filled -= 1;
+        }
+        let at = out[..filled]
+            .iter()
+            .position(|o| unsafe { o.assume_init_ref() }.distance > s.distance)
+            .unwrap_or(filled);
+        out[at..=filled].rotate_right(1);
+        out[at].write(BkSuggestion {
+            word: s.word.as_ptr().cast(),
+            len: s.word.len(),
+            distance: s.distance,
+        });
+        filled += 1;
+        ControlFlow::Continue(())
+    });
+    filled
+}
@@ -0,0 +1,158 @@
+// hamming distance, the number of positions two words of the same length
+// differ at, for fixed length codes like serial numbers where a char gets
+// misread but never dropped or added. it's cheaper than levenshtein and a
+// tree built with it has to be searched with it too, so the generated
+// `TREE` is a `HammingTree` instead of a bare node
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(all(feature = "read", feature = "alloc"))]
+use super::read::sort_by_distance;
+#[cfg(feature = "read")]
+use super::{read::logged, Node, SearchStats, Suggestion};
+#[cfg(all(feature = "read", feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(feature = "read")]
+use core::{fmt, ops::ControlFlow};
+
+/// the chars `a` and `b` differ at, `None` when they aren't the same
+/// length in chars
+pub fn hamming(a: &str, b: &str) -> Option<usize> {
+    let (mut a, mut b) = (a.chars(), b.chars());
+    let mut differ = 0;
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) => differ += (x != y) as usize,
+            (None, None) => return Some(differ),
+            _ => return None,
+        }
+    }
+}
+
+/// a query that's not the length of the codes in the tree
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeLength {
+    pub expected: usize,
+    pub found: usize,
+}
+
+#[cfg(feature = "read")]
+impl fmt::Display for CodeLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the codes are {} chars, not {}",
+            self.expected, self.found
+        )
+    }
+}
+
+#[cfg(feature = "read")]
+std_error!(CodeLength);
+
+/// the generated `TREE` of `BkTreeBuilder::hamming`, a tree of same length
+/// codes searched by hamming distance. the root isn't handed out so it
+/// can't be searched by levenshtein by mistake
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, Copy)]
+pub struct HammingTree {
+    root: &'static Node,
+    word_count: usize,
+    checksum: u32,
+}
+
+#[cfg(feature = "read")]
+impl HammingTree {
+    pub const fn new(root: &'static Node, word_count: usize, checksum: u32) -> Self {
+        Self {
+            root,
+            word_count,
+            checksum,
+        }
+    }
+
+    pub const fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    pub const fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    /// the length in chars of every code
+    pub fn code_len(&self) -> usize {
+        self.root.word().chars().count()
+    }
+
+    /// calls `f` with every code within `tolerance` substitutions of
+    /// `word`, in traversal order until it breaks
+    pub fn for_each_correction(
+        &self,
+        word: &str,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) -> Result<(), CodeLength> {
+        let (expected, found) = (self.code_len(), word.chars().count());
+        if expected != found {
+            return Err(CodeLength { expected, found });
+        }
+        let tolerance = tolerance as usize;
+        // the lengths match all the way down, the writer made sure
+        let measure = |w: &str| hamming(w, word).unwrap_or(usize::MAX);
+        let _ = self.root.walk_by(
+            logged(&word),
+            &measure,
+            tolerance,
+            0,
+            &mut SearchStats::default(),
+            &mut |node, distance| {
+                if distance <= tolerance {
+                    f(Suggestion::new(word, node.word(), distance as u8))?;
+                }
+                ControlFlow::Continue(())
+            },
+        );
+        Ok(())
+    }
+
+    /// every code within `tolerance` of `word`, sorted by distance
+    /// (ties keep traversal order)
+    #[cfg(feature = "alloc")]
+    pub fn corrections(
+        &self,
+        word: &str,
+        tolerance: u8,
+    ) -> Result<Vec<Suggestion<'static>>, CodeLength> {
+        let mut corrections = Vec::new();
+        self.for_each_correction(word, tolerance, |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        })?;
+        sort_by_distance(&mut corrections);
+        Ok(corrections)
+    }
+
+    /// the closest code within `tolerance`, the first found on a tie
+    pub fn best_match(
+        &self,
+        word: &str,
+        tolerance: u8,
+    ) -> Result<Option<Suggestion<'static>>, CodeLength> {
+        let mut best: Option<Suggestion<'static>> = None;
+        self.for_each_correction(word, tolerance, |s| {
+            if best.map_or(true, |b| s.distance < b.distance) {
+                best = Some(s);
+            }
+            match s.distance {
+                0 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        })?;
+        Ok(best)
+    }
+
+    pub fn contains(&self, word: &str) -> Result<bool, CodeLength> {
+        Ok(self.best_match(word, 0)?.is_some())
+    }
+}
@@ -0,0 +1,36 @@
+// small hashes shared by the writer and the reader, they end up baked into
+// generated trees so they must never change
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+pub(crate) const fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash = FNV_OFFSET;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
+
+/// checksum of a word set as emitted in the generated `CHECKSUM`, the
+/// wrapping sum of each word's FNV-1a hash so the tree layout (and the
+/// order words are visited in) doesn't matter
+pub fn checksum<'a>(words: impl IntoIterator<Item = &'a str>) -> u32 {
+    words
+        .into_iter()
+        .fold(0, |sum, word| sum.wrapping_add(fnv1a(word.as_bytes())))
+}
+
+/// the murmur3 finalizer of `hash`, a second hash out of the first for
+/// double hashing. it's odd so stepping by it never stays put
+pub(crate) const fn mix(mut hash: u32) -> u32 {
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash | 1
+}
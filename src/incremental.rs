@@ -0,0 +1,197 @@
+// a search that follows a query as it's typed. every node it measured keeps
+// its dp rows, one per character of the query, so a character typed
+// costs one more row for the nodes visited again and a backspace drops one
+
+extern crate alloc;
+use super::{read::sort_by_distance, Node, SearchStats, Suggestion};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+// the units `distance` counts edits of, each char or grapheme cluster
+fn units(s: &str) -> impl Iterator<Item = &str> {
+    #[cfg(feature = "grapheme")]
+    return unicode_segmentation::UnicodeSegmentation::graphemes(s, true);
+    #[cfg(not(feature = "grapheme"))]
+    return s.char_indices().map(|(i, c)| &s[i..i + c.len_utf8()]);
+}
+
+// the dp of a node's word against a prefix of the query, a row of
+// `columns` for the empty prefix and one for each unit after it
+#[derive(Debug, Clone)]
+struct Rows {
+    columns: usize,
+    cells: Vec<usize>,
+}
+
+impl Rows {
+    fn new(word: &str) -> Self {
+        let columns = units(word).count() + 1;
+        Self {
+            columns,
+            cells: (0..columns).collect(),
+        }
+    }
+
+    // how many units of the query the rows are for
+    fn prefix(&self) -> usize {
+        self.cells.len() / self.columns - 1
+    }
+
+    fn truncate(&mut self, prefix: usize) {
+        self.cells.truncate((prefix + 1) * self.columns);
+    }
+
+    fn push(&mut self, word: &str, unit: &str) {
+        let start = self.cells.len() - self.columns;
+        self.cells.push(self.prefix() + 1);
+        for (j, w) in units(word).enumerate() {
+            let (above, diagonal) = (self.cells[start + j + 1], self.cells[start + j]);
+            let left = *self.cells.last().unwrap();
+            let cell = (above + 1)
+                .min(left + 1)
+                .min(diagonal + (w != unit) as usize);
+            self.cells.push(cell);
+        }
+    }
+
+    fn distance(&self) -> usize {
+        *self.cells.last().unwrap()
+    }
+}
+
+/// [`Node::corrections`] for a query typed a character at a time. the
+/// first search measures like any other, after that a node visited
+/// again only computes the rows for the characters typed since it was
+/// last measured, and none at all after a backspace. the results are
+/// always those of a fresh search for [`IncrementalSearcher::query`].
+///
+/// the rows take a few bytes per character of each word visited, for as
+/// long as the searcher lives or until [`IncrementalSearcher::reset`]
+#[derive(Debug, Clone)]
+pub struct IncrementalSearcher {
+    root: &'static Node,
+    tolerance: u8,
+    query: String,
+    // keyed by the node's address, every node lives in a static
+    rows: BTreeMap<usize, Rows>,
+    results: Vec<Suggestion<'static>>,
+    stats: SearchStats,
+}
+
+impl IncrementalSearcher {
+    /// a searcher for words within `tolerance`, starting from an empty
+    /// query
+    pub fn new(root: &'static Node, tolerance: u8) -> Self {
+        let mut searcher = Self {
+            root,
+            tolerance,
+            query: String::new(),
+            rows: BTreeMap::new(),
+            results: Vec::new(),
+            stats: SearchStats::default(),
+        };
+        searcher.search();
+        searcher
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// the words within tolerance of the query, sorted by distance like
+    /// [`Node::corrections`]
+    pub fn corrections(&self) -> &[Suggestion<'static>] {
+        &self.results
+    }
+
+    /// what the last search took. `distance_calls` counts the nodes
+    /// measured from nothing, the rest were only brought up to date
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    /// searches again with `ch` typed at the end of the query
+    pub fn extend(&mut self, ch: char) -> &[Suggestion<'static>] {
+        self.edit(|query| query.push(ch));
+        &self.results
+    }
+
+    /// searches again with the last character of the query taken off, the
+    /// same query again when it's empty
+    pub fn backspace(&mut self) -> &[Suggestion<'static>] {
+        self.edit(|query| {
+            query.pop();
+        });
+        &self.results
+    }
+
+    /// searches for `word` from scratch, forgetting every row
+    pub fn reset(&mut self, word: &str) -> &[Suggestion<'static>] {
+        self.query.clear();
+        self.query.push_str(word);
+        self.rows.clear();
+        self.search();
+        &self.results
+    }
+
+    // the rows for the units the query keeps are still good, a combining
+    // mark typed or taken off changes the grapheme it's on
+    fn edit(&mut self, edit: impl FnOnce(&mut String)) {
+        let before = self.query.clone();
+        edit(&mut self.query);
+        let kept = units(&before)
+            .zip(units(&self.query))
+            .take_while(|(a, b)| a == b)
+            .count();
+        for rows in self.rows.values_mut() {
+            rows.truncate(kept);
+        }
+        self.search();
+    }
+
+    fn search(&mut self) {
+        let units: Vec<_> = units(&self.query).collect();
+        let mut stats = SearchStats::default();
+        let mut results = Vec::new();
+        Self::walk(
+            self.root,
+            &self.query,
+            &units,
+            self.tolerance as usize,
+            &mut self.rows,
+            &mut stats,
+            &mut results,
+        );
+        sort_by_distance(&mut results);
+        self.results = results;
+        self.stats = stats;
+    }
+
+    // every node in a bucket that can still hold a match, in the order
+    // `Node::corrections` visits them
+    fn walk(
+        node: &'static Node,
+        query: &str,
+        units: &[&str],
+        tolerance: usize,
+        rows: &mut BTreeMap<usize, Rows>,
+        stats: &mut SearchStats,
+        results: &mut Vec<Suggestion<'static>>,
+    ) {
+        stats.nodes_visited += 1;
+        let node_rows = rows.entry(node as *const Node as usize).or_insert_with(|| {
+            stats.distance_calls += 1;
+            Rows::new(node.word())
+        });
+        for unit in &units[node_rows.prefix()..] {
+            node_rows.push(node.word(), unit);
+        }
+        let distance = node_rows.distance();
+        if distance <= tolerance && !node.is_suppressed() {
+            results.push(Suggestion::new(query, node.word(), distance as u8));
+        }
+        let range = distance.saturating_sub(tolerance)..=distance + tolerance;
+        for (_, child) in node.children().filter(|(b, _)| range.contains(b)) {
+            Self::walk(child, query, units, tolerance, rows, stats, results);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// inflected variants of a dictionary of base forms, generated by the
+// writer as real words of the tree so "cars" and "running" are found and
+// corrected like any other word, each remembering the base it came from
+
+#[cfg(feature = "write")]
+use std::string::String;
+
+/// a suffix the writer adds to every base form it fits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InflectionRule {
+    pub suffix: &'static str,
+    /// the base has to end with one of these, unless it's empty
+    pub after: &'static [&'static str],
+    /// the base can't end with any of these
+    pub not_after: &'static [&'static str],
+    /// a trailing e is dropped first, "care" and "ing" make "caring"
+    pub drop_e: bool,
+    /// a one vowel base ending consonant vowel consonant doubles its last
+    /// letter, "run" and "ing" make "running"
+    pub double: bool,
+}
+
+const SIBILANTS: &[&str] = &["s", "x", "z", "ch", "sh"];
+
+const fn rule(
+    suffix: &'static str,
+    after: &'static [&'static str],
+    not_after: &'static [&'static str],
+    drop_e: bool,
+    double: bool,
+) -> InflectionRule {
+    InflectionRule {
+        suffix,
+        after,
+        not_after,
+        drop_e,
+        double,
+    }
+}
+
+/// plurals and the regular past and present participle, "boxes" but
+/// "cars", "cared" and "stopped", "caring" and "running"
+pub const ENGLISH_INFLECTIONS: &[InflectionRule] = &[
+    rule("s", &[], SIBILANTS, false, false),
+    rule("es", SIBILANTS, &[], false, false),
+    rule("ed", &[], &[], true, true),
+    rule("ing", &[], &[], true, true),
+];
+
+/// `base` with `rule` applied, `None` when the rule doesn't fit it
+#[cfg(feature = "write")]
+pub(crate) fn inflect(base: &str, rule: &InflectionRule) -> Option<String> {
+    if !rule.after.is_empty() && !rule.after.iter().any(|end| base.ends_with(end)) {
+        return None;
+    }
+    if rule.not_after.iter().any(|end| base.ends_with(end)) {
+        return None;
+    }
+    let mut word = String::from(base);
+    let bytes = base.as_bytes();
+    let vowel = |b: &u8| matches!(b, b'a' | b'e' | b'i' | b'o' | b'u');
+    match bytes {
+        [.., a, b'e'] if rule.drop_e && *a != b'e' => {
+            word.pop();
+        }
+        [.., a, b, c]
+            if rule.double
+                && bytes.iter().filter(|b| vowel(b)).count() == 1
+                && [a, c].iter().all(|x| x.is_ascii_lowercase() && !vowel(x))
+                && vowel(b)
+                && !matches!(c, b'w' | b'x' | b'y') =>
+        {
+            word.push(*c as char);
+        }
+        _ => (),
+    }
+    word.push_str(rule.suffix);
+    Some(word)
+}
+
+/// the generated `INFLECTIONS`, every variant the writer added along with
+/// the `Node::index` and word of the base it came from, sorted by variant
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, Copy)]
+pub struct Inflections {
+    entries: &'static [(&'static str, u32, &'static str)],
+}
+
+#[cfg(feature = "read")]
+impl Inflections {
+    /// `entries` sorted by variant, as the writer emits them
+    pub const fn new(entries: &'static [(&'static str, u32, &'static str)]) -> Self {
+        Self { entries }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the base form `word` was generated from, `None` for the words of
+    /// the dictionary itself
+    pub fn base_form(&self, word: &str) -> Option<&'static str> {
+        self.entry(word).map(|(_, _, base)| *base)
+    }
+
+    /// the `Node::index` of [`Inflections::base_form`]
+    pub fn base_index(&self, word: &str) -> Option<u32> {
+        self.entry(word).map(|(_, index, _)| *index)
+    }
+
+    fn entry(&self, word: &str) -> Option<&(&'static str, u32, &'static str)> {
+        let at = self.entries.binary_search_by(|(v, _, _)| (*v).cmp(word));
+        at.ok().map(|at| &self.entries[at])
+    }
+}
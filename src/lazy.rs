@@ -0,0 +1,100 @@
+// a runtime tree over a word list compiled into the binary, built the first
+// time it's searched, for host tools without a build script
+
+use super::{RuntimeBkTree, Suggestion};
+use core::fmt;
+use std::{collections::BTreeSet, sync::OnceLock, vec::Vec};
+
+/// why a [`LazyBkTree`] word list couldn't be built
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LazyError {
+    /// no line holds a word
+    Empty,
+    /// `word` already appeared on an earlier line, `line` counts from 1
+    Duplicate { line: usize, word: &'static str },
+}
+
+impl fmt::Display for LazyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the word list is empty"),
+            Self::Duplicate { line, word } => {
+                write!(f, "{word:?} on line {line} is already in the list")
+            }
+        }
+    }
+}
+
+std_error!(LazyError);
+
+/// a [`RuntimeBkTree`] of a newline separated word list, built on first
+/// use and kept from then on:
+///
+/// ```ignore
+/// static DICT: LazyBkTree = LazyBkTree::from_lines(include_str!("../words.txt"));
+/// ```
+///
+/// each line is one word, surrounding whitespace is trimmed and blank lines
+/// are skipped. a list that can't be built gives the same error from every
+/// call instead of panicking, and threads racing the first search build it
+/// only once
+#[derive(Debug)]
+pub struct LazyBkTree {
+    lines: &'static str,
+    tree: OnceLock<Result<RuntimeBkTree, LazyError>>,
+}
+
+impl LazyBkTree {
+    pub const fn from_lines(lines: &'static str) -> Self {
+        Self {
+            lines,
+            tree: OnceLock::new(),
+        }
+    }
+
+    /// the tree, building it if this is the first call
+    pub fn get(&self) -> Result<&RuntimeBkTree, &LazyError> {
+        self.tree.get_or_init(|| build(self.lines)).as_ref()
+    }
+
+    pub fn contains(&self, word: &str) -> Result<bool, &LazyError> {
+        Ok(self.get()?.contains(word))
+    }
+
+    /// see [`RuntimeBkTree::corrections`]
+    pub fn corrections(
+        &self,
+        word: &str,
+        tolerance: u8,
+    ) -> Result<Vec<Suggestion<'_>>, &LazyError> {
+        Ok(self.get()?.corrections(word, tolerance))
+    }
+
+    /// see [`RuntimeBkTree::best_match`]
+    pub fn best_match(
+        &self,
+        word: &str,
+        tolerance: u8,
+    ) -> Result<Option<Suggestion<'_>>, &LazyError> {
+        Ok(self.get()?.best_match(word, tolerance))
+    }
+}
+
+fn build(lines: &'static str) -> Result<RuntimeBkTree, LazyError> {
+    let mut seen = BTreeSet::new();
+    let mut words = Vec::new();
+    for (i, word) in lines.lines().map(str::trim).enumerate() {
+        if word.is_empty() {
+            continue;
+        }
+        if !seen.insert(word) {
+            return Err(LazyError::Duplicate { line: i + 1, word });
+        }
+        words.push(word);
+    }
+    if words.is_empty() {
+        return Err(LazyError::Empty);
+    }
+    Ok(words.into_iter().collect())
+}
@@ -2,17 +2,234 @@
 #[cfg(feature = "std")]
 extern crate std;
 // BK Tree for no_std enviroments using Levenshtein for the diff
+//
+// the read side is checked on 16 bit targets with nightly and rust-src:
+// `cargo +nightly build -Z build-std=core --target msp430-none-elf
+// --no-default-features --features read`, and the same for `avr-none` with
+// `RUSTFLAGS="-C target-cpu=atmega328p"`. see `Node` for the size limits
+//
+// `small-code` trades speed for flash on the tiniest parts: one shared copy
+// of a one row distance loop, no levenshtein automaton for the leaves and an
+// insertion sort wherever results are sorted, with every result the same.
+// on thumbv6m at opt-level "s" with lto, `contains`, `best_match`,
+// `for_each_correction` and `best_k_into` over the 8 word test tree come to
+// 6.6k of `.text` with it and 7.9k without
+//
+// the crate and the trees it generates build on rust 1.77, `rust-version`
+// in Cargo.toml. nothing newer goes in the generated text, which
+// `msrv_codegen` checks, so a tree written by a current toolchain's build
+// script still compiles on an older certified one. optional dependencies
+// can need pinning to an older release there, `unicode-segmentation`
+// 1.11 for `grapheme`, and the tests want a current stable
 
+// `log` lines for following a search on the host, nothing without the
+// feature. they're separate from `defmt`, which is for logging on the device
+#[allow(unused_macros)]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)*);
+    };
+}
+#[allow(unused_macros)]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+    };
+}
+
+// `std::error::Error` for an error type, generic ones over their params.
+// every error has `Display` in no_std, `core::error::Error` has to wait for
+// the rust version to pass 1.81
+#[allow(unused_macros)]
+macro_rules! std_error {
+    ($ty:ty) => {
+        #[cfg(feature = "std")]
+        impl std::error::Error for $ty {}
+    };
+    ($ty:ty, $($param:ident),+) => {
+        #[cfg(feature = "std")]
+        impl<$($param: core::fmt::Debug + core::fmt::Display),+> std::error::Error for $ty {}
+    };
+}
+
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(all(feature = "read", not(feature = "small-code")))]
+mod automaton;
+#[cfg(any(feature = "read", feature = "write"))]
+mod bloom;
+#[cfg(feature = "read")]
+mod cache;
+#[cfg(feature = "read")]
+mod case;
+#[cfg(feature = "embedded-cli")]
+mod completion;
+#[cfg(all(feature = "confusables", any(feature = "read", feature = "write")))]
+mod confusable;
+#[cfg(feature = "write")]
+mod diff;
+#[cfg(any(feature = "read", feature = "write"))]
+mod distance;
+#[cfg(all(feature = "read", feature = "alloc"))]
+mod edit;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(all(feature = "hamming", any(feature = "read", feature = "write")))]
+mod hamming;
+#[cfg(any(feature = "read", feature = "write"))]
+mod hash;
+#[cfg(all(feature = "read", feature = "alloc"))]
+mod incremental;
+#[cfg(all(feature = "inflect", any(feature = "read", feature = "write")))]
+mod inflect;
+#[cfg(all(feature = "read", feature = "std"))]
+mod lazy;
+#[cfg(all(feature = "read", feature = "alloc"))]
+mod overlay;
+#[cfg(all(feature = "phonetic", any(feature = "read", feature = "write")))]
+mod phonetic;
+#[cfg(feature = "avr-progmem")]
+mod progmem;
+#[cfg(feature = "read")]
+mod query;
+#[cfg(feature = "read")]
+mod query_chars;
+#[cfg(all(feature = "read", feature = "alloc"))]
+mod runtime;
+#[cfg(feature = "sdmmc")]
+mod sdmmc;
+#[cfg(all(feature = "read", feature = "std"))]
+mod shared;
+#[cfg(feature = "stem")]
+mod stem;
+#[cfg(feature = "read")]
+mod storage;
+#[cfg(feature = "read")]
+mod stream;
+#[cfg(all(feature = "read", feature = "alloc"))]
+mod suggest;
 #[cfg(feature = "read")]
-pub use read::Node;
+mod supplement;
+#[cfg(feature = "proptest")]
+pub mod testing;
+#[cfg(all(feature = "read", feature = "alloc"))]
+mod tiered;
+#[cfg(feature = "read")]
+mod usage;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(feature = "rkyv")]
+pub use archive::{ArchivedBkTree, ValidationError};
+#[cfg(feature = "read")]
+pub use bloom::BloomFilter;
+#[cfg(feature = "read")]
+pub use cache::{CachedSearcher, MAX_CACHED_QUERY};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use case::apply_case_string;
+#[cfg(feature = "read")]
+pub use case::{apply_case, BufTooSmall};
+#[cfg(feature = "embedded-cli")]
+pub use completion::{autocomplete, command_suggestions};
+#[cfg(all(feature = "confusables", any(feature = "read", feature = "write")))]
+pub use confusable::fold_confusable;
+#[cfg(all(feature = "confusables", feature = "read"))]
+pub use confusable::fold_confusables;
+#[cfg(all(
+    feature = "confusables",
+    feature = "alloc",
+    any(feature = "read", feature = "write")
+))]
+pub use confusable::fold_confusables_string;
+#[cfg(feature = "write")]
+pub use diff::{
+    diff_generated, diff_word_lists, diff_word_lists_by, generated_words, DictDiff, SourceError,
+};
+#[cfg(feature = "read")]
+pub use distance::{distance, levenshtein_const, MAX_CONST_LEN};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use edit::{edit_script, EditScript, Op};
+#[cfg(feature = "ffi")]
+pub use ffi::{bktree_contains, bktree_corrections, BkSuggestion};
+#[cfg(all(feature = "hamming", any(feature = "read", feature = "write")))]
+pub use hamming::hamming;
+#[cfg(all(feature = "hamming", feature = "read"))]
+pub use hamming::{CodeLength, HammingTree};
+#[cfg(any(feature = "read", feature = "write"))]
+pub use hash::checksum;
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use incremental::IncrementalSearcher;
+#[cfg(all(feature = "inflect", feature = "read"))]
+pub use inflect::Inflections;
+#[cfg(all(feature = "inflect", any(feature = "read", feature = "write")))]
+pub use inflect::{InflectionRule, ENGLISH_INFLECTIONS};
+#[cfg(all(feature = "read", feature = "std"))]
+pub use lazy::{LazyBkTree, LazyError};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use overlay::Overlay;
+#[cfg(all(feature = "phonetic", feature = "read"))]
+pub use phonetic::PhoneticIndex;
+#[cfg(all(feature = "phonetic", any(feature = "read", feature = "write")))]
+pub use phonetic::{phonetic_code, PhoneticCode};
+#[cfg(feature = "avr-progmem")]
+pub use progmem::{FarRead, NearRead, ProgmemBlob, ProgmemStorage};
+#[cfg(feature = "read")]
+pub use query::{validate_query, QueryError, QueryLimits};
+#[cfg(feature = "read")]
+pub use query_chars::{distance_chars, QueryChars};
+#[cfg(feature = "read")]
+pub use read::{
+    suggested_tolerance, AmbiguousQuery, BestK, Cancelled, Corrections, IncludeSuppressed,
+    LengthStats, MemoryUsage, Node, SearchStats, StaticBkTree, Suggestion, TreeDisplay, WordTable,
+    CANCEL_INTERVAL, MAX_DISPLAY_DEPTH, WILDCARD,
+};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use read::{AllocError, NodeIterator, TryNodeIterator};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use runtime::{LoadStats, RuntimeBkTree, RuntimeStats};
+#[cfg(all(feature = "sdmmc", feature = "alloc"))]
+pub use sdmmc::load_file;
+#[cfg(feature = "sdmmc")]
+pub use sdmmc::{for_each_word, for_each_word_in_file, WordListError};
+#[cfg(all(feature = "read", feature = "std"))]
+pub use shared::SharedBkTree;
+#[cfg(feature = "stem")]
+pub use stem::{StemMatch, SuffixRule, ENGLISH_SUFFIXES, MAX_STEM_BYTES};
+#[cfg(feature = "async")]
+pub use storage::{corrections_in_async_storage, AsyncNodeStorage};
+#[cfg(feature = "read")]
+pub use storage::{
+    corrections_in_storage, BlobError, BlobStorage, DecodeError, NodeBuf, NodeStorage,
+    StorageError, StoredMatch, MAX_NODE_BYTES,
+};
+#[cfg(feature = "read")]
+pub use stream::{StreamCorrector, WordEvent};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use suggest::{did_you_mean, write_did_you_mean};
+#[cfg(feature = "read")]
+pub use supplement::{ParseError, Supplement, SupplementedTree};
+#[cfg(all(feature = "read", feature = "alloc"))]
+pub use tiered::{TieredResults, ToleranceError};
+#[cfg(feature = "read")]
+pub use usage::UsageCounters;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmBkTree;
 #[cfg(feature = "write")]
-pub use write::write_bktree;
+pub use write::{write_bktree, BkTreeBuilder, BuildStats, SourceStats, WriteError, SHARD_NODES};
 
-// this is the lenght of the children array in Node
+// this is the number of buckets a Node has, the lenght of its children
+// array with `array-children`
 // corresponds to the number of top level words with a diff
 // equal to or lower than the root/parent node
+#[allow(unused)]
 const CHILDREN_LENGTH: usize = 15;
 
+/// longest word in bytes a node of the storage blob can hold
+#[cfg(any(feature = "read", feature = "write"))]
+pub const MAX_STORED_WORD: usize = 64;
+
 #[allow(unused)]
 const ROOT_WORD: &str = "the";
 
@@ -24,190 +241,4058 @@ const ROOT_WORD: &str = "the";
 /// to ensure lookup speeds are fast
 ///
 #[cfg(feature = "write")]
-mod write {
-    use super::{CHILDREN_LENGTH, ROOT_WORD};
-    use levenshtein::levenshtein;
-    use std::{
-        boxed::Box,
-        env::var,
-        format,
-        fs::File,
-        io::Write,
-        path::{Path, PathBuf},
-        string::String,
-        vec::Vec,
+mod write;
+
+/// read is explicitly for reading the contents of the tree
+/// during runtime.
+///
+/// use embedded_bktree::read::*;
+/// include!(concat!(env!("OUT_DIR"), "tree.rs"));
+/// let corrections = TREE.corrections("foo", 1);
+///
+/// a tolerance of 0 means exact matches only, the search then costs the
+/// same as `contains`.
+///
+/// an empty query is just a very short word: it matches the words with at
+/// most `tolerance` chars and is never contained in the tree.
+///
+#[cfg(feature = "read")]
+mod read;
+
+/// checks for trees built some other way than the writer
+#[cfg(feature = "validate")]
+mod validate;
+#[cfg(feature = "validate")]
+pub use validate::{verify_search, verify_searches, InvariantError, Mismatch};
+
+#[cfg(feature = "test")]
+#[cfg(test)]
+mod test {
+    use super::{
+        checksum, distance::distance, verify_searches, write, BestK, BkTreeBuilder, CachedSearcher,
+        Corrections, InvariantError, Node, StaticBkTree, Suggestion,
+    };
+    use core::ops::ControlFlow;
+    use std::{path::Path, string::String, vec, vec::Vec};
+
+    // a tree built in grapheme mode refuses to compile without it and
+    // vice versa, so each mode gets its own fixture
+    #[cfg(not(feature = "grapheme"))]
+    include!("../tree.test");
+    #[cfg(feature = "grapheme")]
+    include!("../tree_grapheme.test");
+
+    const FIXTURE: &str = match cfg!(feature = "grapheme") {
+        true => "tree_grapheme.test",
+        false => "tree.test",
     };
 
-    #[derive(Debug, Clone)]
-    pub struct Node<'a> {
-        pub word: &'a str,
-        pub children: [Option<Box<Node<'a>>>; CHILDREN_LENGTH],
+    #[test]
+    fn write_bktree() {
+        let path = Path::new(".").join(FIXTURE);
+        let word_list = &mut vec!["the", "them", "she", "he", "car", "care", "card", "cake"];
+        write::write_bktree(Some(path), word_list);
+        // the fixture included above is the previous run's output
+        assert_eq!(TREE.check_invariants(), Ok(()));
+    }
+
+    // deterministic pseudo random words, enough for a tree with some depth
+    #[cfg_attr(feature = "grapheme", allow(dead_code))]
+    pub(crate) fn generated_words(count: usize) -> Vec<String> {
+        let mut seed = 0x2545_f491_u32;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+        let mut words = vec![String::from("the")];
+        while words.len() < count {
+            let len = 2 + next() as usize % 7;
+            words.push(
+                (0..len)
+                    .map(|_| b"aeinrstcd"[next() as usize % 9] as char)
+                    .collect(),
+            );
+        }
+        words
+    }
+
+    #[test]
+    fn dict_diff() {
+        use super::{diff_generated, diff_word_lists, diff_word_lists_by, generated_words};
+
+        let old = ["the", "car", "card", "cake", "car"];
+        let new = ["cart", "the", "card", "she", "cart"];
+        let diff = diff_word_lists(old, new);
+        assert_eq!(diff.added, ["cart", "she"]);
+        assert_eq!(diff.removed, ["cake", "car"]);
+        assert_eq!(diff.common, ["card", "the"]);
+        assert_eq!(diff_word_lists(new, old).added, diff.removed);
+        // order doesn't matter
+        assert!(diff_word_lists(old, old.iter().rev().copied()).is_empty());
+
+        let folded = diff_word_lists_by(["The", "Car"], ["the", "CARD"], str::to_lowercase);
+        assert_eq!(
+            (folded.added, folded.removed),
+            (vec!["card".into()], vec!["car".into()])
+        );
+
+        let mut report = Vec::new();
+        diff.write_report(&mut report).unwrap();
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "2 added, 2 removed, 2 unchanged\n+ cart\n+ she\n- cake\n- car\n"
+        );
+
+        // generated trees read back in node order, whichever layout
+        let words = ["the", "caf\u{e9}", "say \"hi\"", "a\\b\tc"];
+        for dfs_order in [false, true] {
+            let source = BkTreeBuilder::new(words).dfs_order(dfs_order).build();
+            let mut read = generated_words(&source).unwrap();
+            assert_eq!(read[0], "the");
+            read.sort_unstable();
+            let mut expected = words.map(String::from);
+            expected.sort_unstable();
+            assert_eq!(read, expected);
+        }
+        let fixture = include_str!("../tree.test");
+        let bumped = BkTreeBuilder::new(["the", "them", "she", "car", "cart", "card"]).build();
+        let diff = diff_generated(fixture, &bumped).unwrap();
+        assert_eq!(diff.added, ["cart"]);
+        assert_eq!(diff.removed, ["cake", "care", "he"]);
+        assert_eq!(
+            generated_words("static X: u8 = 0;"),
+            Err(super::SourceError::NoNodes)
+        );
     }
 
-    impl<'a> Node<'a> {
-        fn new(word: &'a str) -> Self {
-            Self {
-                word,
-                children: [const { None }; CHILDREN_LENGTH],
+    #[test]
+    fn cached_searcher() {
+        let mut cache = CachedSearcher::<2, 3>::new(&TREE);
+        let long = "thesearenotthedroidsyouarelookingfor";
+        let queries = [
+            ("cars", 1), // miss
+            ("cars", 1), // hit
+            ("cars", 2), // miss
+            ("cars", 1), // hit
+            ("he", 1),   // miss, evicts cars 2
+            ("cars", 1), // hit
+            (long, 255), // too long to cache
+            ("cars", 2), // miss, evicts he 1
+            ("he", 1),   // miss, evicts cars 1
+            ("cars", 1), // miss
+        ];
+        for (query, tolerance) in queries {
+            let expected: Vec<_> = TREE
+                .corrections(query, tolerance)
+                .into_iter()
+                .take(3)
+                .collect();
+            let cached = cache.corrections(query, tolerance);
+            assert_eq!(cached.len(), expected.len());
+            for (a, b) in cached.iter().zip(&expected) {
+                assert_eq!((a.word, a.distance), (b.word, b.distance));
             }
         }
-        fn add(&mut self, word: &'a str) {
-            let diff = levenshtein(self.word, word);
-            if diff < CHILDREN_LENGTH {
-                if let Some(node) = self.children[diff].as_mut() {
-                    node.add(word);
-                } else {
-                    self.children[diff] = Some(Box::new(Node::new(word)));
-                }
+        assert_eq!((cache.hits(), cache.misses()), (3, 7));
+        cache.clear();
+        cache.corrections("cars", 1);
+        assert_eq!(cache.misses(), 8);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let usage = TREE.memory_usage();
+        assert_eq!(usage.nodes, WORD_COUNT);
+        assert_eq!(usage.node_bytes, NODE_BYTES);
+        assert_eq!(usage.string_bytes, STRING_BYTES);
+        assert_eq!(usage.string_bytes, "themtheshehecarcarecardcake".len());
+        assert_eq!(usage.total(), NODE_BYTES + STRING_BYTES);
+        assert_eq!(TREE.child_at(3).unwrap().memory_usage().nodes, 3);
+    }
+
+    #[test]
+    fn compact_children() {
+        // the same fields with a slot for every bucket
+        #[allow(dead_code)]
+        struct ArrayNode {
+            word: &'static str,
+            index: u32,
+            mask: u32,
+            suppressed: bool,
+            children: [Option<&'static ArrayNode>; 15],
+        }
+        let array_bytes = WORD_COUNT * core::mem::size_of::<ArrayNode>();
+        #[cfg(feature = "array-children")]
+        assert_eq!(NODE_BYTES, array_bytes);
+        #[cfg(not(feature = "array-children"))]
+        {
+            assert!(NODE_BYTES * 2 < array_bytes);
+            #[cfg(target_pointer_width = "64")]
+            assert_eq!((NODE_BYTES, array_bytes), (575, 1216));
+        }
+    }
+
+    #[test]
+    fn child_accessors() {
+        for node in TREE.iter() {
+            let children: Vec<_> = node.children().collect();
+            for distance in 0..=super::CHILDREN_LENGTH {
+                let child = children.iter().find(|(b, _)| *b == distance);
+                assert_eq!(node.child_at(distance), child.map(|(_, c)| *c));
             }
+            assert_eq!(node.is_leaf(), children.is_empty());
+        }
+        assert_eq!(TREE.word(), "the");
+        assert_eq!(TREE.index(), 0);
+    }
+
+    #[test]
+    fn tolerance_zero() {
+        fn depth(node: &Node) -> usize {
+            1 + node.children().map(|(_, c)| depth(c)).max().unwrap_or(0)
+        }
+        let depth = depth(&TREE);
+        for word in ["the", "he", "cake", "card"] {
+            let corrections = TREE.corrections(word, 0);
+            assert_eq!(corrections.len(), 1);
+            assert_eq!((corrections[0].word, corrections[0].distance), (word, 0));
+            assert!(TREE.search_stats(word, 0).nodes_visited <= depth);
         }
-        pub fn as_string(&self) -> String {
-            assert_eq!(ROOT_WORD, self.word);
-            let string = format!("static TREE: Node = {:?};", self);
-            // ensuring children are refs
-            string.replace("Some(", "Some(&")
+        for word in ["cars", "shes", "", "zzzzzzzzzzzzzzzzzzzzzzz"] {
+            assert!(TREE.corrections(word, 0).is_empty());
+            assert!(TREE.best_match(word, 0).is_none());
+            assert!(TREE.search_stats(word, 0).nodes_visited <= depth);
         }
+        // the -> them -> she -> he
+        assert_eq!(TREE.search_stats("he", 0).nodes_visited, 4);
+        assert!(TREE.search_stats("he", 2).nodes_visited > 4);
     }
 
-    /// Write word list to bk tree file
-    /// You can specify a specific path, otherwise 'OUT_DIR' is used.
-    /// the default file name is tree.rs -
-    /// #example:
-    /// ```
-    /// // build.rs file
-    /// // include!(concat!(env!("OUT_DIR"), "/tree.rs"));
-    /// ```
-    pub fn write_bktree<'a>(file_path: Option<PathBuf>, word_list: &mut Vec<&'a str>) {
-        let mut tree = Node::new(ROOT_WORD); // root node
-        let index = word_list
+    #[test]
+    fn corrections_tiered() {
+        use super::ToleranceError;
+
+        for word in ["cars", "shes", "he", "cake", "xyz"] {
+            let tiered = TREE.corrections_tiered(word, &[1, 2]).unwrap();
+            let near = TREE.corrections(word, 1);
+            let other: Vec<_> = TREE
+                .corrections(word, 2)
+                .into_iter()
+                .filter(|s| s.distance == 2)
+                .collect();
+            assert_eq!(tiered.tiers, [near, other]);
+            // one search at 2 worth of work, less than the two apart
+            let (one, two) = (TREE.search_stats(word, 1), TREE.search_stats(word, 2));
+            assert_eq!(tiered.stats, two);
+            assert!(tiered.stats.distance_calls < one.distance_calls + two.distance_calls);
+        }
+        let exact = TREE.corrections_tiered("card", &[0, 1, 3]).unwrap();
+        let words: Vec<Vec<_>> = exact
+            .tiers
             .iter()
-            .position(|x| *x == ROOT_WORD)
-            .expect(format!("{} was not found in word_list", ROOT_WORD).as_str());
-        word_list.remove(index); // remove root node word
-        word_list.dedup();
-        word_list.iter().for_each(|w| tree.add(w));
-
-        // write the tree to cargo out's directory
-        let mut buffer = File::create(match file_path {
-            Some(path) => path,
-            None => Path::new(&var("OUT_DIR").unwrap()).join("tree.rs"),
-        })
-        .unwrap();
-        buffer.write_all(tree.as_string().as_bytes()).unwrap();
+            .map(|tier| tier.iter().map(|s| s.word).collect())
+            .collect();
+        assert_eq!(words[0], ["card"]);
+        assert_eq!(words[1], ["car", "care"]);
+        assert!(words[2].contains(&"cake"));
+        assert_eq!(
+            TREE.corrections_tiered("car", &[]),
+            Err(ToleranceError::Empty)
+        );
+        assert_eq!(
+            TREE.corrections_tiered("car", &[1, 2, 2]),
+            Err(ToleranceError::NotAscending { at: 2 })
+        );
     }
-}
 
-/// read is explicitly for reading the contents of the tree
-/// during runtime.
-///
-/// use embedded_bktree::read::*;
-/// include!(concat!(env!("OUT_DIR"), "tree.rs"));
-/// let corrections = TREE.corrections("foo");
-///
-// #[cfg(feature = "read")]
-mod read {
-    use super::CHILDREN_LENGTH;
-    use levenshtein::levenshtein;
-
-    extern crate alloc;
-    use alloc::{vec, vec::Vec};
+    #[test]
+    fn exists_and_count_within() {
+        for query in ["cars", "shes", "", "xyzzy", "the", "carder"] {
+            for tolerance in [0, 1, 2, 3] {
+                let corrections = TREE.corrections(query, tolerance);
+                assert_eq!(TREE.count_within(query, tolerance), corrections.len());
+                assert_eq!(
+                    TREE.exists_within(query, tolerance),
+                    !corrections.is_empty()
+                );
+            }
+        }
+    }
 
-    #[derive(Debug, Clone)]
-    pub struct Node {
-        pub word: &'static str,
-        pub children: [Option<&'static Node>; CHILDREN_LENGTH],
+    #[test]
+    fn edit_scripts() {
+        use super::{edit_script, Op};
+        assert_eq!(
+            edit_script("shes", "she").ops,
+            [Op::Keep(3), Op::Delete("s")]
+        );
+        assert_eq!(
+            edit_script("cars", "care").ops,
+            [Op::Keep(3), Op::Substitute("s", "e")]
+        );
+        assert_eq!(edit_script("he", "the").ops, [Op::Insert("t"), Op::Keep(2)]);
+        assert_eq!(
+            edit_script("", "he").ops,
+            [Op::Insert("h"), Op::Insert("e")]
+        );
+        assert_eq!(edit_script("same", "same").ops, [Op::Keep(4)]);
+        assert_eq!(
+            edit_script("cåt", "cat").ops,
+            [Op::Keep(1), Op::Substitute("å", "a"), Op::Keep(1)]
+        );
+        for (s, script) in TREE.corrections_with_edits("cards", 2) {
+            assert_eq!(script.edits(), s.distance as usize);
+        }
     }
-    impl Node {
-        pub fn iter(&'static self) -> NodeIterator {
-            NodeIterator::new(self)
+
+    // checked when the tests build
+    const _: () = assert!(TREE.contains_const("the") && TREE.contains_const("card"));
+    const _: () = assert!(!TREE.contains_const("cart") && !TREE.contains_const(""));
+    const _: () = assert!(WRAPPED.contains_const("cake"));
+    const _: () = assert!(super::levenshtein_const("kitten", "sitting") == 3);
+
+    #[test]
+    fn contains_const() {
+        use super::levenshtein_const;
+        let words = [
+            "",
+            "a",
+            "the",
+            "teh",
+            "naïve",
+            "naive",
+            "日本語",
+            "日本",
+            "car",
+        ];
+        for a in words {
+            for b in words {
+                assert_eq!(
+                    levenshtein_const(a, b),
+                    super::distance::char_distance(a, b)
+                );
+            }
+        }
+        for word in ["the", "he", "cake", "cart", "ca", "x"] {
+            assert_eq!(TREE.contains_const(word), TREE.contains(word));
         }
+    }
+
+    #[test]
+    #[should_panic(expected = "too long")]
+    fn levenshtein_const_limit() {
+        let long = "a".repeat(super::MAX_CONST_LEN + 1);
+        super::levenshtein_const(&long, &long);
+    }
+
+    #[cfg(not(feature = "small-code"))]
+    #[test]
+    fn automaton() {
+        use crate::automaton::{Automaton, MAX_AUTOMATON_LEN};
+        use crate::distance::char_distance;
+        use std::format;
 
-        pub fn canidates<'a>(&'static self, word: &'a str, tolerance: u8) -> Vec<&'a str> {
-            let mut canidates = Vec::new();
-            let distance = levenshtein(self.word, word) as u8;
-            let (min, max) = (distance - tolerance, distance + tolerance);
-            for (_, node) in self
-                .children
+        // every string of up to 4 chars from a small alphabet, one of them
+        // wider than a byte
+        let mut words = vec![String::new()];
+        for len in 0..4 {
+            let longer: Vec<String> = words
                 .iter()
-                .enumerate()
-                .filter(|(i, _n)| *i as u8 >= min && *i as u8 <= max)
-            {
-                if let Some(node) = node {
-                    canidates.push(node.word);
-                    canidates.append(&mut node.canidates(word, tolerance));
+                .filter(|w| w.chars().count() == len)
+                .flat_map(|w| "ab\u{e9}".chars().map(move |c| format!("{w}{c}")))
+                .collect();
+            words.extend(longer);
+        }
+        for tolerance in 0..=2 {
+            for query in &words {
+                let automaton = Automaton::new(query, tolerance).unwrap();
+                for word in &words {
+                    let expected = char_distance(query, word);
+                    let expected = (expected <= tolerance).then_some(expected);
+                    assert_eq!(automaton.distance(word), expected, "{query} {word}");
                 }
             }
-            canidates
         }
+
+        let long = "ab".repeat(MAX_AUTOMATON_LEN / 2) + "a";
+        let automaton = Automaton::new(&long, 2).unwrap();
+        assert_eq!(automaton.distance(&long), Some(0));
+        assert_eq!(automaton.distance(&long[1..]), Some(1));
+        assert_eq!(automaton.distance(&(long.clone() + "bb")), Some(2));
+        assert_eq!(automaton.distance(&(long.clone() + "bbb")), None);
+        assert!(Automaton::new(&(long + "b"), 2).is_none());
+        assert!(Automaton::new("abc", 3).is_none());
+    }
+
+    #[test]
+    fn wildcard() {
+        let words = |pattern, tolerance| {
+            let mut words: Vec<_> = TREE
+                .wildcard_matches(pattern, tolerance)
+                .iter()
+                .map(|s| (s.word, s.distance))
+                .collect();
+            words.sort();
+            words
+        };
+        assert_eq!(words("ca?e", 0), [("cake", 0), ("care", 0)]);
+        assert_eq!(words("c?r", 0), [("car", 0)]);
+        assert_eq!(words("c?r", 1), [("car", 0), ("card", 1), ("care", 1)]);
+        assert_eq!(words("?a?e", 0), [("cake", 0), ("care", 0)]);
+        assert_eq!(words("??e", 0), [("she", 0), ("the", 0)]);
+        assert_eq!(
+            words("????", 0),
+            [("cake", 0), ("card", 0), ("care", 0), ("them", 0)]
+        );
+        assert_eq!(words("x?", 0), []);
+        // no wildcard is an ordinary search
+        assert_eq!(words("cars", 1), [("car", 1), ("card", 1), ("care", 1)]);
+        // any char can be the wildcard, and the tree's own words don't match it
+        let mut found = Vec::new();
+        TREE.for_each_wildcard_match("*he", '*', 0, |s| {
+            found.push(s.word);
+            ControlFlow::Continue(())
+        });
+        found.sort();
+        assert_eq!(found, ["she", "the"]);
+    }
+
+    #[test]
+    fn partial() {
+        use super::distance::partial_distance;
+        use std::format;
+        let words = |word, tolerance| {
+            let mut words: Vec<_> = TREE
+                .partial_corrections(word, tolerance)
+                .iter()
+                .map(|s| (s.word, s.distance))
+                .collect();
+            words.sort();
+            words
+        };
+        assert_eq!(words("ar", 0), [("car", 0), ("card", 0), ("care", 0)]);
+        assert_eq!(
+            words("he", 0),
+            [("he", 0), ("she", 0), ("the", 0), ("them", 0)]
+        );
+        assert_eq!(TREE.corrections("ar", 0), []);
+        assert_eq!(words("ake", 0), [("cake", 0)]);
+        assert_eq!(words("cqke", 1), [("cake", 1)]);
+        assert_eq!(partial_distance("volts", "millivolts"), 0);
+        assert_eq!(partial_distance("vlots", "millivolts"), 2);
+        assert_eq!(partial_distance("millivolts", "volts"), 5);
+        assert_eq!(partial_distance("", "car"), 0);
+        // past the stack row, along the query instead
+        let long = format!("{}millivolts", "x".repeat(70));
+        assert_eq!(partial_distance("volts", &long), 0);
+        assert_eq!(partial_distance("vlots", &long), 2);
+        assert_eq!(partial_distance("", &long), 0);
+        assert_eq!(partial_distance(&long, &long[1..]), 1);
+
+        // a tree of its own, 5 deletions apart
+        static VOLTS: Node = Node::new("volts", 1, 0, &[], &[]);
+        static MILLIVOLTS: Node = Node::new("millivolts", 0, 0, &[5], &[&VOLTS]);
+        assert_eq!(MILLIVOLTS.corrections("volts", 2).len(), 1);
+        let found: Vec<_> = MILLIVOLTS
+            .partial_corrections("volts", 0)
+            .iter()
+            .map(|s| (s.word, s.distance))
+            .collect();
+        assert_eq!(found, [("millivolts", 0), ("volts", 0)]);
+        assert_eq!(MILLIVOLTS.corrections("olt", 1), []);
+        assert_eq!(MILLIVOLTS.partial_corrections("olt", 0).len(), 2);
+        static LONG: Node = Node::new(
+            "electromagnetically-compatible-switched-mode-power-supply-millivolts",
+            0,
+            0,
+            &[],
+            &[],
+        );
+        assert_eq!(LONG.partial_corrections("vlots", 2).len(), 1);
+    }
+
+    #[test]
+    fn did_you_mean() {
+        use super::{did_you_mean, write_did_you_mean};
+
+        // the subcommands of a tool, in their buckets
+        static START: Node = Node::new("start", 4, 0, &[], &[]);
+        static STASH: Node = Node::new("stash", 3, 0, &[2], &[&START]);
+        static SWITCH: Node = Node::new("switch", 2, 0, &[], &[]);
+        static TRASH: Node = Node::new("trash", 1, 0, &[], &[]);
+        static STATUS: Node = Node::new("status", 0, 0, &[3, 4, 5], &[&STASH, &SWITCH, &TRASH]);
+        assert_eq!(STATUS.check_invariants(), Ok(()));
+
+        let suggestions = did_you_mean("stutus", &STATUS, 3);
+        assert_eq!(suggestions, ["status"]);
+        let mut message = String::new();
+        write_did_you_mean(&mut message, &suggestions).unwrap();
+        assert_eq!(message, "did you mean 'status'?");
+
+        assert_eq!(did_you_mean("STATUS", &STATUS, 3), ["status"]);
+        assert_eq!(did_you_mean("Swich", &STATUS, 3), ["switch"]);
+        // trash is found after stash, the shared first char puts it ahead
+        assert_eq!(did_you_mean("tash", &STATUS, 3), ["trash", "stash"]);
+        assert_eq!(did_you_mean("tash", &STATUS, 1), ["trash"]);
+        assert!(did_you_mean("stat", &STATUS, 0).is_empty());
+        // one edit from "st" is most two letter words
+        assert!(did_you_mean("st", &STATUS, 3).is_empty());
+        assert!(did_you_mean("xyzzy", &STATUS, 3).is_empty());
+
+        let mut message = String::new();
+        write_did_you_mean(&mut message, &["stash", "start"]).unwrap();
+        assert_eq!(message, "did you mean 'stash' or 'start'?");
+        message.clear();
+        write_did_you_mean(&mut message, &["stash", "start", "status"]).unwrap();
+        assert_eq!(message, "did you mean 'stash', 'start' or 'status'?");
+        message.clear();
+        write_did_you_mean(&mut message, &[]).unwrap();
+        assert_eq!(message, "");
+    }
+
+    #[cfg(feature = "embedded-cli")]
+    #[test]
+    fn embedded_cli() {
+        use super::{autocomplete, command_suggestions};
+        use embedded_cli::autocomplete::{Autocompletion, Request};
+
+        static START: Node = Node::new("start", 4, 0, &[], &[]);
+        static STASH: Node = Node::new("stash", 3, 0, &[2], &[&START]);
+        static SWITCH: Node = Node::new("switch", 2, 0, &[], &[]);
+        static TRASH: Node = Node::new("trash", 1, 0, &[], &[]);
+        static STATUS: Node = Node::new("status", 0, 0, &[3, 4, 5], &[&STASH, &SWITCH, &TRASH]);
+
+        let complete = |input| {
+            let mut buf = [0; 16];
+            let mut autocompletion = Autocompletion::new(&mut buf);
+            autocomplete(
+                &STATUS,
+                Request::from_input(input).unwrap(),
+                &mut autocompletion,
+            );
+            let partial = autocompletion.is_partial();
+            autocompletion
+                .autocompleted()
+                .map(|s| (String::from(s), partial))
+        };
+        assert_eq!(complete("sw"), Some(("itch".into(), false)));
+        // status, stash and start all go on with an "a"
+        assert_eq!(complete("st"), Some(("a".into(), true)));
+        assert_eq!(complete("status"), Some(("".into(), false)));
+        assert_eq!(complete("swich"), None);
+
+        let suggest = |input| {
+            let mut out = [""; 4];
+            let found = command_suggestions(&STATUS, input, &mut out);
+            out[..found].to_vec()
+        };
+        assert_eq!(suggest("st"), ["status", "stash", "start"]);
+        assert_eq!(suggest("swich"), ["switch"]);
+        assert_eq!(suggest("stas"), ["stash"]);
+        assert_eq!(suggest("tash"), ["stash", "trash"]);
+        assert!(suggest("xyzzy").is_empty());
+        let mut out = [""; 2];
+        assert_eq!(command_suggestions(&STATUS, "s", &mut out), 2);
+        assert_eq!(out, ["status", "stash"]);
+    }
+
+    #[test]
+    fn query_validation() {
+        use super::{validate_query, QueryError, QueryLimits};
+        let word = QueryLimits::WORD;
+        assert_eq!(validate_query("care", &word), Ok(()));
+        assert_eq!(validate_query("", &word), Err(QueryError::Empty));
+        let long = "a".repeat(33);
+        assert_eq!(validate_query(&long, &word), Err(QueryError::TooLong));
+        assert_eq!(validate_query(&long[..32], &word), Ok(()));
+        let huge = "ab ".repeat(100_000);
+        assert_eq!(validate_query(&huge, &word), Err(QueryError::TooLong));
+        for spaced in ["the car", "car\n", "\tcar"] {
+            assert_eq!(
+                validate_query(spaced, &word),
+                Err(QueryError::ContainsWhitespace)
+            );
+        }
+        assert_eq!(
+            validate_query("don't", &word),
+            Err(QueryError::DisallowedChar('\''))
+        );
+        assert_eq!(
+            validate_query("c4r", &word),
+            Err(QueryError::DisallowedChar('4'))
+        );
+        // non ascii letters are letters
+        assert_eq!(validate_query("café", &word), Ok(()));
+
+        let apostrophes = QueryLimits {
+            allowed: |c| c.is_alphabetic() || c == '\'',
+            ..QueryLimits::default()
+        };
+        assert_eq!(validate_query("don't", &apostrophes), Ok(()));
+        assert_eq!(
+            validate_query("don't-care", &apostrophes),
+            Err(QueryError::DisallowedChar('-'))
+        );
+
+        // refused queries aren't searched, the raw search takes them
+        assert_eq!(
+            TREE.checked_corrections("cars", 1, &word).map(|c| c.len()),
+            Ok(3)
+        );
+        assert_eq!(
+            TREE.checked_corrections("car ", 1, &word),
+            Err(QueryError::ContainsWhitespace)
+        );
+        assert!(!TREE.corrections("car ", 1).is_empty());
+        assert_eq!(
+            TREE.checked_best_match("car", 0, &word)
+                .map(|s| s.map(|s| s.word)),
+            Ok(Some("car"))
+        );
+        assert_eq!(
+            TREE.checked_best_match("", 1, &word),
+            Err(QueryError::Empty)
+        );
+    }
+
+    #[test]
+    fn ambiguous() {
+        use super::AmbiguousQuery;
+        let words = |query: &[&[char]], tolerance| {
+            let mut words: Vec<_> = TREE
+                .ambiguous_matches(AmbiguousQuery(query), tolerance)
+                .iter()
+                .map(|s| (s.word, s.distance))
+                .collect();
+            words.sort();
+            words
+        };
+        // both readings of the middle position
+        assert_eq!(
+            words(&[&['c'], &['a', 'o'], &['r', 'k'], &['e']], 0),
+            [("cake", 0), ("care", 0)]
+        );
+        assert_eq!(
+            words(&[&['t', 's'], &['h'], &['e']], 0),
+            [("she", 0), ("the", 0)]
+        );
+        assert_eq!(
+            words(&[&['t', 's'], &['h'], &['e']], 1),
+            [("he", 1), ("she", 0), ("the", 0), ("them", 1)]
+        );
+        // keypad 2 2 7 3
+        let (abc, pqrs, def): (&[char], &[char], &[char]) =
+            (&['a', 'b', 'c'], &['p', 'q', 'r', 's'], &['d', 'e', 'f']);
+        assert_eq!(words(&[abc, abc, pqrs, def], 0), [("card", 0), ("care", 0)]);
+        // nothing fits an empty position
+        assert_eq!(words(&[&['h'], &[]], 0), []);
+        assert_eq!(words(&[&['h'], &[]], 1), [("he", 1)]);
+        let confidence = TREE.ambiguous_matches(AmbiguousQuery(&[&['h'], &['e', 'a']]), 0);
+        assert_eq!(confidence[0].confidence, 255);
+    }
+
+    #[test]
+    fn stream_corrector() {
+        use super::{StreamCorrector, WordEvent};
+        let mut stream = StreamCorrector::<8>::new(&TREE, 1);
+        let mut events = Vec::new();
+        let mut record = |e: Option<WordEvent>| {
+            if let Some(e) = e {
+                events.push((String::from(e.word), e.exact, e.suggestion, e.overflow));
+            }
+        };
+        for b in b"the cars,  she\r\nhe\tcaek cardcardcard ca'r. cakes" {
+            record(stream.push(*b as char));
+        }
+        record(stream.finish());
+        record(stream.finish());
+        let event =
+            |w: &str, exact, suggestion, overflow| (String::from(w), exact, suggestion, overflow);
+        assert_eq!(
+            events,
+            [
+                event("the", true, None, false),
+                event("cars", false, Some("car"), false),
+                event("she", true, None, false),
+                event("he", true, None, false),
+                event("caek", false, None, false),
+                event("cardcard", false, None, true),
+                event("ca'r", false, Some("car"), false),
+                event("cakes", false, Some("cake"), false),
+            ]
+        );
+        // the buffer is fine after an overflow
+        assert_eq!(stream.push('h'), None);
+        assert_eq!(stream.push('e').map(|e| e.word), None);
+        assert!(stream.push(' ').is_some_and(|e| e.exact));
+    }
+
+    #[test]
+    fn node_indexes() {
+        for (i, node) in TREE.iter().enumerate() {
+            assert_eq!(node.index() as usize, i);
+        }
+        assert_eq!(TREE.index(), 0);
     }
 
-    pub struct NodeIterator {
-        stack: Vec<(u8, &'static Node)>,
-        first: bool,
+    #[test]
+    fn node_indexes_past_u16() {
+        use std::format;
+
+        let words: Vec<_> = (1..70_000).map(|i| format!("w{i}")).collect();
+        let words = ["the"].into_iter().chain(words.iter().map(|w| w.as_str()));
+        let builder = super::BkTreeBuilder::new(words);
+        assert_eq!(builder.word_list().len(), 70_000);
+        let source = builder.try_build().unwrap();
+        assert!(source.contains(", 69999, "));
+        // the word table's indexes stay u16
+        assert_eq!(
+            builder.word_table(true).try_build(),
+            Err(super::WriteError::TooManyWords { count: 70_000 })
+        );
     }
-    impl NodeIterator {
-        fn new(node: &'static Node) -> Self {
-            let stack = vec![(0, node)];
-            Self { stack, first: true }
+
+    #[test]
+    fn nth_word() {
+        let words: Vec<_> = TREE.iter().map(|n| n.word()).collect();
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(TREE.nth(i).map(|n| n.word()), Some(*word));
+        }
+        assert_eq!(TREE.nth(WORD_COUNT), None);
+        assert_eq!(TREE.nth(usize::MAX), None);
+        let page: Vec<_> = TREE.range(2..5).map(|n| n.word()).collect();
+        assert_eq!(page, words[2..5]);
+        assert_eq!(TREE.range(WORD_COUNT - 1..WORD_COUNT + 3).count(), 1);
+        // a subtree counts from itself
+        let (_, child) = TREE.children().next().unwrap();
+        let subtree: Vec<_> = child.iter().collect();
+        for (i, node) in subtree.iter().enumerate() {
+            assert_eq!(child.nth(i), Some(*node));
         }
+        assert_eq!(child.nth(subtree.len()), None);
+    }
+
+    #[test]
+    fn nth_unnumbered() {
+        // built by hand, every index 0
+        static B: Node = Node::new("b", 0, 0, &[], &[]);
+        static C: Node = Node::new("c", 0, 0, &[], &[]);
+        static ROOT: Node = Node::new("a", 0, 0, &[1, 2], &[&B, &C]);
+        let words: Vec<_> = ROOT.iter().map(|n| n.word()).collect();
+        let nth: Vec<_> = (0..4).map(|i| ROOT.nth(i).map(|n| n.word())).collect();
+        assert_eq!(nth, [Some(words[0]), Some(words[1]), Some(words[2]), None]);
     }
-    impl Iterator for NodeIterator {
-        type Item = &'static Node;
 
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.first {
-                self.first = false;
-                return Some(self.stack.first().unwrap().1);
+    #[test]
+    fn length_stats() {
+        use super::{suggested_tolerance, LengthStats};
+        use std::format;
+        let stats = TREE.length_stats();
+        let mut histogram = [0; 32];
+        histogram[2..5].copy_from_slice(&[1, 3, 4]);
+        assert_eq!(
+            stats,
+            LengthStats {
+                min: 2,
+                max: 4,
+                histogram
             }
-            loop {
-                for (i, node) in self
-                    .stack
-                    .last()
-                    .unwrap()
-                    .1
-                    .children
+        );
+        // the writer's constant is the same, debug prints it as it's written
+        let source =
+            BkTreeBuilder::new(["the", "them", "she", "he", "car", "care", "card", "cake"])
+                .length_stats(true)
+                .build();
+        assert!(source.contains(&format!("const LENGTH_STATS: LengthStats = {stats:?};")));
+
+        assert!(stats.can_match(1, 1));
+        assert!(!stats.can_match(1, 0));
+        assert!(!stats.can_match(8, 3));
+        assert!(stats.can_match(8, 4));
+        assert_eq!(suggested_tolerance(2, &stats), 0);
+        assert_eq!(suggested_tolerance(3, &stats), 1);
+        assert_eq!(suggested_tolerance(6, &stats), 2);
+        // the longest word is 4, nothing is 2 edits from 7 chars
+        assert_eq!(suggested_tolerance(7, &stats), 0);
+        let mut long = LengthStats::EMPTY;
+        long.add(40);
+        assert_eq!((long.min, long.max, long.histogram[31]), (40, 40, 1));
+        assert!(long.can_match(30, 1) && !long.can_match(29, 1));
+    }
+
+    #[test]
+    fn usage_counters() {
+        use super::UsageCounters;
+        let mut usage = UsageCounters::<WORD_COUNT>::new(&TREE);
+        let words = |usage: &UsageCounters<WORD_COUNT>| -> Vec<_> {
+            usage
+                .corrections("cars", 1)
+                .iter()
+                .map(|s| s.word)
+                .collect()
+        };
+        // car, card and care are all 1 away
+        assert_eq!(usage.best_match("cars", 1).unwrap().word, "car");
+        assert_eq!(words(&usage), ["car", "care", "card"]);
+        assert!(usage.bump("card"));
+        assert!(!usage.bump("cars"));
+        assert_eq!(usage.count("card"), 1);
+        assert_eq!(usage.best_match("cars", 1).unwrap().word, "card");
+        assert_eq!(words(&usage), ["card", "car", "care"]);
+        // use never beats distance
+        assert_eq!(usage.best_match("car", 1).unwrap().word, "car");
+        usage.bump("care");
+        usage.bump("care");
+        assert_eq!(words(&usage), ["care", "card", "car"]);
+        usage.decay();
+        assert_eq!((usage.count("care"), usage.count("card")), (1, 0));
+        for _ in 0..70_000 {
+            usage.bump("the");
+        }
+        assert_eq!(usage.count("the"), u16::MAX);
+        usage.clear();
+        assert_eq!(words(&usage), ["car", "care", "card"]);
+    }
+
+    #[test]
+    fn runtime_tree() {
+        use super::RuntimeBkTree;
+        let mut tree = RuntimeBkTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.best_match("the", 3).map(|s| s.word), None);
+        let words = generated_words(400);
+        for word in &words {
+            tree.insert(word.as_str());
+        }
+        let mut unique = words.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(tree.len(), unique.len());
+        assert!(!tree.insert("the"));
+        assert!(!tree.insert(""));
+        // further apart than the generated tree's 15 buckets allow
+        let far = "zzzzzzzzzzzzzzzzzzzzzz";
+        assert!(tree.insert(String::from(far)));
+        assert!(tree.contains(far));
+        assert_eq!(tree.words().next(), Some("the"));
+
+        let queries = generated_words(500).into_iter().skip(350);
+        for query in queries.chain([String::new(), String::from("zzzzzzzzzzzzzzzzzzzzz")]) {
+            for tolerance in [0, 1, 2, 3] {
+                let mut found: Vec<_> = tree
+                    .corrections(&query, tolerance)
                     .iter()
-                    .rev()
-                    .filter(|n| n.is_some())
-                    .skip(self.stack.last().unwrap().0 as usize)
-                    .enumerate()
-                {
-                    if let Some(node) = node {
-                        self.stack.push((i as u8, node));
-                        return Some(node);
-                    }
-                }
+                    .map(|s| (s.distance, s.word))
+                    .collect();
+                let mut expected: Vec<_> = tree
+                    .words()
+                    .map(|w| (distance(&query, w) as u8, w))
+                    .filter(|(d, _)| *d <= tolerance)
+                    .collect();
+                // sorted by distance already, the order within one isn't
+                assert!(found.is_sorted_by_key(|(d, _)| *d));
+                found.sort();
+                expected.sort();
+                assert_eq!(found, expected, "{query} {tolerance}");
+                let best = tree.best_match(&query, tolerance).map(|s| s.distance);
+                assert_eq!(best, expected.first().map(|(d, _)| *d));
+            }
+            assert_eq!(
+                tree.contains(&query),
+                unique.contains(&query) || query == far
+            );
+        }
+    }
+
+    #[test]
+    fn runtime_bulk_load() {
+        use super::{LoadStats, RuntimeBkTree};
+        let words = generated_words(10_000);
+        let mut unique = words.clone();
+        unique.sort();
+        unique.dedup();
+        let tree: RuntimeBkTree = words.iter().cloned().collect();
+        assert_eq!(tree.len(), unique.len());
+        assert_eq!(tree.words().next(), Some("the"));
+        for word in words.iter().step_by(97) {
+            assert!(tree.contains(word));
+        }
+        assert!(!tree.contains("zzz"));
+
+        let mut tree = RuntimeBkTree::new();
+        let stats = tree.bulk_load(["", "car", "card", "car", "the"]);
+        assert_eq!(
+            stats,
+            LoadStats {
+                inserted: 3,
+                skipped: 2
+            }
+        );
+        assert_eq!(tree.words().next(), Some("car"));
+        let stats = tree.bulk_load(vec!["the", "cake", "cake"]);
+        assert_eq!(
+            stats,
+            LoadStats {
+                inserted: 1,
+                skipped: 2
+            }
+        );
+        tree.extend(["he", "she"].map(String::from));
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.corrections("cars", 1).len(), 2);
+        assert_eq!(RuntimeBkTree::from_iter(Vec::<String>::new()).len(), 0);
+    }
+
+    #[test]
+    fn runtime_remove() {
+        use super::RuntimeBkTree;
+        let mut tree = RuntimeBkTree::new();
+        assert!(!tree.remove("the"));
+        let mut tree: RuntimeBkTree = ["the", "them", "she", "he", "car", "care", "card", "cake"]
+            .into_iter()
+            .collect();
+        let words = |tree: &RuntimeBkTree, query| {
+            let mut words: Vec<String> = tree
+                .corrections(query, 1)
+                .iter()
+                .map(|s| s.word.into())
+                .collect();
+            words.sort();
+            words
+        };
+        assert_eq!(words(&tree, "cars"), ["car", "card", "care"]);
+        assert!(tree.remove("care"));
+        assert!(!tree.remove("care"));
+        assert!(!tree.remove("cars"));
+        assert_eq!(words(&tree, "cars"), ["car", "card"]);
+        assert!(!tree.contains("care"));
+        assert_eq!(tree.best_match("care", 0).map(|s| s.word), None);
+        // what was stored below it is still found
+        assert!(tree.contains("card") && tree.contains("cake"));
+        assert_eq!((tree.len(), tree.tombstones()), (7, 1));
+
+        // the root
+        assert!(tree.remove("the"));
+        assert!(!tree.contains("the"));
+        assert_eq!(words(&tree, "the"), ["he", "she", "them"]);
+        assert!(!tree.words().any(|w| w == "the" || w == "care"));
+        assert_eq!((tree.len(), tree.tombstones()), (6, 2));
+
+        // added back in place
+        assert!(tree.insert("care"));
+        assert_eq!((tree.len(), tree.tombstones()), (7, 1));
+
+        tree.rebuild();
+        assert_eq!(tree.tombstones(), 0);
+        assert_eq!(tree.words().next(), Some("he"));
+        let mut all: Vec<_> = tree.words().collect();
+        all.sort();
+        assert_eq!(all, ["cake", "car", "card", "care", "he", "she", "them"]);
+        assert_eq!(words(&tree, "cars"), ["car", "card", "care"]);
+        assert!(!tree.contains("the"));
+        assert!(tree.insert("the"));
+        tree.words().for_each(|w| assert!(tree.contains(w)));
+    }
+
+    #[test]
+    fn overlay() {
+        use super::{Overlay, RuntimeBkTree};
+        let words = |overlay: &Overlay, query| -> Vec<(String, u8)> {
+            let corrections = overlay.corrections(query, 1);
+            corrections
+                .iter()
+                .map(|s| (s.word.into(), s.distance))
+                .collect()
+        };
+        let owned = |pairs: &[(&str, u8)]| -> Vec<(String, u8)> {
+            pairs.iter().map(|(w, d)| (String::from(*w), *d)).collect()
+        };
+
+        let plain = Overlay::new(&TREE, None);
+        assert_eq!(
+            words(&plain, "cars"),
+            owned(&[("car", 1), ("care", 1), ("card", 1)])
+        );
+        assert_eq!(plain.autocorrect("cars", 1), Some("car"));
 
-                // made it through children and are back up to root
-                self.stack.pop();
+        // "cart" ties the static words, "card" is in both
+        let user: RuntimeBkTree = ["cart", "card", "zebra"].into_iter().collect();
+        let overlay = Overlay::new(&TREE, Some(&user));
+        assert_eq!(
+            words(&overlay, "cars"),
+            owned(&[("cart", 1), ("card", 1), ("car", 1), ("care", 1)])
+        );
+        assert_eq!(overlay.best_match("cars", 1).map(|s| s.word), Some("cart"));
+        assert_eq!(overlay.autocorrect("cars", 1), Some("cart"));
+        // a closer static word still wins
+        assert_eq!(overlay.best_match("cake", 1).map(|s| s.word), Some("cake"));
+        assert_eq!(overlay.best_match("zebr", 1).map(|s| s.word), Some("zebra"));
+        assert_eq!(overlay.best_match("qqqq", 1).map(|s| s.word), None);
+        assert!(overlay.contains("zebra") && overlay.contains("the"));
+        assert!(!overlay.contains("cars"));
+        assert_eq!(overlay.autocorrect("zebra", 1), None);
+        assert_eq!(overlay.autocorrect("the", 1), None);
+    }
 
-                match self.stack.pop() {
-                    Some(last) => self.stack.push((last.0 + 1, last.1)),
-                    None => return None,
+    #[test]
+    fn runtime_from_static() {
+        use super::RuntimeBkTree;
+        let mut tree = RuntimeBkTree::from_static(&TREE);
+        assert_eq!(tree.len(), WORD_COUNT);
+        assert_eq!(tree.words().next(), Some("the"));
+        let pairs = |s: &[Suggestion]| -> Vec<(String, u8)> {
+            s.iter().map(|s| (s.word.into(), s.distance)).collect()
+        };
+        for query in ["cars", "the", "he", "cak", "", "zzzz", "shed"] {
+            for tolerance in [0, 1, 2, 3] {
+                // same shape, so the same order too
+                assert_eq!(
+                    pairs(&tree.corrections(query, tolerance)),
+                    pairs(&TREE.corrections(query, tolerance))
+                );
+                // and the same nodes visited, only the leaf checks differ.
+                // tolerance 0 on a generated tree takes the exact path
+                if tolerance > 0 {
+                    assert_eq!(
+                        tree.search_stats(query, tolerance).nodes_visited,
+                        TREE.search_stats(query, tolerance).nodes_visited
+                    );
                 }
             }
         }
+        assert!(tree.insert("cart"));
+        assert!(!tree.insert("card"));
+        assert!(tree.corrections("cars", 1).iter().any(|s| s.word == "cart"));
+        assert!(tree.remove("the"));
+        assert!(!tree.contains("the") && tree.contains("them"));
     }
-}
 
-#[cfg(feature = "test")]
-#[cfg(test)]
-mod test {
-    use super::{write, Node};
-    use std::{path::Path, println, vec};
+    #[cfg(feature = "serde")]
+    #[test]
+    fn runtime_serde() {
+        use super::RuntimeBkTree;
+        use std::string::ToString;
+        // what someone editing the config by hand would write
+        let json = r#"{
+            "root": "car",
+            "words": ["card", "care", "cake", "shed"]
+        }"#;
+        let tree: RuntimeBkTree = serde_json::from_str(json).unwrap();
+        assert_eq!(tree.len(), 5);
+        assert_eq!(
+            tree.words().collect::<Vec<_>>(),
+            ["car", "card", "care", "cake", "shed"]
+        );
+        assert_eq!(tree.best_match("shad", 1).map(|s| s.word), Some("shed"));
 
-    include!("../tree.test");
+        let round_trip = serde_json::to_string(&tree).unwrap();
+        assert_eq!(
+            round_trip,
+            r#"{"root":"car","words":["card","care","cake","shed"]}"#
+        );
+        let again: RuntimeBkTree = serde_json::from_str(&round_trip).unwrap();
+        assert_eq!(
+            again.words().collect::<Vec<_>>(),
+            tree.words().collect::<Vec<_>>()
+        );
+
+        // removed words aren't written out
+        let mut edited = again.clone();
+        edited.remove("car");
+        let json = serde_json::to_string(&edited).unwrap();
+        assert_eq!(json, r#"{"root":"card","words":["care","cake","shed"]}"#);
+        // two exports diff on their words
+        let exported: RuntimeBkTree = serde_json::from_str(&json).unwrap();
+        let diff = super::diff_word_lists(again.words(), exported.words());
+        assert_eq!(
+            (diff.added.len(), diff.removed),
+            (0, vec!["car".to_string()])
+        );
+
+        let empty = serde_json::to_string(&RuntimeBkTree::new()).unwrap();
+        assert_eq!(empty, r#"{"root":null,"words":[]}"#);
+        let tree: RuntimeBkTree = serde_json::from_str(&empty).unwrap();
+        assert!(tree.is_empty());
+
+        let error = |json| {
+            serde_json::from_str::<RuntimeBkTree>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(error(r#"{"root":"car","words":["card","car"]}"#)
+            .starts_with(r#"duplicate word "car" at words[1]"#));
+        assert!(
+            error(r#"{"root":"car","words":["card",""]}"#).starts_with("empty word at words[1]")
+        );
+        assert!(error(r#"{"root":"","words":[]}"#).starts_with("empty root word"));
+        assert!(error(r#"{"root":null,"words":["car"]}"#).starts_with("words without a root"));
+        assert!(error(r#"{"root":"car","children":[]}"#).contains("unknown field"));
+    }
 
     #[test]
-    fn write_bktree() {
-        let path = Path::new(".").join("tree.test");
-        let word_list = &mut vec!["the", "them", "she", "he", "car", "care", "card", "cake"];
-        write::write_bktree(Some(path), word_list);
+    fn shared_tree() {
+        use super::{RuntimeBkTree, SharedBkTree};
+        use std::thread;
+        let shared = SharedBkTree::from(RuntimeBkTree::from_static(&TREE));
+        let words = generated_words(2000);
+        let writers = 4;
+        thread::scope(|scope| {
+            for chunk in words.chunks(words.len() / writers) {
+                let shared = &shared;
+                scope.spawn(move || {
+                    for word in chunk {
+                        shared.insert(word.as_str());
+                        // a word is searchable as soon as insert returns
+                        assert!(shared.contains(word));
+                    }
+                });
+            }
+            for reader in 0..4 {
+                let (shared, words) = (&shared, &words);
+                scope.spawn(move || {
+                    for word in words.iter().skip(reader).step_by(7) {
+                        let found = shared.corrections(word, 1);
+                        let tree = shared.read();
+                        assert!(found.iter().all(|(w, _)| tree.contains(w)));
+                        drop(tree);
+                        assert!(shared.best_match("cars", 1).is_some());
+                    }
+                });
+            }
+        });
+        let mut unique: Vec<&str> = words
+            .iter()
+            .map(|w| w.as_str())
+            .chain(TREE.iter().map(|n| n.word()))
+            .collect();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(shared.len(), unique.len());
+        let tree = shared.into_inner();
+        assert!(unique.iter().all(|w| tree.contains(w)));
+    }
+
+    #[test]
+    fn runtime_optimize() {
+        use super::RuntimeBkTree;
+        // each word next to the closest one left, like related words
+        // arriving together
+        let mut left = generated_words(600);
+        left.sort();
+        left.dedup();
+        let mut path = vec![left.swap_remove(0)];
+        while !left.is_empty() {
+            let last = path.last().unwrap();
+            let next = (0..left.len())
+                .min_by_key(|&i| distance(last, &left[i]))
+                .unwrap();
+            path.push(left.swap_remove(next));
+        }
+        let mut tree = RuntimeBkTree::new();
+        for word in &path {
+            tree.insert(word.as_str());
+        }
+        tree.remove(&path[0]);
+        tree.remove(&path[10]);
+        let before = tree.stats();
+        assert_eq!((before.nodes, before.tombstones), (path.len(), 2));
+
+        let sorted = |tree: &RuntimeBkTree, query: &str, tolerance| {
+            let mut found: Vec<(u8, String)> = tree
+                .corrections(query, tolerance)
+                .iter()
+                .map(|s| (s.distance, s.word.into()))
+                .collect();
+            found.sort();
+            found
+        };
+        let queries = generated_words(700);
+        let expected: Vec<_> = queries.iter().map(|q| sorted(&tree, q, 2)).collect();
+
+        tree.optimize();
+        let after = tree.stats();
+        assert!(after.depth < before.depth, "{before:?} {after:?}");
+        assert_eq!((after.nodes, after.tombstones), (path.len() - 2, 0));
+        assert_eq!(tree.len(), path.len() - 2);
+        for (query, expected) in queries.iter().zip(expected) {
+            assert_eq!(sorted(&tree, query, 2), expected);
+        }
+        assert!(!tree.contains(&path[10]) && tree.contains(&path[11]));
+        // still a working tree
+        assert!(tree.insert("zzzz") && tree.contains("zzzz"));
+
+        let mut empty = RuntimeBkTree::new();
+        empty.optimize();
+        assert_eq!(empty.stats(), Default::default());
+    }
+
+    #[test]
+    fn lazy_tree() {
+        use super::{LazyBkTree, LazyError};
+        use std::thread;
+        static DICT: LazyBkTree =
+            LazyBkTree::from_lines("the\nthem\r\n  she\n\nhe\ncar\ncare\ncard\ncake\n");
+        // both threads get the one tree built
+        let trees = thread::scope(|scope| {
+            let a = scope.spawn(|| DICT.get().unwrap() as *const _ as usize);
+            let b = scope.spawn(|| DICT.get().unwrap() as *const _ as usize);
+            [a.join().unwrap(), b.join().unwrap()]
+        });
+        assert_eq!(trees[0], trees[1]);
+        assert_eq!(DICT.get().unwrap() as *const _ as usize, trees[0]);
+        assert_eq!(DICT.get().unwrap().len(), 8);
+        assert_eq!(DICT.contains("she"), Ok(true));
+        assert_eq!(
+            DICT.best_match("cakes", 1).unwrap().map(|s| s.word),
+            Some("cake")
+        );
+        assert_eq!(DICT.corrections("cars", 1).unwrap().len(), 3);
+
+        static BROKEN: LazyBkTree = LazyBkTree::from_lines("the\ncar\n the \n");
+        let error = LazyError::Duplicate {
+            line: 3,
+            word: "the",
+        };
+        assert_eq!(BROKEN.get().unwrap_err(), &error);
+        assert_eq!(BROKEN.contains("car"), Err(&error));
+        static EMPTY: LazyBkTree = LazyBkTree::from_lines("\n  \n");
+        assert_eq!(EMPTY.best_match("car", 1).unwrap_err(), &LazyError::Empty);
+    }
+
+    #[test]
+    fn confidence() {
+        use super::Suggestion;
+        let score = |query, word, distance| Suggestion::new(query, word, distance).confidence;
+        assert_eq!(score("the", "the", 0), 255);
+        assert_eq!(score("teh", "the", 2), 85);
+        assert_eq!(score("he", "the", 1), 170);
+        assert_eq!(score("car", "card", 1), 191);
+        assert_eq!(score("cake", "care", 1), 191);
+        assert_eq!(score("abc", "xyz", 3), 0);
+        assert_eq!(score("naïve", "naive", 1), 204);
+        // degenerate inputs
+        assert_eq!(score("", "", 0), 255);
+        assert_eq!(score("", "he", 2), 0);
+        assert_eq!(score("a", "b", 9), 0);
+        // searches fill it in
+        let found = TREE.corrections("cars", 1);
+        assert!(found
+            .iter()
+            .all(|s| s.confidence == score("cars", s.word, s.distance)));
+        assert_eq!(TREE.best_match("the", 1).map(|s| s.confidence), Some(255));
     }
 
+    // what would wrap with a 16 bit usize, checked with the host's
     #[test]
-    fn canidates() {
-        assert!(TREE.canidates("shes", 1).contains(&"she"));
-        assert!(TREE.canidates("cars", 1).contains(&"car"));
+    fn sixteen_bit() {
+        use super::{BlobError, BlobStorage, NodeBuf, NodeStorage, Suggestion};
+
+        // 255 * 300 is past u16::MAX
+        let (long, longer) = ("a".repeat(300), "a".repeat(299) + "b");
+        assert_eq!(Suggestion::new(&long, &longer, 1).confidence, 254);
+        assert_eq!(Suggestion::new(&long, "", 150).confidence, 127);
+
+        // a count this big used to put node 2^30's offset back at node 0's
+        let words = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+        let mut blob = BkTreeBuilder::new(words).build_storage();
+        blob[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut storage = BlobStorage::new(|offset: u32, buf: &mut [u8]| {
+            let bytes = blob.get(offset as usize..offset as usize + buf.len());
+            buf.copy_from_slice(bytes.ok_or(())?);
+            Ok::<_, ()>(())
+        });
+        let mut buf = NodeBuf::new();
+        assert_eq!(storage.read_node(0, &mut buf), Ok(()));
+        assert_eq!(
+            storage.read_node(1 << 30, &mut buf),
+            Err(BlobError::Corrupt { id: 1 << 30 })
+        );
+        assert_eq!(
+            storage.read_node(u32::MAX - 1, &mut buf),
+            Err(BlobError::Corrupt { id: u32::MAX - 1 })
+        );
+    }
+
+    #[cfg(feature = "confusables")]
+    #[test]
+    fn confusables() {
+        use super::{fold_confusable, fold_confusables, fold_confusables_string, BufTooSmall};
+
+        // a cyrillic а in the middle
+        let query = "c\u{430}r";
+        assert_eq!(TREE.best_match(query, 0), None);
+        assert_eq!(TREE.best_match(query, 1).map(|s| s.distance), Some(1));
+        let mut buf = [0; 16];
+        let folded = fold_confusables(query, &mut buf).unwrap();
+        assert_eq!(folded, "car");
+        assert_eq!(TREE.best_match(folded, 0).map(|s| s.word), Some("car"));
+
+        let mut buf = [0; 32];
+        // fullwidth, greek and the case kept for lowercasing after
+        assert_eq!(
+            fold_confusables("\u{ff43}\u{ff41}\u{ff52}\u{ff44}", &mut buf),
+            Ok("card")
+        );
+        assert_eq!(fold_confusables("\u{3ba}\u{3bf}", &mut buf), Ok("ko"));
+        let upper = fold_confusables("\u{421}\u{410}R", &mut buf).unwrap();
+        assert_eq!((upper, upper.to_lowercase().as_str()), ("CAR", "car"));
+        // anything else as it was
+        assert_eq!(fold_confusables("été ß 日本", &mut buf), Ok("été ß 日本"));
+        assert_eq!(fold_confusable('\u{ff5f}'), '\u{ff5f}');
+        assert_eq!(fold_confusable('\u{ff01}'), '!');
+        // the folded word is never longer
+        assert_eq!(
+            fold_confusables("c\u{430}r", &mut [0; 2]),
+            Err(BufTooSmall { needed: 3 })
+        );
+        assert_eq!(fold_confusables_string("\u{441}\u{430}ke"), "cake");
+
+        // the dictionary on its own
+        let words = ["the", "c\u{430}rd", "card", "\u{441}ake"];
+        let folded = BkTreeBuilder::new(words).fold_confusables(true).build();
+        assert!(folded.contains("\"card\"") && folded.contains("\"cake\""));
+        assert!(folded.contains("const WORD_COUNT: usize = 3;"));
+        let plain = BkTreeBuilder::new(words).build();
+        assert!(plain.contains("const WORD_COUNT: usize = 4;"));
+    }
+
+    #[test]
+    fn apply_case() {
+        use super::{apply_case, apply_case_string, BufTooSmall};
+        let mut buf = [0; 16];
+        assert_eq!(apply_case("Teh", "the", &mut buf), Ok("The"));
+        assert_eq!(apply_case("TEH", "the", &mut buf), Ok("THE"));
+        assert_eq!(apply_case("teh", "the", &mut buf), Ok("the"));
+        assert_eq!(apply_case("tEH", "the", &mut buf), Ok("the"));
+        assert_eq!(apply_case("I", "i", &mut buf), Ok("I"));
+        assert_eq!(apply_case("123", "one", &mut buf), Ok("one"));
+        // uppercase mappings that change length
+        assert_eq!(apply_case("STRASE", "straße", &mut buf), Ok("STRASSE"));
+        assert_eq!(apply_case("Strase", "ßtrase", &mut buf), Ok("SStrase"));
+        assert_eq!(apply_case("ÉTÉ", "été", &mut buf), Ok("ÉTÉ"));
+        assert_eq!(
+            apply_case("STRASE", "straße", &mut [0; 6]),
+            Err(BufTooSmall { needed: 7 })
+        );
+        assert_eq!(apply_case_string("CARS", "care"), "CARE");
+    }
+
+    #[test]
+    fn oracle() {
+        let queries = ["cars", "shes", "", "the", "xyzzy", "carde", "he"];
+        for tolerance in [0, 1, 2, 4] {
+            assert_eq!(verify_searches(&TREE, queries, tolerance), Ok(()));
+        }
+    }
+
+    // nothing in the generated source needs a compiler newer than the
+    // 1.77 `rust-version`
+    #[test]
+    fn msrv_codegen() {
+        let words = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+        let builder = || BkTreeBuilder::new(words);
+        #[allow(unused_mut)]
+        let mut sources = vec![
+            builder().build(),
+            builder().static_wrapper(true).build(),
+            builder().dfs_order(true).build(),
+            builder().progmem(true).build(),
+            builder().dfs_order(true).word_table(true).build(),
+            builder().length_stats(true).build(),
+            builder()
+                .dfs_order(true)
+                .tier("small", 4)
+                .tier("large", 8)
+                .build(),
+        ];
+        #[cfg(feature = "phonetic")]
+        sources.push(builder().phonetic_index(true).build());
+        #[cfg(feature = "inflect")]
+        sources.push(builder().inflections(super::ENGLISH_INFLECTIONS).build());
+        #[cfg(feature = "hamming")]
+        sources.push(BkTreeBuilder::new(["ab12", "ab13"]).hamming(true).build());
+        for source in sources {
+            // inline const blocks are 1.79, raw refs and unsafe attributes
+            // 1.82
+            for newer in ["const {", "&raw ", "#[unsafe(", "unsafe extern"] {
+                assert!(!source.contains(newer), "{newer} in {source}");
+            }
+            // const items and asserts, 1.57
+            assert!(source.contains("const _: () = assert!("));
+        }
+    }
+
+    #[test]
+    fn merged_sources() {
+        use super::{BuildStats, SourceStats};
+
+        let common = ["the", "car", "care", "cake"];
+        let glossary = ["card", "care"];
+        let customer = ["cake", "zed"];
+        let builder = BkTreeBuilder::new([])
+            .source(common, 1)
+            .source(glossary, 2)
+            .source(customer, 3);
+        // "care" keeps the glossary's rank and "cake" the customer's
+        assert_eq!(
+            builder.word_list(),
+            ["cake", "zed", "card", "care", "the", "car"]
+        );
+        let source = |weight, words, kept| SourceStats {
+            weight,
+            words,
+            kept,
+        };
+        assert_eq!(
+            builder.stats(),
+            BuildStats {
+                words: 6,
+                sources: vec![
+                    source(0, 0, 0),
+                    source(1, 4, 2),
+                    source(2, 2, 2),
+                    source(3, 2, 2)
+                ],
+                bloom_bytes: 0,
+                missing_suppressed: vec![],
+                unplaced: vec![],
+            }
+        );
+
+        // equal weights interleave the same in any order
+        let a = ["the", "car", "cake"];
+        let b = ["care", "car", "card"];
+        let ab = BkTreeBuilder::new([]).source(a, 1).source(b, 1);
+        let ba = BkTreeBuilder::new([]).source(b, 1).source(a, 1);
+        assert_eq!(ab.word_list(), ["care", "the", "car", "cake", "card"]);
+        assert_eq!(ab.word_list(), ba.word_list());
+        assert_eq!(ab.build(), ba.build());
+
+        // the words of `new` are weight 0, alone they're the list as given
+        let words = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+        assert_eq!(BkTreeBuilder::new(words).word_list(), words);
+        let path = std::env::temp_dir().join("embedded-bktree-source.txt");
+        std::fs::write(&path, "zed\n\n  the\ncar\n").unwrap();
+        let from_file = BkTreeBuilder::new(["cake", "car"]).source_file(&path, 1);
+        assert_eq!(from_file.word_list(), ["zed", "the", "car", "cake"]);
+        assert_eq!(from_file.stats().sources[0].kept, 1);
+    }
+
+    mod fallible {
+        use super::super::AllocError;
+        use super::TREE;
+        use std::{
+            alloc::{GlobalAlloc, Layout, System},
+            cell::Cell,
+            ptr,
+            vec::Vec,
+        };
+
+        // the system allocator, but a test can cap how many more
+        // allocations its own thread gets
+        struct Failing;
+
+        std::thread_local! {
+            static LEFT: Cell<Option<usize>> = const { Cell::new(None) };
+        }
+
+        fn allowed() -> bool {
+            LEFT.try_with(|left| match left.get() {
+                Some(0) => false,
+                Some(n) => {
+                    left.set(Some(n - 1));
+                    true
+                }
+                None => true,
+            })
+            .unwrap_or(true)
+        }
+
+        unsafe impl GlobalAlloc for Failing {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                match allowed() {
+                    true => unsafe { System.alloc(layout) },
+                    false => ptr::null_mut(),
+                }
+            }
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                unsafe { System.dealloc(ptr, layout) }
+            }
+            unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+                match allowed() {
+                    true => unsafe { System.realloc(ptr, layout, size) },
+                    false => ptr::null_mut(),
+                }
+            }
+        }
+
+        #[global_allocator]
+        static ALLOCATOR: Failing = Failing;
+
+        // runs `f` allowed only `count` allocations
+        fn with_allocations<T>(count: usize, f: impl FnOnce() -> T) -> T {
+            LEFT.with(|left| left.set(Some(count)));
+            let out = f();
+            LEFT.with(|left| left.set(None));
+            out
+        }
+
+        #[test]
+        fn try_corrections() {
+            let expected = TREE.corrections("cars", 1);
+            assert_eq!(expected.len(), 3);
+            // nothing reserved and no allocations left
+            assert_eq!(
+                with_allocations(0, || TREE.try_corrections("cars", 1, 0)),
+                Err(AllocError)
+            );
+            // room for two, the third doesn't fit
+            assert_eq!(
+                with_allocations(1, || TREE.try_corrections("cars", 1, 2)),
+                Err(AllocError)
+            );
+            // a good guess is the only allocation
+            assert_eq!(
+                with_allocations(1, || TREE.try_corrections("cars", 1, 4)),
+                Ok(expected)
+            );
+            for (word, tolerance) in [("cars", 2), ("he", 1), ("xyzzy", 1), ("the", 0)] {
+                assert_eq!(
+                    TREE.try_corrections(word, tolerance, 0),
+                    Ok(TREE.corrections(word, tolerance))
+                );
+            }
+
+            let candidates = TREE.candidates("cars", 1);
+            assert_eq!(
+                with_allocations(0, || TREE.try_candidates("cars", 1, 0)),
+                Err(AllocError)
+            );
+            let capacity = candidates.len();
+            assert_eq!(
+                with_allocations(1, || TREE.try_candidates("cars", 1, capacity)),
+                Ok(candidates)
+            );
+        }
+
+        #[test]
+        fn try_iter() {
+            let expected: Vec<_> = TREE.iter().collect();
+            assert_eq!(
+                with_allocations(0, || TREE.try_iter(1).err()),
+                Some(AllocError)
+            );
+            // the root fits but its first child doesn't, then it stops
+            let mut nodes = with_allocations(1, || TREE.try_iter(1)).unwrap();
+            assert_eq!(with_allocations(0, || nodes.next()), Some(Ok(&TREE)));
+            assert_eq!(with_allocations(0, || nodes.next()), Some(Err(AllocError)));
+            assert_eq!(nodes.next(), None);
+            // deep enough, counted since collecting would allocate too
+            let count = with_allocations(1, || {
+                TREE.try_iter(8)
+                    .unwrap()
+                    .try_fold(0, |count, node| node.map(|_| count + 1))
+            });
+            assert_eq!(count, Ok(expected.len()));
+            let nodes: Result<Vec<_>, _> = TREE.try_iter(8).unwrap().collect();
+            assert_eq!(nodes, Ok(expected));
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod sharded {
+        use super::super::{verify_searches, BkTreeBuilder, Node, WriteError};
+        use super::generated_words;
+        use std::{format, fs, vec::Vec};
+
+        include!("../tree_sharded.test");
+
+        #[test]
+        fn write_sharded() {
+            let words = generated_words(2000);
+            let builder = BkTreeBuilder::new(words.iter().map(|w| w.as_str())).shards(4);
+            builder.write(Some("tree_sharded.test".into()));
+            let root = fs::read_to_string("tree_sharded.test").unwrap();
+            assert!(root.contains("static TREE: Node = Node::new(Node::interned(&TREE_WORDS, "));
+            assert!(root.ends_with("include!(\"tree_sharded_part_3.test\");\n"));
+            let mut statics = 0;
+            for part in 0..4 {
+                let part = fs::read_to_string(format!("tree_sharded_part_{part}.test")).unwrap();
+                assert!(part.starts_with("static TREE_"));
+                statics += part.lines().count();
+            }
+            // every node but the root, which stays with the constants
+            assert_eq!(statics, builder.word_list().len() - 1);
+            // `build` isn't sharded
+            let plain = BkTreeBuilder::new(words.iter().map(|w| w.as_str()));
+            assert!(builder.build() == plain.build());
+
+            // a part per `SHARD_NODES`, so one here
+            let dir = std::env::temp_dir().join("embedded-bktree-sharded");
+            fs::create_dir_all(&dir).unwrap();
+            BkTreeBuilder::new(words.iter().map(|w| w.as_str()))
+                .sharded(true)
+                .write(Some(dir.join("tree.rs")));
+            assert!(dir.join("tree_part_0.rs").exists());
+            assert!(!dir.join("tree_part_1.rs").exists());
+            let tiered = BkTreeBuilder::new(["the"]).tier("small", 1).sharded(true);
+            assert_eq!(
+                tiered.try_write(Some(dir.join("tiered.rs"))),
+                Err(WriteError::Incompatible {
+                    option: "sharded",
+                    with: "tier"
+                })
+            );
+        }
+
+        #[test]
+        fn sharded_search() {
+            const _: () = assert!(WORD_COUNT > 1500);
+            assert_eq!(TREE.iter().count(), WORD_COUNT);
+            assert_eq!(TREE.check_invariants(), Ok(()));
+            let words = generated_words(200);
+            let queries: Vec<_> = words.iter().map(|w| w.as_str()).collect();
+            for tolerance in [0, 1, 2] {
+                assert_eq!(
+                    verify_searches(&TREE, queries.iter().copied(), tolerance),
+                    Ok(())
+                );
+            }
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod bloom {
+        use super::super::{BkTreeBuilder, BloomFilter, Node};
+        use super::generated_words;
+        use std::{format, string::String};
+
+        include!("../tree_bloom.test");
+
+        #[test]
+        fn write_bloom() {
+            let words = generated_words(1000);
+            let builder = BkTreeBuilder::new(words.iter().map(|w| w.as_str())).bloom_filter(0.01);
+            builder.write(Some("tree_bloom.test".into()));
+            assert_eq!(builder.stats().bloom_bytes, BLOOM.bytes());
+            let plain = BkTreeBuilder::new(words.iter().map(|w| w.as_str()));
+            assert_eq!(plain.stats().bloom_bytes, 0);
+            let stricter = plain.bloom_filter(0.001);
+            assert!(stricter.stats().bloom_bytes > BLOOM.bytes());
+        }
+
+        #[test]
+        fn contains_fast() {
+            // about 5 bytes a word at 1%
+            const _: () = assert!(WORD_COUNT > 800);
+            assert!(BLOOM.bytes() < WORD_COUNT * 6);
+            for node in TREE.iter() {
+                assert!(BLOOM.may_contain(node.word()), "{:?}", node.word());
+                assert!(TREE.contains_fast(&BLOOM, node.word()));
+            }
+            let (mut missing, mut passed) = (0, 0);
+            for word in generated_words(1000) {
+                for extra in ["q", "zz", "x"] {
+                    let other: String = format!("{word}{extra}");
+                    assert_eq!(TREE.contains_fast(&BLOOM, &other), TREE.contains(&other));
+                    missing += 1;
+                    passed += BLOOM.may_contain(&other) as usize;
+                }
+            }
+            assert!(passed * 100 < missing * 3, "{passed} of {missing}");
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod suppressed {
+        use super::super::{BkTreeBuilder, Corrections, Node};
+        use super::TREE as PLAIN;
+        use core::ops::ControlFlow;
+        use std::{string::ToString, vec, vec::Vec};
+
+        include!("../tree_suppressed.test");
+
+        const WORDS: [&str; 8] = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+
+        #[test]
+        fn write_suppressed() {
+            let builder = BkTreeBuilder::new(WORDS).suppress(["them", "zorp", "care"]);
+            builder.write(Some("tree_suppressed.test".into()));
+            assert_eq!(builder.stats().missing_suppressed, ["zorp"]);
+            assert_eq!(builder.build().matches(".suppress()").count(), 2);
+            let plain = BkTreeBuilder::new(WORDS);
+            assert!(plain.stats().missing_suppressed.is_empty());
+            assert!(!plain.build().contains(".suppress()"));
+        }
+
+        #[test]
+        fn suppressed_search() {
+            let words = |suggestions: Vec<_>| -> Vec<_> {
+                suggestions
+                    .iter()
+                    .map(|s: &super::Suggestion| s.word)
+                    .collect()
+            };
+            for word in ["them", "care"] {
+                assert!(TREE.contains(word));
+                assert!(TREE.corrections(word, 2).iter().all(|s| s.word != word));
+                assert_eq!(TREE.best_match(word, 0), None);
+                let hatch: &dyn Corrections = &TREE.include_suppressed();
+                assert!(hatch.contains(word));
+                assert_eq!(words(hatch.corrections(word, 0)), [word]);
+            }
+            assert_eq!(words(TREE.corrections("them", 1)), ["the"]);
+            assert_eq!(
+                words(TREE.include_suppressed().corrections("them", 1)),
+                ["them", "the"]
+            );
+            // searches still go through "them" to "she" below it
+            assert_eq!(words(TREE.corrections("shes", 1)), ["she"]);
+            let mut completions = vec![];
+            TREE.for_each_completion("car", |w| {
+                completions.push(w);
+                ControlFlow::Continue(())
+            });
+            assert_eq!(completions, ["car", "card"]);
+            for word in ["cars", "shes", "cake", "xyz", "", "the", "them"] {
+                for tolerance in 0..4 {
+                    let all = TREE.include_suppressed().corrections(word, tolerance);
+                    assert_eq!(all, PLAIN.corrections(word, tolerance));
+                    let shown: Vec<_> = all
+                        .into_iter()
+                        .filter(|s| !["them", "care"].contains(&s.word))
+                        .collect();
+                    assert_eq!(TREE.corrections(word, tolerance), shown);
+                }
+            }
+            let suppressed: Vec<_> = TREE
+                .iter()
+                .filter(|n: &&Node| n.is_suppressed())
+                .map(|n| n.word().to_string())
+                .collect();
+            assert_eq!(suppressed, ["care", "them"]);
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod large {
+        use super::super::{verify_searches, BkTreeBuilder, Node};
+        use super::generated_words;
+        use std::{string::String, vec, vec::Vec};
+
+        include!("../tree_large.test");
+
+        #[test]
+        fn write_large() {
+            let words = generated_words(1000);
+            BkTreeBuilder::new(words.iter().map(|w| w.as_str()))
+                .write(Some("tree_large.test".into()));
+        }
+
+        #[test]
+        fn oracle_large() {
+            const _: () = assert!(WORD_COUNT > 800);
+            assert_eq!(TREE.iter().count(), WORD_COUNT);
+            assert!(TREE.iter().enumerate().all(|(i, n)| TREE.nth(i) == Some(n)));
+            assert_eq!(TREE.check_invariants(), Ok(()));
+            let words = generated_words(300);
+            let queries: Vec<_> = words
+                .iter()
+                .map(|w| w.as_str())
+                .chain(["", "dddd"])
+                .collect();
+            for tolerance in [0, 1, 2, 3] {
+                assert_eq!(
+                    verify_searches(&TREE, queries.iter().copied(), tolerance),
+                    Ok(())
+                );
+            }
+        }
+
+        #[test]
+        fn incremental_large() {
+            use super::super::IncrementalSearcher;
+
+            for tolerance in [0, 1, 2] {
+                let mut searcher = IncrementalSearcher::new(&TREE, tolerance);
+                assert_eq!(searcher.corrections(), TREE.corrections("", tolerance));
+                let (mut incremental, mut fresh) = (0, 0);
+                let mut check = |searcher: &IncrementalSearcher| {
+                    let query = searcher.query();
+                    assert_eq!(searcher.corrections(), TREE.corrections(query, tolerance));
+                    let stats = TREE.search_stats(query, tolerance);
+                    assert!(searcher.stats().nodes_visited >= stats.nodes_visited);
+                    incremental += searcher.stats().distance_calls;
+                    fresh += stats.distance_calls + stats.automaton_runs;
+                };
+                for word in generated_words(20) {
+                    for c in word.chars().chain(['z']) {
+                        searcher.extend(c);
+                        check(&searcher);
+                    }
+                    for _ in 0..3 {
+                        searcher.backspace();
+                        check(&searcher);
+                    }
+                    searcher.reset(&word[1..]);
+                    check(&searcher);
+                    while !searcher.query().is_empty() {
+                        searcher.backspace();
+                        check(&searcher);
+                    }
+                }
+                assert!(incremental < fresh, "{incremental} {fresh}");
+            }
+        }
+
+        #[test]
+        fn completions_large() {
+            use core::ops::ControlFlow;
+
+            let words = generated_words(100);
+            let prefixes = words
+                .iter()
+                .flat_map(|w| (0..=w.len()).map(move |end| &w[..end]))
+                .chain(["zzzz"]);
+            for prefix in prefixes {
+                let mut found = Vec::new();
+                TREE.for_each_completion(prefix, |w| {
+                    found.push(w);
+                    ControlFlow::Continue(())
+                });
+                let mut expected: Vec<_> = TREE
+                    .iter()
+                    .map(|n| n.word())
+                    .filter(|w| w.starts_with(prefix))
+                    .collect();
+                found.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(found, expected, "{prefix:?}");
+            }
+        }
+
+        #[cfg(not(feature = "small-code"))]
+        #[test]
+        fn leaf_checks_large() {
+            use super::super::SearchStats;
+            use crate::{
+                automaton::Automaton,
+                distance::{char_mask, distance},
+                read::{walk_tree, Generated, LeafCheck},
+            };
+            use core::ops::ControlFlow;
+
+            let search = |query: &str, leaf: LeafCheck, tolerance, stats: &mut SearchStats| {
+                let mut found = Vec::new();
+                let _ = walk_tree(
+                    &Generated::default(),
+                    &TREE,
+                    &query,
+                    &|w| distance(w, query),
+                    &leaf,
+                    tolerance,
+                    0,
+                    stats,
+                    &mut |node, distance| {
+                        if distance <= tolerance {
+                            found.push((node.word(), distance));
+                        }
+                        ControlFlow::Continue(())
+                    },
+                );
+                found
+            };
+            let mut plain = SearchStats::default();
+            let mut masked = SearchStats::default();
+            let mut automaton = SearchStats::default();
+            for query in generated_words(1300).iter().skip(900) {
+                for tolerance in [1, 2] {
+                    let expected = search(query, LeafCheck::default(), tolerance, &mut plain);
+                    let mask = LeafCheck {
+                        mask: Some(char_mask(query)),
+                        automaton: None,
+                    };
+                    assert_eq!(search(query, mask, tolerance, &mut masked), expected);
+                    let both = LeafCheck::new(query, tolerance);
+                    assert!(both.automaton.is_some());
+                    assert_eq!(search(query, both, tolerance, &mut automaton), expected);
+                }
+                // the dp past the automaton's tolerances
+                assert!(Automaton::new(query, 3).is_none());
+            }
+            assert_eq!(masked.nodes_visited, plain.nodes_visited);
+            assert_eq!(automaton.nodes_visited, plain.nodes_visited);
+            assert!(masked.distance_calls < plain.distance_calls * 9 / 10);
+            // every leaf the mask doesn't rule out goes to the automaton
+            assert!(automaton.automaton_runs > 0);
+            assert!(automaton.distance_calls < masked.distance_calls * 3 / 4);
+
+            // and the public searches take the same path
+            let stats = TREE.search_stats("cartes", 1);
+            assert!(stats.distance_calls + stats.automaton_runs < stats.nodes_visited);
+        }
+
+        #[cfg(feature = "rkyv")]
+        #[test]
+        fn archived_large() {
+            use super::super::{ArchivedBkTree, Suggestion, ValidationError};
+            use crate::archive::{ArchiveNode, TreeArchive};
+            use rkyv::util::AlignedVec;
+            use std::boxed::Box;
+
+            // somewhere aligned that lives as long as flash would
+            fn leak(bytes: &[u8]) -> &'static [u8] {
+                let mut aligned = AlignedVec::<16>::new();
+                aligned.extend_from_slice(bytes);
+                Box::leak(Box::new(aligned)).as_slice()
+            }
+            let words = generated_words(1000);
+            let bytes = BkTreeBuilder::new(words.iter().map(|w| w.as_str())).build_archive();
+            let archived = ArchivedBkTree::new(leak(&bytes)).unwrap();
+            assert_eq!(archived.len(), WORD_COUNT);
+
+            let pairs = |s: &[Suggestion<'static>]| -> Vec<(&'static str, u8)> {
+                s.iter().map(|s| (s.word, s.distance)).collect()
+            };
+            for query in generated_words(1300)
+                .iter()
+                .skip(900)
+                .chain([&String::new()])
+            {
+                for tolerance in [0, 1, 2] {
+                    // same shape, so the same order too
+                    assert_eq!(
+                        pairs(&archived.corrections(query, tolerance)),
+                        pairs(&TREE.corrections(query, tolerance))
+                    );
+                }
+                assert_eq!(archived.contains(query), TREE.contains(query));
+                assert_eq!(
+                    archived.best_match(query, 2).map(|s| s.word),
+                    TREE.best_match(query, 2).map(|s| s.word)
+                );
+            }
+
+            let malformed = Err(ValidationError::Malformed);
+            assert_eq!(
+                ArchivedBkTree::new(leak(&bytes[..bytes.len() - 5])).map(|_| ()),
+                malformed
+            );
+            assert_eq!(
+                ArchivedBkTree::new(leak(&bytes[1..])).map(|_| ()),
+                malformed
+            );
+            assert_eq!(ArchivedBkTree::new(leak(&[])).map(|_| ()), malformed);
+            let mut corrupt = bytes.clone();
+            let at = corrupt.len() - 12;
+            corrupt[at..].fill(0xff);
+            assert_eq!(ArchivedBkTree::new(leak(&corrupt)).map(|_| ()), malformed);
+
+            let archive =
+                |nodes| rkyv::to_bytes::<rkyv::rancor::Error>(&TreeArchive { nodes }).unwrap();
+            let node = |word: &str, children: Vec<(u8, u32)>| ArchiveNode {
+                word: word.into(),
+                children,
+            };
+            let empty = archive(Vec::new());
+            assert_eq!(
+                ArchivedBkTree::new(leak(&empty)).map(|_| ()),
+                Err(ValidationError::Empty)
+            );
+            let looped = archive(vec![node("the", vec![(1, 1)]), node("he", vec![(1, 0)])]);
+            assert_eq!(
+                ArchivedBkTree::new(leak(&looped)).map(|_| ()),
+                Err(ValidationError::BadChild { node: 1 })
+            );
+            let dangling = archive(vec![node("the", vec![(1, 7)])]);
+            assert_eq!(
+                ArchivedBkTree::new(leak(&dangling)).map(|_| ()),
+                Err(ValidationError::BadChild { node: 0 })
+            );
+        }
+
+        #[test]
+        fn storage_large() {
+            use super::super::{
+                corrections_in_storage, BlobError, BlobStorage, NodeBuf, NodeStorage, StorageError,
+                StoredMatch,
+            };
+
+            let words = generated_words(1000);
+            let blob = BkTreeBuilder::new(words.iter().map(|w| w.as_str())).build_storage();
+            let by_id: Vec<_> = TREE.iter().map(|n| n.word()).collect();
+            let read = |offset: u32, buf: &mut [u8]| {
+                let bytes = blob.get(offset as usize..offset as usize + buf.len());
+                buf.copy_from_slice(bytes.ok_or("out of range")?);
+                Ok(())
+            };
+            let mut storage = BlobStorage::new(read);
+            let mut out = [StoredMatch { id: 0, distance: 0 }; 4];
+            for query in generated_words(1300)
+                .iter()
+                .skip(900)
+                .chain([&String::new()])
+            {
+                for tolerance in [0, 1, 2] {
+                    let filled = corrections_in_storage::<_, 64>(
+                        &mut storage,
+                        0,
+                        query,
+                        tolerance,
+                        &mut out,
+                    )
+                    .unwrap();
+                    let (best, len) = TREE.best_k_into::<4>(query, tolerance);
+                    let found: Vec<_> = out[..filled]
+                        .iter()
+                        .map(|m| (by_id[m.id as usize], m.distance))
+                        .collect();
+                    let expected: Vec<_> = best[..len].iter().flatten().copied().collect();
+                    assert_eq!(found, expected);
+                }
+            }
+
+            let mut buf = NodeBuf::new();
+            storage.read_node(0, &mut buf).unwrap();
+            assert_eq!(buf.word(), TREE.word());
+            assert_eq!(
+                storage.read_node(by_id.len() as u32, &mut buf),
+                Err(BlobError::Corrupt {
+                    id: by_id.len() as u32
+                })
+            );
+            // too few ids can wait to be read for a wide search
+            assert_eq!(
+                corrections_in_storage::<_, 2>(&mut storage, 0, "dddd", 3, &mut out),
+                Err(StorageError::TooDeep)
+            );
+
+            // errors from the storage come back out of the search
+            let mut reads = 0;
+            let mut failing = BlobStorage::new(|offset, buf: &mut [u8]| {
+                reads += 1;
+                match reads {
+                    7 => Err("bus error"),
+                    _ => read(offset, buf),
+                }
+            });
+            assert_eq!(
+                corrections_in_storage::<_, 64>(&mut failing, 0, "dddd", 2, &mut out),
+                Err(StorageError::Storage(BlobError::Read("bus error")))
+            );
+
+            // a record whose word isn't utf-8
+            let mut corrupt = blob.clone();
+            let root = u32::from_le_bytes(corrupt[4..8].try_into().unwrap()) as usize;
+            corrupt[root + 1] = 0xff;
+            let mut storage = BlobStorage::new(|offset: u32, buf: &mut [u8]| {
+                buf.copy_from_slice(&corrupt[offset as usize..offset as usize + buf.len()]);
+                Ok::<_, ()>(())
+            });
+            assert_eq!(
+                corrections_in_storage::<_, 64>(&mut storage, 0, "the", 1, &mut out),
+                Err(StorageError::Storage(BlobError::Corrupt { id: 0 }))
+            );
+
+            // children that loop back instead of ending the search
+            let blob = |nodes: &[(&str, &[(u8, u32)])]| {
+                let records: Vec<Vec<u8>> = nodes
+                    .iter()
+                    .map(|(word, children)| {
+                        let mut record = vec![word.len() as u8];
+                        record.extend(word.bytes());
+                        record.push(children.len() as u8);
+                        for (bucket, id) in children.iter() {
+                            record.push(*bucket);
+                            record.extend(id.to_le_bytes());
+                        }
+                        record
+                    })
+                    .collect();
+                let mut blob = (nodes.len() as u32).to_le_bytes().to_vec();
+                let mut offset = 4 * (nodes.len() as u32 + 2);
+                for record in &records {
+                    blob.extend(offset.to_le_bytes());
+                    offset += record.len() as u32;
+                }
+                blob.extend(offset.to_le_bytes());
+                blob.extend(records.concat());
+                blob
+            };
+            let self_loop = blob(&[("the", &[(1, 1)]), ("he", &[(1, 1)])]);
+            let back_edge = blob(&[("the", &[(1, 1)]), ("he", &[(1, 0)])]);
+            let past_end = blob(&[("the", &[(1, 1)]), ("he", &[(1, 2)])]);
+            let no_bucket = blob(&[("the", &[(1, 1)]), ("he", &[]), ("she", &[(0, 2)])]);
+            for (blob, id) in [(self_loop, 1), (back_edge, 1), (past_end, 1)] {
+                let mut storage = BlobStorage::new(|offset: u32, buf: &mut [u8]| {
+                    buf.copy_from_slice(&blob[offset as usize..offset as usize + buf.len()]);
+                    Ok::<_, ()>(())
+                });
+                assert_eq!(
+                    corrections_in_storage::<_, 64>(&mut storage, 0, "the", 1, &mut out),
+                    Err(StorageError::Storage(BlobError::Corrupt { id }))
+                );
+            }
+            let mut storage = BlobStorage::new(|offset: u32, buf: &mut [u8]| {
+                buf.copy_from_slice(&no_bucket[offset as usize..offset as usize + buf.len()]);
+                Ok::<_, ()>(())
+            });
+            assert_eq!(
+                storage.read_node(2, &mut NodeBuf::new()),
+                Err(BlobError::Corrupt { id: 2 })
+            );
+        }
+
+        #[cfg(feature = "async")]
+        #[test]
+        fn async_storage_large() {
+            use super::super::{
+                corrections_in_async_storage, corrections_in_storage, AsyncNodeStorage, BlobError,
+                BlobStorage, NodeBuf, NodeStorage, StorageError, StoredMatch,
+            };
+            use core::{
+                future::Future,
+                pin::pin,
+                task::{Context, Poll, Waker},
+            };
+
+            // a driver that has to wait once for every read
+            struct Flash<'b> {
+                blob: &'b [u8],
+            }
+            struct Busy(bool);
+            impl Future for Busy {
+                type Output = ();
+                fn poll(mut self: core::pin::Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+                    match core::mem::replace(&mut self.0, true) {
+                        true => Poll::Ready(()),
+                        false => Poll::Pending,
+                    }
+                }
+            }
+            impl AsyncNodeStorage for Flash<'_> {
+                type Error = BlobError<()>;
+                async fn read_node(
+                    &mut self,
+                    id: u32,
+                    buf: &mut NodeBuf,
+                ) -> Result<(), Self::Error> {
+                    Busy(false).await;
+                    let blob = self.blob;
+                    BlobStorage::new(|offset: u32, buf: &mut [u8]| {
+                        let bytes = blob.get(offset as usize..offset as usize + buf.len());
+                        buf.copy_from_slice(bytes.ok_or(())?);
+                        Ok(())
+                    })
+                    .read_node(id, buf)
+                }
+            }
+            fn block_on<F: Future>(future: F) -> F::Output {
+                let mut future = pin!(future);
+                let mut cx = Context::from_waker(Waker::noop());
+                loop {
+                    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                        return output;
+                    }
+                }
+            }
+
+            let words = generated_words(1000);
+            let blob = BkTreeBuilder::new(words.iter().map(|w| w.as_str())).build_storage();
+            let mut sync = BlobStorage::new(|offset: u32, buf: &mut [u8]| {
+                buf.copy_from_slice(&blob[offset as usize..offset as usize + buf.len()]);
+                Ok::<_, ()>(())
+            });
+            let mut flash = Flash { blob: &blob };
+            let none = StoredMatch { id: 0, distance: 0 };
+            for query in generated_words(1100).iter().skip(950) {
+                for tolerance in [0, 1, 2] {
+                    let (mut a, mut b) = ([none; 4], [none; 4]);
+                    let filled = block_on(corrections_in_async_storage::<_, 64>(
+                        &mut flash, 0, query, tolerance, &mut a,
+                    ));
+                    let expected =
+                        corrections_in_storage::<_, 64>(&mut sync, 0, query, tolerance, &mut b);
+                    assert_eq!(filled, expected);
+                    assert_eq!(a, b);
+                }
+            }
+
+            // dropped while waiting on a read, the driver is still usable
+            let mut out = [none; 4];
+            {
+                let mut search = pin!(corrections_in_async_storage::<_, 64>(
+                    &mut flash, 0, "dddd", 2, &mut out
+                ));
+                let mut cx = Context::from_waker(Waker::noop());
+                for _ in 0..5 {
+                    assert!(search.as_mut().poll(&mut cx).is_pending());
+                }
+            }
+            let mut expected = [none; 4];
+            assert_eq!(
+                block_on(corrections_in_async_storage::<_, 64>(
+                    &mut flash, 0, "dddd", 2, &mut out
+                )),
+                corrections_in_storage::<_, 64>(&mut sync, 0, "dddd", 2, &mut expected)
+            );
+            assert_eq!(out, expected);
+            assert_eq!(
+                block_on(corrections_in_async_storage::<_, 0>(
+                    &mut flash, 0, "dddd", 2, &mut out
+                )),
+                Err(StorageError::TooDeep)
+            );
+        }
+
+        #[cfg(feature = "avr-progmem")]
+        #[test]
+        fn progmem_large() {
+            use super::super::{corrections_in_storage, FarRead, ProgmemBlob, StoredMatch};
+
+            // a far pointer the host can still follow, counting the loads
+            struct Lpm(usize);
+            impl FarRead for Lpm {
+                unsafe fn read(&mut self, at: *const u8, buf: &mut [u8]) {
+                    for (i, byte) in buf.iter_mut().enumerate() {
+                        *byte = unsafe { at.add(i).read() };
+                    }
+                    self.0 += buf.len();
+                }
+            }
+            let words = generated_words(1000);
+            let builder = BkTreeBuilder::new(words.iter().map(|w| w.as_str()));
+            let blob = ProgmemBlob::new(builder.build_storage().leak());
+            let by_id: Vec<_> = TREE.iter().map(|n| n.word()).collect();
+            let mut storage = blob.storage(Lpm(0));
+            let mut out = [StoredMatch { id: 0, distance: 0 }; 4];
+            for query in generated_words(1100).iter().skip(950) {
+                let filled =
+                    corrections_in_storage::<_, 64>(&mut storage, 0, query, 2, &mut out).unwrap();
+                let found: Vec<_> = out[..filled]
+                    .iter()
+                    .map(|m| (by_id[m.id as usize], m.distance))
+                    .collect();
+                let (best, len) = TREE.best_k_into::<4>(query, 2);
+                assert_eq!(
+                    found,
+                    best[..len].iter().flatten().copied().collect::<Vec<_>>()
+                );
+            }
+        }
+
+        #[test]
+        fn wildcard_large() {
+            use crate::distance::wildcard_distance;
+            let words = generated_words(100);
+            for (i, word) in words.iter().enumerate() {
+                // blank out one or two positions
+                let pattern: String = word
+                    .chars()
+                    .enumerate()
+                    .map(|(j, c)| if j % 3 == i % 3 { '?' } else { c })
+                    .collect();
+                for tolerance in [0, 1, 2] {
+                    let mut found: Vec<_> = TREE
+                        .wildcard_matches(&pattern, tolerance)
+                        .iter()
+                        .map(|s| s.word)
+                        .collect();
+                    let mut expected: Vec<_> = TREE
+                        .iter()
+                        .map(|n| n.word())
+                        .filter(|w| wildcard_distance(&pattern, w, '?') <= tolerance as usize)
+                        .collect();
+                    found.sort();
+                    expected.sort();
+                    assert_eq!(found, expected, "{pattern} {tolerance}");
+                }
+            }
+        }
+
+        #[test]
+        fn cancelled() {
+            use super::super::{Cancelled, CANCEL_INTERVAL};
+            use std::{
+                sync::atomic::{AtomicBool, Ordering},
+                thread,
+            };
+            static STALE: AtomicBool = AtomicBool::new(false);
+            static STARTED: AtomicBool = AtomicBool::new(false);
+
+            // never asked to stop it's an ordinary search
+            for query in ["the", "cadr", "sintre"] {
+                assert_eq!(
+                    TREE.cancellable_corrections(query, 2, || false),
+                    Ok(TREE.corrections(query, 2))
+                );
+            }
+
+            let escape = thread::spawn(|| {
+                while !STARTED.load(Ordering::Acquire) {
+                    thread::yield_now();
+                }
+                STALE.store(true, Ordering::Release);
+            });
+            // every word is within 8 of "the", a whole search finds them all.
+            // the first check holds the search until the flag is set
+            let result = TREE.cancellable_corrections("the", 8, || {
+                STARTED.store(true, Ordering::Release);
+                while !STALE.load(Ordering::Acquire) {
+                    thread::yield_now();
+                }
+                STALE.load(Ordering::Relaxed)
+            });
+            escape.join().unwrap();
+            let Err(Cancelled { partial }) = result else {
+                panic!("search wasn't cancelled");
+            };
+            // it stopped at the first check
+            assert_eq!(partial.len(), CANCEL_INTERVAL - 1);
+            assert!(partial.len() < WORD_COUNT);
+            let mut visited = 0;
+            let result = TREE.for_each_correction_cancellable(
+                "the",
+                8,
+                || true,
+                |_| {
+                    visited += 1;
+                    core::ops::ControlFlow::Continue(())
+                },
+            );
+            assert_eq!(
+                (result, visited),
+                (Err(Cancelled { partial: () }), CANCEL_INTERVAL - 1)
+            );
+        }
+
+        #[test]
+        fn partial_large() {
+            use crate::distance::partial_distance;
+            let words = generated_words(100);
+            for (i, word) in words.iter().enumerate() {
+                // a piece out of the middle, or the whole word
+                let chars: Vec<_> = word.chars().collect();
+                let start = (i % 3).min(chars.len() - 1);
+                let end = chars.len() - (i % 2).min(chars.len() - start - 1);
+                let query: String = chars[start..end].iter().collect();
+                for tolerance in [0, 1, 2] {
+                    let mut found: Vec<_> = TREE
+                        .partial_corrections(&query, tolerance)
+                        .iter()
+                        .map(|s| s.word)
+                        .collect();
+                    let mut expected: Vec<_> = TREE
+                        .iter()
+                        .map(|n| n.word())
+                        .filter(|w| partial_distance(&query, w) <= tolerance as usize)
+                        .collect();
+                    found.sort();
+                    expected.sort();
+                    assert_eq!(found, expected, "{query} {tolerance}");
+                    assert!(found.contains(&word.as_str()));
+                }
+            }
+        }
+
+        #[test]
+        fn ambiguous_large() {
+            use super::super::AmbiguousQuery;
+            use crate::distance::ambiguous_distance;
+            let words = generated_words(100);
+            for (i, word) in words.iter().enumerate() {
+                // every other position could also be the next letter along
+                let sets: Vec<Vec<char>> = word
+                    .chars()
+                    .enumerate()
+                    .map(|(j, c)| match (i + j) % 2 {
+                        0 => vec![c, (c as u8 + 1) as char],
+                        _ => vec![c],
+                    })
+                    .collect();
+                let sets: Vec<&[char]> = sets.iter().map(|s| s.as_slice()).collect();
+                for tolerance in [0, 1, 2] {
+                    let mut found: Vec<_> = TREE
+                        .ambiguous_matches(AmbiguousQuery(&sets), tolerance)
+                        .iter()
+                        .map(|s| s.word)
+                        .collect();
+                    let mut expected: Vec<_> = TREE
+                        .iter()
+                        .map(|n| n.word())
+                        .filter(|w| ambiguous_distance(&sets, w) <= tolerance as usize)
+                        .collect();
+                    found.sort();
+                    expected.sort();
+                    assert_eq!(found, expected, "{word} {tolerance}");
+                }
+            }
+        }
+    }
+
+    // a runtime tree dumped by `write_codegen`, rooted somewhere other
+    // than "the"
+    #[cfg(not(feature = "grapheme"))]
+    mod runtime_codegen {
+        use super::super::{BkTreeBuilder, Node, RuntimeBkTree};
+        use super::generated_words;
+        use std::{fs, string::String, vec::Vec};
+
+        include!("../tree_runtime.test");
+
+        fn runtime_tree() -> RuntimeBkTree {
+            generated_words(300).into_iter().rev().collect()
+        }
+
+        #[test]
+        fn write_codegen() {
+            let mut out = Vec::new();
+            let unplaced = runtime_tree()
+                .write_codegen(&mut out, "RUNTIME")
+                .unwrap()
+                .len();
+            assert_eq!(unplaced, 0);
+            // the included file is the previous run's output
+            let previous = fs::read_to_string("tree_runtime.test").unwrap();
+            fs::write("tree_runtime.test", &out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), previous);
+            assert!(RuntimeBkTree::new()
+                .write_codegen(Vec::new(), "EMPTY")
+                .is_err());
+            // the same source the builder makes of the words in that order
+            let tree: RuntimeBkTree = generated_words(300).into_iter().collect();
+            let mut out = Vec::new();
+            tree.write_codegen(&mut out, "TREE").unwrap();
+            let words: Vec<_> = tree.words().collect();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                BkTreeBuilder::new(words).build()
+            );
+        }
+
+        #[test]
+        fn write_codegen_unplaced() {
+            // left out and reported like the builder does
+            let words = ["the", "car", "internationalization"];
+            let tree: RuntimeBkTree = words.into_iter().collect();
+            let mut out = Vec::new();
+            let unplaced = tree.write_codegen(&mut out, "TREE").unwrap();
+            assert_eq!(unplaced, ["internationalization"]);
+            let builder = BkTreeBuilder::new(words);
+            assert_eq!(builder.stats().unplaced, unplaced);
+            assert_eq!(String::from_utf8(out).unwrap(), builder.build());
+            // without "the" it's rooted at its own first word
+            let unrooted: RuntimeBkTree = ["car", "cart"].into_iter().collect();
+            let mut out = Vec::new();
+            unrooted.write_codegen(&mut out, "TREE").unwrap();
+            let source = String::from_utf8(out).unwrap();
+            assert!(source.starts_with("static TREE: Node = Node::new(\"car\", "));
+            assert!(source.contains("\"cart\""));
+        }
+
+        #[test]
+        fn round_trip() {
+            let tree = runtime_tree();
+            assert_eq!(RUNTIME.word(), tree.words().next().unwrap());
+            assert_ne!(RUNTIME.word(), "the");
+            assert_eq!(WORD_COUNT, tree.len());
+            assert_eq!(RUNTIME.check_invariants(), Ok(()));
+            for query in generated_words(400).iter().skip(250) {
+                for tolerance in [0, 1, 2] {
+                    let found: Vec<_> = RUNTIME.corrections(query, tolerance);
+                    let expected = tree.corrections(query, tolerance);
+                    let pairs = |s: &[super::super::Suggestion]| -> Vec<(String, u8)> {
+                        s.iter().map(|s| (s.word.into(), s.distance)).collect()
+                    };
+                    assert_eq!(pairs(&found), pairs(&expected), "{query} {tolerance}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn candidates() {
+        assert!(TREE.candidates("shes", 1).contains(&"she"));
+        assert!(TREE.candidates("cars", 1).contains(&"car"));
+        // unverified, whatever the search looked at
+        assert!(TREE.candidates("cars", 1).contains(&"the"));
+        assert!(!TREE.corrections("cars", 1).iter().any(|s| s.word == "the"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn canidates_still_works() {
+        assert_eq!(TREE.canidates("cars", 1), TREE.candidates("cars", 1));
+    }
+
+    #[test]
+    fn corrections() {
+        let corrections = TREE.corrections("cars", 1);
+        assert_eq!(corrections[0].word, "car");
+        assert!(corrections.iter().all(|s| s.distance <= 1));
+        assert!(corrections.iter().any(|s| s.word == "card"));
+    }
+
+    #[test]
+    fn best_k_into() {
+        for (word, tolerance) in [("cars", 1), ("shes", 2), ("cake", 3), ("xyz", 4)] {
+            let expected = TREE.corrections(word, tolerance);
+            let (best, len) = TREE.best_k_into::<4>(word, tolerance);
+            assert_eq!(len, expected.len().min(4));
+            for (i, slot) in best.iter().enumerate() {
+                let expected = expected.get(i).map(|s| (s.word, s.distance));
+                assert_eq!(*slot, expected);
+            }
+        }
+        assert_eq!(TREE.best_k_into::<0>("cars", 2).1, 0);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn wasm_entries() {
+        let tree = super::WasmBkTree::new(StaticBkTree::new(&TREE, WORD_COUNT, CHECKSUM));
+        assert_eq!(tree.word_count(), WORD_COUNT);
+        assert!(tree.contains("card") && !tree.contains("cards"));
+        let corrections = TREE.corrections("cars", 1);
+        let entries = super::wasm::entries(&corrections);
+        assert_eq!(entries.len(), corrections.len());
+        for (entry, s) in entries.iter().zip(&corrections) {
+            assert_eq!((entry.0.as_str(), entry.1), (s.word, s.distance));
+        }
+        assert!(super::wasm::entries(&[]).is_empty());
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod dfs_codegen {
+        use super::super::{BkTreeBuilder, Node};
+        use super::TREE as NESTED;
+        use std::vec::Vec;
+
+        include!("../tree_dfs.test");
+
+        #[test]
+        fn write_dfs() {
+            let words = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+            let builder = BkTreeBuilder::new(words).dfs_order(true);
+            builder.write(Some("tree_dfs.test".into()));
+            let source = builder.build();
+            let statics: Vec<_> = source
+                .lines()
+                .filter_map(|line| line.strip_prefix("static ")?.split(':').next())
+                .collect();
+            // each subtree straight after its parent, lowest bucket first
+            assert_eq!(
+                statics,
+                [
+                    "TREE_WORDS",
+                    "TREE",
+                    "TREE_5",
+                    "TREE_6",
+                    "TREE_7",
+                    "TREE_2",
+                    "TREE_4",
+                    "TREE_3",
+                    "TREE_1"
+                ]
+            );
+            assert!(source
+                .starts_with("static TREE_WORDS: [u8; 27] = *b\"thethemshehecarcarecakecard\";\n"));
+        }
+
+        #[test]
+        fn no_shared_nodes() {
+            use std::{collections::BTreeSet, format, string::ToString};
+
+            // the same few shapes over and over, alike but never equal
+            let words: Vec<_> = [
+                "the", "ab", "ba", "abc", "bac", "abcd", "bacd", "dcba", "cd",
+            ]
+            .iter()
+            .flat_map(|w| [w.to_string(), w.repeat(2), format!("{w}s")])
+            .collect();
+            let builder = BkTreeBuilder::new(words.iter().map(|w| w.as_str())).dfs_order(true);
+            let source = builder.build();
+            let nodes: Vec<_> = source
+                .lines()
+                .filter(|line| line.starts_with("static TREE") && line.contains("Node::new("))
+                .map(|line| line.split_once('=').unwrap().1)
+                .collect();
+            assert_eq!(nodes.len(), builder.word_list().len());
+            assert_eq!(nodes.iter().collect::<BTreeSet<_>>().len(), nodes.len());
+        }
+
+        #[test]
+        fn dfs_search() {
+            assert_eq!(STRING_BYTES, TREE_WORDS.len());
+            assert_eq!(TREE.check_invariants(), Ok(()));
+            let nodes = |tree: &'static Node| -> Vec<_> {
+                tree.iter()
+                    .map(|n| (n.word(), n.index(), n.mask()))
+                    .collect()
+            };
+            assert_eq!(nodes(&TREE), nodes(&NESTED));
+            for word in ["cars", "shes", "cake", "xyz", "", "the"] {
+                for tolerance in 0..4 {
+                    assert_eq!(
+                        TREE.corrections(word, tolerance),
+                        NESTED.corrections(word, tolerance)
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod const_codegen {
+        use super::super::{BkTreeBuilder, Node, WriteError};
+        use super::TREE as STATIC;
+        use std::vec::Vec;
+
+        include!("../tree_const.test");
+
+        // a configuration holding the tree, built at compile time
+        struct Config {
+            name: &'static str,
+            dictionary: &'static Node,
+        }
+
+        const CONFIG: Config = Config {
+            name: "device",
+            dictionary: TREE,
+        };
+
+        const ROOT_WORD: &str = CONFIG.dictionary.word();
+
+        #[test]
+        fn write_const() {
+            let words = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+            let builder = BkTreeBuilder::new(words).dfs_order(true).const_items(true);
+            builder.write(Some("tree_const.test".into()));
+            let source = builder.build();
+            assert!(source.lines().all(|line| !line.starts_with("static ")));
+            assert!(source.starts_with(
+                "const TREE_WORDS: &[u8; 27] = b\"thethemshehecarcarecakecard\";\n\
+                 const TREE: &Node = &Node::new(Node::interned(TREE_WORDS, 0, 3), 0, "
+            ));
+            let nested = BkTreeBuilder::new(words)
+                .const_items(true)
+                .static_wrapper(true)
+                .build();
+            assert!(nested.starts_with("const TREE_ROOT: &Node = &Node::new(\"the\", 0, "));
+            assert!(nested.contains(
+                "const TREE: &StaticBkTree = &StaticBkTree::new(TREE_ROOT, WORD_COUNT, CHECKSUM);\n"
+            ));
+            assert_eq!(
+                BkTreeBuilder::new(words)
+                    .const_items(true)
+                    .progmem(true)
+                    .try_build(),
+                Err(WriteError::Incompatible {
+                    option: "const_items",
+                    with: "progmem"
+                })
+            );
+        }
+
+        #[test]
+        fn const_search() {
+            assert_eq!((CONFIG.name, ROOT_WORD), ("device", "the"));
+            assert_eq!(TREE.check_invariants(), Ok(()));
+            let nodes = |tree: &'static Node| -> Vec<_> {
+                tree.iter()
+                    .map(|n| (n.word(), n.index(), n.mask()))
+                    .collect()
+            };
+            assert_eq!(nodes(CONFIG.dictionary), nodes(&STATIC));
+            for word in ["cars", "shes", "cake", "xyz", "", "the"] {
+                for tolerance in 0..4 {
+                    assert_eq!(
+                        CONFIG.dictionary.corrections(word, tolerance),
+                        STATIC.corrections(word, tolerance)
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "log")]
+    mod log_lines {
+        use super::TREE;
+        use std::{
+            format,
+            string::String,
+            sync::{Mutex, Once},
+            vec::Vec,
+        };
+
+        // every line logged by any test, the query tells them apart
+        static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct Capture;
+
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                let line = format!("{} {}", record.level(), record.args());
+                LINES.lock().unwrap().push(line);
+            }
+            fn flush(&self) {}
+        }
+
+        fn lines(query: &str) -> Vec<String> {
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                log::set_logger(&Capture).unwrap();
+                log::set_max_level(log::LevelFilter::Trace);
+            });
+            let _ = TREE.corrections(query, 1);
+            let query = format!("{query:?}: ");
+            let lines = LINES.lock().unwrap();
+            lines
+                .iter()
+                .filter(|l| l.contains(&query))
+                .cloned()
+                .collect()
+        }
+
+        #[test]
+        fn search_lines() {
+            let lines = lines("cadr");
+            for line in [
+                "TRACE \"cadr\": visited \"the\" at 4",
+                "TRACE \"cadr\": buckets 3..=5 of \"the\"",
+                "DEBUG \"cadr\": accepted \"car\" at 1",
+                "TRACE \"cadr\": rejected \"the\" at 4",
+            ] {
+                assert!(lines.iter().any(|l| l == line), "{line} not in {lines:#?}");
+            }
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod phrases {
+        use super::super::{
+            validate_query, BkTreeBuilder, Node, QueryError, QueryLimits, WriteError,
+        };
+
+        include!("../tree_phrases.test");
+
+        const PHRASES: [&str; 9] = [
+            "the",
+            "git commit",
+            "git cherry-pick",
+            "git checkout",
+            "git clone",
+            "docker compose",
+            "docker run",
+            "cargo build",
+            "cargo test",
+        ];
+
+        #[test]
+        fn write_phrases() {
+            let builder = BkTreeBuilder::new(PHRASES);
+            builder.write(Some("tree_phrases.test".into()));
+            assert!(builder.build().contains("Node::new(\"git cherry-pick\", "));
+            for untidy in ["git commit ", "\tgit clone", "git\ncommit", "docker\r\nrun"] {
+                assert_eq!(
+                    BkTreeBuilder::new(["the", untidy]).try_build(),
+                    Err(WriteError::Whitespace {
+                        word: untidy.into()
+                    })
+                );
+            }
+            // too far from "the" for any of its buckets, left out like
+            // before phrases were allowed
+            let long = "docker compose up --build";
+            let builder = BkTreeBuilder::new(["the", "car", long]);
+            assert!(!builder.try_build().unwrap().contains(long));
+            assert_eq!(builder.stats().unplaced, [long]);
+            let words = ["the", "car", "internationalization"];
+            assert!(BkTreeBuilder::new(words).try_build().is_ok());
+            assert!(BkTreeBuilder::new(PHRASES).stats().unplaced.is_empty());
+        }
+
+        #[test]
+        fn phrase_search() {
+            assert_eq!(WORD_COUNT, PHRASES.len());
+            let best = TREE.best_match("git chery-pick", 1).unwrap();
+            assert_eq!((best.word, best.distance), ("git cherry-pick", 1));
+            // the space is an edit like any other
+            let best = TREE.best_match("gitcommit", 1).unwrap();
+            assert_eq!((best.word, best.distance), ("git commit", 1));
+            let found: std::vec::Vec<_> = TREE
+                .corrections("git chekout", 2)
+                .iter()
+                .map(|s| (s.word, s.distance))
+                .collect();
+            assert_eq!(found, [("git checkout", 1)]);
+            assert!(TREE.best_match("cargo", 2).is_none());
+
+            let phrase = QueryLimits::PHRASE;
+            assert_eq!(validate_query("git chery-pick", &phrase), Ok(()));
+            assert_eq!(
+                TREE.checked_best_match("cargo tset", 2, &phrase)
+                    .map(|s| s.map(|s| s.word)),
+                Ok(Some("cargo test"))
+            );
+            for spaced in [" git clone", "git clone ", "git  clone", "git\tclone"] {
+                assert_eq!(
+                    validate_query(spaced, &phrase),
+                    Err(QueryError::ContainsWhitespace)
+                );
+            }
+            assert_eq!(
+                validate_query("git clone", &QueryLimits::WORD),
+                Err(QueryError::ContainsWhitespace)
+            );
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod word_table {
+        use super::super::{BkTreeBuilder, Node, WordTable};
+        use core::ops::ControlFlow;
+
+        include!("../tree_words.test");
+
+        const WORDS_LIST: [&str; 8] = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+
+        #[test]
+        fn write_word_table() {
+            let builder = BkTreeBuilder::new(WORDS_LIST)
+                .dfs_order(true)
+                .word_table(true);
+            builder.write(Some("tree_words.test".into()));
+            let source = builder.build();
+            assert!(source.contains("static WORDS: WordTable = WordTable::new(&[Node::interned("));
+            let plain = BkTreeBuilder::new(WORDS_LIST).word_table(true).build();
+            assert!(plain.contains("static WORDS: WordTable = WordTable::new(&[\"the\", "));
+            assert!(!BkTreeBuilder::new(WORDS_LIST).build().contains("WORDS"));
+        }
+
+        #[test]
+        fn word_at() {
+            assert_eq!(WORDS.len(), WORD_COUNT);
+            for node in TREE.iter() {
+                assert_eq!(WORDS.word_at(node.index() as u16), node.word());
+            }
+            for word in ["the", "cat", "sh", "zzzz"] {
+                for tolerance in 0..3 {
+                    let indexed = TREE.indexed_corrections(word, tolerance);
+                    let words: std::vec::Vec<_> = indexed
+                        .iter()
+                        .map(|&(index, distance)| (WORDS.word_at(index), distance))
+                        .collect();
+                    let expected: std::vec::Vec<_> = TREE
+                        .corrections(word, tolerance)
+                        .iter()
+                        .map(|s| (s.word, s.distance))
+                        .collect();
+                    assert_eq!(words, expected, "{word} {tolerance}");
+                    let best = TREE.best_indexed(word, tolerance);
+                    let best = best.map(|(index, distance)| (WORDS.word_at(index), distance));
+                    let expected = TREE
+                        .best_match(word, tolerance)
+                        .map(|s| (s.word, s.distance));
+                    assert_eq!(best, expected);
+                }
+            }
+            let mut first = None;
+            TREE.for_each_indexed("card", 1, |index, distance| {
+                first = Some((index, distance));
+                ControlFlow::Break(())
+            });
+            assert!(first.is_some());
+        }
+    }
+
+    #[cfg(not(feature = "grapheme"))]
+    mod tiers {
+        use super::super::{BkTreeBuilder, Node};
+        use std::{fs, string::String};
+
+        // the generated tiers with the features turned on or off
+        mod small {
+            use super::Node;
+            include!("../tree_tier_small.test");
+
+            pub(super) fn tier() -> (&'static Node, usize, &'static [u8]) {
+                (&TREE, WORD_COUNT, &TREE_WORDS)
+            }
+        }
+        mod large {
+            use super::Node;
+            include!("../tree_tier_large.test");
+
+            pub(super) fn tier() -> (&'static Node, usize, &'static [u8]) {
+                (&TREE, WORD_COUNT, &TREE_WORDS)
+            }
+        }
+
+        const WORDS: [&str; 8] = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+
+        fn enable(source: &str, small: bool, large: bool) -> String {
+            let cfg = |on| match on {
+                true => "all()",
+                false => "any()",
+            };
+            source
+                .replace("feature = \"small\"", cfg(small))
+                .replace("feature = \"large\"", cfg(large))
+        }
+
+        #[test]
+        fn write_tiers() {
+            let builder = BkTreeBuilder::new(WORDS)
+                .dfs_order(true)
+                .tier("small", 4)
+                .tier("large", 8);
+            let source = builder.build();
+            fs::write("tree_tier_small.test", enable(&source, true, false)).unwrap();
+            // both on is the large one
+            fs::write("tree_tier_large.test", enable(&source, true, true)).unwrap();
+            assert!(source.starts_with(
+                "#[cfg(not(any(feature = \"small\", feature = \"large\")))]\ncompile_error!("
+            ));
+            assert!(source.contains(
+                "#[cfg(all(feature = \"small\", not(any(feature = \"large\"))))]\nstatic TREE_WORDS: [u8; 12] = Node::words_prefix(&TREE_TIER_WORDS);\n"
+            ));
+            assert!(source.contains(
+                "#[cfg(feature = \"large\")]\nstatic TREE_WORDS: [u8; 27] = Node::words_prefix(&TREE_TIER_WORDS);\n"
+            ));
+            // only the smallest words once, the rest after them
+            assert!(source.contains("const TREE_TIER_WORDS: [u8; 27] = *b\"thethemshehe"));
+
+            // without dfs_order nothing's shared, and a tier that isn't a
+            // superset of the one before gets its own words
+            let nested = BkTreeBuilder::new(WORDS).tier("small", 4).tier("large", 8);
+            assert!(!nested.build().contains("TIER_WORDS"));
+            let apart = BkTreeBuilder::new(WORDS)
+                .dfs_order(true)
+                .tier_words("small", ["the", "car"])
+                .tier_words("large", ["the", "he", "cake"]);
+            let apart = apart.build();
+            assert!(apart.contains("const TREE_TIER_WORDS: [u8; 6] = *b\"thecar\";"));
+            assert!(apart.contains("static TREE_WORDS: [u8; 9] = *b\"thehecake\";"));
+        }
+
+        #[test]
+        fn tier_trees() {
+            let (small, small_count, small_words) = small::tier();
+            let (large, large_count, large_words) = large::tier();
+            assert_eq!((small_count, large_count), (4, 8));
+            assert_eq!(small.iter().count(), 4);
+            assert_eq!(large.iter().count(), 8);
+            assert!(small.contains("he") && !small.contains("car"));
+            assert!(large.contains("car"));
+            assert_eq!(large.check_invariants(), Ok(()));
+            assert_eq!(small_words, &large_words[..12]);
+        }
+    }
+
+    #[cfg(all(feature = "avr-progmem", not(feature = "grapheme")))]
+    mod progmem_codegen {
+        use super::super::{
+            corrections_in_storage, BkTreeBuilder, BlobError, DecodeError, NearRead, Node, NodeBuf,
+            NodeStorage, ProgmemBlob, StoredMatch,
+        };
+        use super::TREE as NODES;
+
+        include!("../tree_progmem.test");
+
+        #[test]
+        fn write_progmem() {
+            let words = ["the", "them", "she", "he", "car", "care", "card", "cake"];
+            let builder = BkTreeBuilder::new(words).progmem(true);
+            builder.write(Some("tree_progmem.test".into()));
+            let source = builder.build();
+            assert!(source.starts_with(
+                "#[cfg_attr(target_arch = \"avr\", link_section = \".progmem.data\")]\n\
+                 static TREE_BLOB: [u8; "
+            ));
+            assert!(source.contains("static TREE: ProgmemBlob = ProgmemBlob::new(&TREE_BLOB);"));
+            assert_eq!(BLOB_BYTES, builder.build_storage().len());
+        }
+
+        #[test]
+        fn progmem_search() {
+            assert_eq!(TREE.len(), BLOB_BYTES);
+            let mut storage = TREE.storage(NearRead);
+            let mut buf = NodeBuf::new();
+            storage.read_node(0, &mut buf).unwrap();
+            assert_eq!(buf.word(), "the");
+            let by_id: std::vec::Vec<_> = NODES.iter().map(|n| n.word()).collect();
+            assert_eq!(by_id.len(), WORD_COUNT);
+            let mut out = [StoredMatch { id: 0, distance: 0 }; 8];
+            for (word, tolerance) in [("cars", 1), ("shes", 2), ("cake", 3), ("xyz", 4)] {
+                let filled =
+                    corrections_in_storage::<_, 16>(&mut storage, 0, word, tolerance, &mut out)
+                        .unwrap();
+                let expected = NODES.corrections(word, tolerance);
+                assert_eq!(filled, expected.len());
+                for (m, s) in out.iter().zip(&expected) {
+                    assert_eq!((by_id[m.id as usize], m.distance), (s.word, s.distance));
+                }
+            }
+            // reads past the end of the blob are refused, not followed
+            let short = ProgmemBlob::new(&TREE_BLOB[..BLOB_BYTES - 1]);
+            assert_eq!(
+                short
+                    .storage(NearRead)
+                    .read_node(WORD_COUNT as u32 - 1, &mut buf),
+                Err(BlobError::Read(DecodeError::Truncated))
+            );
+        }
+    }
+
+    #[test]
+    fn supplement() {
+        use super::{ParseError, Supplement, SupplementedTree};
+
+        let tree = StaticBkTree::new(&TREE, WORD_COUNT, CHECKSUM);
+        let blob: &[u8] = Supplement::encode(CHECKSUM, ["cart", "carts", "she"]).leak();
+        let supplement = Supplement::parse(blob).unwrap();
+        assert_eq!(supplement.len(), 3);
+        assert_eq!(
+            supplement.words().collect::<Vec<_>>(),
+            ["cart", "carts", "she"]
+        );
+        let merged = SupplementedTree::new(tree, supplement).unwrap();
+        assert!(merged.contains("cart") && merged.contains("card"));
+        assert!(!merged.contains("cards"));
+        let pairs = |word, tolerance| -> Vec<(&str, u8)> {
+            merged
+                .corrections(word, tolerance)
+                .iter()
+                .map(|s| (s.word, s.distance))
+                .collect()
+        };
+        // "she" is in both and only comes back once
+        assert_eq!(pairs("shes", 1), [("she", 1)]);
+        let mut expected = vec![("cart", 0)];
+        expected.extend(
+            TREE.corrections("cart", 1)
+                .iter()
+                .map(|s| (s.word, s.distance)),
+        );
+        expected.push(("carts", 1));
+        assert_eq!(pairs("cart", 1), expected);
+        assert_eq!(merged.best_match("carts", 1).map(|s| s.word), Some("carts"));
+        // baked words win ties
+        assert_eq!(
+            merged.best_match("carx", 1).map(|s| s.word),
+            TREE.best_match("carx", 1).map(|s| s.word)
+        );
+
+        let other = StaticBkTree::new(&TREE, WORD_COUNT, CHECKSUM ^ 1);
+        assert_eq!(
+            SupplementedTree::new(other, supplement).map(|_| ()),
+            Err(ParseError::BaseMismatch {
+                expected: CHECKSUM ^ 1,
+                found: CHECKSUM
+            })
+        );
+
+        let parse = |bytes: &[u8]| Supplement::parse(bytes.to_vec().leak()).map(|_| ());
+        assert_eq!(parse(&blob[..3]), Err(ParseError::BadMagic));
+        assert_eq!(parse(&blob[..10]), Err(ParseError::Truncated));
+        assert_eq!(parse(&blob[..blob.len() - 1]), Err(ParseError::Truncated));
+        assert_eq!(
+            parse(&[blob, b"x"].concat()),
+            Err(ParseError::TrailingBytes)
+        );
+        let mut corrupt = blob.to_vec();
+        corrupt[0] = b'X';
+        assert_eq!(parse(&corrupt), Err(ParseError::BadMagic));
+        let mut corrupt = blob.to_vec();
+        corrupt[4] = 2;
+        assert_eq!(parse(&corrupt), Err(ParseError::UnsupportedVersion(2)));
+        let mut corrupt = blob.to_vec();
+        // "cart" to "cars", still utf-8 but not what was summed
+        corrupt[17 + 4] = b's';
+        assert_eq!(parse(&corrupt), Err(ParseError::ChecksumMismatch));
+        corrupt[17 + 4] = 0xff;
+        assert_eq!(parse(&corrupt), Err(ParseError::BadWord { index: 0 }));
+        let empty = Supplement::encode(CHECKSUM, []);
+        assert!(Supplement::parse(empty.leak()).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "sdmmc")]
+    #[test]
+    fn sdmmc() {
+        use super::{for_each_word, load_file, RuntimeBkTree, WordListError};
+        use core::cell::RefCell;
+        use embedded_sdmmc::{
+            Block, BlockCount, BlockDevice, BlockIdx, Mode, TimeSource, Timestamp, VolumeIdx,
+            VolumeManager,
+        };
+        use std::collections::BTreeMap;
+
+        // a blank fat16 card, blocks that were never written read as zeros
+        struct Card(RefCell<BTreeMap<u32, [u8; 512]>>);
+        impl BlockDevice for Card {
+            type Error = ();
+            fn read(&self, blocks: &mut [Block], start: BlockIdx) -> Result<(), ()> {
+                let card = self.0.borrow();
+                for (i, block) in blocks.iter_mut().enumerate() {
+                    block.contents = card.get(&(start.0 + i as u32)).copied().unwrap_or([0; 512]);
+                }
+                Ok(())
+            }
+            fn write(&self, blocks: &[Block], start: BlockIdx) -> Result<(), ()> {
+                let mut card = self.0.borrow_mut();
+                for (i, block) in blocks.iter().enumerate() {
+                    card.insert(start.0 + i as u32, block.contents);
+                }
+                Ok(())
+            }
+            fn num_blocks(&self) -> Result<BlockCount, ()> {
+                Ok(BlockCount(BLOCKS + 1))
+            }
+        }
+        struct Clock;
+        impl TimeSource for Clock {
+            fn get_timestamp(&self) -> Timestamp {
+                Timestamp::from_fat(0, 0)
+            }
+        }
+        // one reserved block, two 20 block fats, 32 of root directory and
+        // 5000 one block clusters
+        const BLOCKS: u32 = 1 + 40 + 32 + 5000;
+        let mut mbr = [0; 512];
+        mbr[446 + 4] = 0x06;
+        mbr[446 + 8..446 + 12].copy_from_slice(&1u32.to_le_bytes());
+        mbr[446 + 12..446 + 16].copy_from_slice(&BLOCKS.to_le_bytes());
+        let mut bpb = [0; 512];
+        bpb[11..13].copy_from_slice(&512u16.to_le_bytes());
+        bpb[13] = 1;
+        bpb[14..16].copy_from_slice(&1u16.to_le_bytes());
+        bpb[16] = 2;
+        bpb[17..19].copy_from_slice(&512u16.to_le_bytes());
+        bpb[21] = 0xf8;
+        bpb[22..24].copy_from_slice(&20u16.to_le_bytes());
+        bpb[32..36].copy_from_slice(&BLOCKS.to_le_bytes());
+        let mut fat = [0; 512];
+        fat[..4].copy_from_slice(&[0xf8, 0xff, 0xff, 0xff]);
+        for block in [&mut mbr, &mut bpb] {
+            block[510..].copy_from_slice(&[0x55, 0xaa]);
+        }
+        let card = Card(RefCell::new(BTreeMap::from([
+            (0, mbr),
+            (1, bpb),
+            (2, fat),
+            (22, fat),
+        ])));
+
+        // "straddles" runs across the 16 byte chunks of the first read
+        let list = "the\r\ncar\n\n  straddles \ncard\nthe\ncartographer\nlast";
+        let manager = VolumeManager::new(card, Clock);
+        let volume = manager.open_volume(VolumeIdx(0)).unwrap();
+        let root = volume.open_root_dir().unwrap();
+        let file = root
+            .open_file_in_dir("WORDS.TXT", Mode::ReadWriteCreateOrTruncate)
+            .unwrap();
+        file.write(list.as_bytes()).unwrap();
+        file.close().unwrap();
+
+        let file = root.open_file_in_dir("WORDS.TXT", Mode::ReadOnly).unwrap();
+        let mut tree = RuntimeBkTree::new();
+        let stats = load_file(&file, &mut [0; 16], &mut tree).unwrap();
+        assert_eq!((stats.inserted, stats.skipped), (6, 1));
+        assert_eq!(
+            tree.words().collect::<Vec<_>>(),
+            ["the", "car", "straddles", "card", "cartographer", "last"]
+        );
+        assert!(tree.contains("straddles"));
+        file.close().unwrap();
+
+        // the same list from memory with line numbers and bad entries
+        let read_from = |bytes: &'static [u8]| {
+            let mut at = 0;
+            move |chunk: &mut [u8]| {
+                let n = chunk.len().min(bytes.len() - at).min(5);
+                chunk[..n].copy_from_slice(&bytes[at..at + n]);
+                at += n;
+                Ok::<_, ()>(n)
+            }
+        };
+        let mut lines = Vec::new();
+        let words = for_each_word(read_from(list.as_bytes()), &mut [0; 16], |line, word| {
+            lines.push((line, String::from(word)))
+        });
+        assert_eq!(words, Ok(7));
+        assert_eq!(lines[2], (4, String::from("straddles")));
+        assert_eq!(lines[6], (8, String::from("last")));
+        assert_eq!(
+            for_each_word(read_from(b"car\ncartographer\n"), &mut [0; 8], |_, _| ()),
+            Err(WordListError::TooLong { line: 2 })
+        );
+        assert_eq!(
+            for_each_word(read_from(b"car\n\ncar\xff\n"), &mut [0; 8], |_, _| ()),
+            Err(WordListError::NotUtf8 { line: 3 })
+        );
+        assert_eq!(
+            for_each_word(|_: &mut [u8]| Err("card removed"), &mut [0; 8], |_, _| ()),
+            Err(WordListError::Read("card removed"))
+        );
+    }
+
+    #[cfg(feature = "proptest")]
+    mod properties {
+        use super::super::testing::{
+            invariants_hold, iterator_yields_every_word_once, runtime_tree,
+            search_matches_brute_force, word, word_list,
+        };
+        use proptest::prelude::*;
+        use std::{string::String, vec::Vec};
+
+        proptest! {
+            // few cases so the suite stays quick, fuzz harder downstream
+            #![proptest_config(ProptestConfig::with_cases(32))]
+
+            #[test]
+            fn search(words in word_list("abcd", 1..=6, 1..60), query in word("abcd", 0..=7), tolerance in 0u8..4) {
+                search_matches_brute_force(&runtime_tree(&words), &query, tolerance)?;
+            }
+
+            #[cfg(not(feature = "small-code"))]
+            #[test]
+            fn automaton(query in word("abcd", 0..=8), word in word("abcd", 0..=8), tolerance in 0usize..=2) {
+                let expected = crate::distance::char_distance(&query, &word);
+                let automaton = crate::automaton::Automaton::new(&query, tolerance).unwrap();
+                prop_assert_eq!(automaton.distance(&word), (expected <= tolerance).then_some(expected));
+            }
+
+            #[test]
+            fn invariants(words in word_list("aeinrst", 1..=8, 0..80)) {
+                invariants_hold(&runtime_tree(&words))?;
+            }
+
+            #[test]
+            fn iterator(words in word_list("ab", 1..=4, 0..40)) {
+                iterator_yields_every_word_once(&runtime_tree(&words), &words)?;
+            }
+
+            #[test]
+            fn after_removal(words in word_list("abc", 1..=5, 1..40), removed in word_list("abc", 1..=5, 0..10)) {
+                let mut tree = runtime_tree(&words);
+                removed.iter().for_each(|w| { tree.remove(w); });
+                let kept: Vec<String> = words.iter().filter(|w| !removed.contains(w)).cloned().collect();
+                invariants_hold(&tree)?;
+                iterator_yields_every_word_once(&tree, &kept)?;
+                search_matches_brute_force(&tree, "abc", 2)?;
+            }
+        }
+    }
+
+    #[cfg(all(feature = "phonetic", not(feature = "grapheme")))]
+    mod phonetic_codegen {
+        use super::super::{phonetic_code, BkTreeBuilder, Node, PhoneticIndex};
+        use std::vec::Vec;
+
+        include!("../tree_phonetic.test");
+
+        const WORDS: [&str; 13] = [
+            "the", "phone", "photo", "fine", "fun", "bone", "tone", "phase", "face", "fish",
+            "night", "knight", "nite",
+        ];
+
+        #[test]
+        fn write_phonetic() {
+            let builder = BkTreeBuilder::new(WORDS).phonetic_index(true);
+            builder.write(Some("tree_phonetic.test".into()));
+            assert!(builder.build().contains(
+                "static PHONETIC: PhoneticIndex = PhoneticIndex::new(&[([48, 0, 0, 0], \"the\"), "
+            ));
+            assert!(!BkTreeBuilder::new(WORDS).build().contains("PHONETIC"));
+        }
+
+        #[test]
+        fn codes() {
+            assert_eq!(phonetic_code("fone"), *b"FN\0\0");
+            assert_eq!(phonetic_code("Phone"), phonetic_code("fone"));
+            assert_eq!(phonetic_code("knight"), phonetic_code("nite"));
+            assert_eq!(phonetic_code("night"), *b"NT\0\0");
+            assert_eq!(phonetic_code("ache"), *b"AX\0\0");
+            assert_eq!(phonetic_code("thumb"), *b"0M\0\0");
+            assert_eq!(phonetic_code("box"), *b"BKS\0");
+            assert_eq!(phonetic_code("extraordinary"), *b"AKST");
+            assert_eq!(phonetic_code(""), [0; 4]);
+            assert_eq!(phonetic_code("123"), [0; 4]);
+        }
+
+        #[test]
+        fn phonetic_corrections() {
+            assert_eq!(PHONETIC.len(), WORD_COUNT);
+            let candidates = |word| PHONETIC.phonetic_candidates(word).collect::<Vec<_>>();
+            assert_eq!(candidates("fone"), ["fine", "fun", "phone"]);
+            assert_eq!(candidates("nyte"), ["knight", "night", "nite"]);
+            assert!(candidates("zzz").is_empty() && candidates("").is_empty());
+
+            // edit distance alone can't get there at tolerance 1
+            let words = |s: Vec<super::super::Suggestion<'static>>| -> Vec<(&'static str, u8)> {
+                s.iter().map(|s| (s.word, s.distance)).collect()
+            };
+            assert!(!TREE
+                .corrections("fone", 1)
+                .iter()
+                .any(|s| s.word == "phone"));
+            assert_eq!(
+                words(TREE.phonetic_corrections(&PHONETIC, "fone", 1)),
+                [
+                    ("tone", 1),
+                    ("bone", 1),
+                    ("fine", 1),
+                    ("fun", 2),
+                    ("phone", 2)
+                ]
+            );
+            let empty = PhoneticIndex::new(&[]);
+            assert_eq!(
+                words(TREE.phonetic_corrections(&empty, "fone", 1)),
+                words(TREE.corrections("fone", 1))
+            );
+        }
+    }
+
+    #[cfg(all(feature = "hamming", not(feature = "grapheme")))]
+    mod hamming_codegen {
+        use super::super::{hamming, BkTreeBuilder, CodeLength, HammingTree, Node};
+        use std::vec::Vec;
+
+        include!("../tree_hamming.test");
+
+        const CODES: [&str; 8] = [
+            "AB12CD34", "AB12CD35", "XY98ZW76", "AB13CD34", "QQ11RR22", "AB12CE34", "ZZ00ZZ00",
+            "MN45OP67",
+        ];
+
+        #[test]
+        fn write_hamming() {
+            let builder = BkTreeBuilder::new(CODES).hamming(true);
+            builder.write(Some("tree_hamming.test".into()));
+            let source = builder.build();
+            assert!(source.contains(
+                "static TREE: HammingTree = HammingTree::new(&TREE_ROOT, WORD_COUNT, CHECKSUM);"
+            ));
+            assert!(source.starts_with("static TREE_ROOT: Node = Node::new(\"AB12CD34\""));
+        }
+
+        #[test]
+        #[should_panic(expected = "hamming codes differ in length")]
+        fn mixed_lengths() {
+            BkTreeBuilder::new(["AB12CD34", "AB12CD3"])
+                .hamming(true)
+                .build();
+        }
+
+        #[test]
+        fn hamming_errors() {
+            use super::super::WriteError;
+
+            let builder = BkTreeBuilder::new(CODES).hamming(true);
+            assert_eq!(
+                builder.try_build_storage(),
+                Err(WriteError::Incompatible {
+                    option: "hamming",
+                    with: "build_storage"
+                })
+            );
+            assert_eq!(
+                builder.progmem(true).try_build(),
+                Err(WriteError::Incompatible {
+                    option: "hamming",
+                    with: "progmem"
+                })
+            );
+            let long = BkTreeBuilder::new(["0123456789ABCDEF"]).hamming(true);
+            assert_eq!(long.try_build(), Err(WriteError::CodeTooLong { len: 16 }));
+            let empty = BkTreeBuilder::new([]).hamming(true);
+            assert_eq!(empty.try_build(), Err(WriteError::Empty));
+        }
+
+        #[test]
+        fn hamming_search() {
+            assert_eq!(TREE.word_count(), CODES.len());
+            assert_eq!(TREE.code_len(), 8);
+            // one flipped char
+            let found = TREE.corrections("AB12CD3X", 1).unwrap();
+            let words: Vec<_> = found.iter().map(|s| (s.word, s.distance)).collect();
+            assert_eq!(words.len(), 2);
+            assert!(words.contains(&("AB12CD34", 1)) && words.contains(&("AB12CD35", 1)));
+            assert_eq!(
+                TREE.best_match("XY98ZW77", 2).unwrap().map(|s| s.word),
+                Some("XY98ZW76")
+            );
+            assert_eq!(TREE.contains("QQ11RR22"), Ok(true));
+            // every code against every query, the pruning loses nothing
+            for query in ["AB12CD34", "AB12XX34", "ZZ00ZZ0Z", "00000000", "MN45OP76"] {
+                for tolerance in 0..=8 {
+                    let mut found: Vec<_> = TREE
+                        .corrections(query, tolerance)
+                        .unwrap()
+                        .iter()
+                        .map(|s| s.word)
+                        .collect();
+                    found.sort_unstable();
+                    let mut expected: Vec<_> = CODES
+                        .into_iter()
+                        .filter(|c| hamming(c, query).unwrap() <= tolerance as usize)
+                        .collect();
+                    expected.sort_unstable();
+                    assert_eq!(found, expected, "{query} {tolerance}");
+                }
+            }
+        }
+
+        #[test]
+        fn wrong_length() {
+            let short = CodeLength {
+                expected: 8,
+                found: 7,
+            };
+            assert_eq!(TREE.corrections("AB12CD3", 3), Err(short));
+            assert_eq!(TREE.best_match("AB12CD345", 3).unwrap_err().found, 9);
+            assert_eq!(hamming("car", "cart"), None);
+            // a transposition is two substitutions
+            assert_eq!(hamming("AB12", "BA12"), Some(2));
+        }
+    }
+
+    #[cfg(all(feature = "inflect", not(feature = "grapheme")))]
+    mod inflect_codegen {
+        use super::super::{
+            inflect::inflect, BkTreeBuilder, Inflections, Node, ENGLISH_INFLECTIONS,
+        };
+        use std::{string::String, vec::Vec};
+
+        include!("../tree_inflect.test");
+
+        // "cars" is in the list and also what "car" inflects to
+        const WORDS: [&str; 8] = ["the", "car", "care", "run", "box", "stop", "cars", "visit"];
+
+        #[test]
+        fn rules() {
+            let inflected = |base| -> Vec<String> {
+                ENGLISH_INFLECTIONS
+                    .iter()
+                    .filter_map(|rule| inflect(base, rule))
+                    .collect()
+            };
+            assert_eq!(inflected("care"), ["cares", "cared", "caring"]);
+            assert_eq!(inflected("run"), ["runs", "runned", "running"]);
+            assert_eq!(inflected("stop"), ["stops", "stopped", "stopping"]);
+            // sibilants take es and x never doubles
+            assert_eq!(inflected("box"), ["boxes", "boxed", "boxing"]);
+            assert_eq!(inflected("church")[0], "churches");
+            // two vowels, it doesn't double
+            assert_eq!(inflected("visit"), ["visits", "visited", "visiting"]);
+        }
+
+        #[test]
+        fn write_inflections() {
+            let builder = BkTreeBuilder::new(WORDS).inflections(ENGLISH_INFLECTIONS);
+            builder.write(Some("tree_inflect.test".into()));
+            let source = builder.build();
+            assert!(source
+                .contains("static INFLECTIONS: Inflections = Inflections::new(&[(\"boxed\", "));
+            assert!(!BkTreeBuilder::new(WORDS).build().contains("INFLECTIONS"));
+            let none = BkTreeBuilder::new(WORDS).inflections(&[]).build();
+            assert_eq!(none, BkTreeBuilder::new(WORDS).build());
+        }
+
+        #[test]
+        fn base_forms() {
+            let index = |word| {
+                TREE.iter()
+                    .find(|n: &&Node| n.word() == word)
+                    .unwrap()
+                    .index()
+            };
+            for (variant, base) in [
+                ("cars", None),
+                ("cares", Some("care")),
+                ("cared", Some("care")),
+                ("caring", Some("care")),
+                ("running", Some("run")),
+                ("boxes", Some("box")),
+                ("stopped", Some("stop")),
+                ("visiting", Some("visit")),
+                ("care", None),
+                ("xyzzy", None),
+            ] {
+                assert!(variant == "xyzzy" || TREE.contains(variant), "{variant}");
+                assert_eq!(INFLECTIONS.base_form(variant), base, "{variant}");
+                assert_eq!(INFLECTIONS.base_index(variant), base.map(index));
+            }
+            // every variant is a word of the tree, the list's words aren't
+            // variants
+            assert_eq!(WORD_COUNT, WORDS.len() + INFLECTIONS.len());
+            assert!(WORDS.iter().all(|w| INFLECTIONS.base_form(w).is_none()));
+
+            // a typo gets corrected to the variant, which knows its base
+            let best = TREE.best_match("runing", 1).unwrap();
+            assert_eq!(best.word, "running");
+            assert_eq!(INFLECTIONS.base_form(best.word), Some("run"));
+            assert!(Inflections::new(&[]).is_empty());
+        }
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn ffi() {
+        use super::{bktree_contains, bktree_corrections, BkSuggestion};
+        use core::{
+            ffi::{c_char, CStr},
+            ptr, slice,
+        };
+
+        // called through pointers the way c would see them
+        let contains: unsafe extern "C" fn(*const Node, *const c_char) -> bool = bktree_contains;
+        let corrections: unsafe extern "C" fn(
+            *const Node,
+            *const c_char,
+            u8,
+            *mut BkSuggestion,
+            usize,
+        ) -> usize = bktree_corrections;
+        let c = |s: &CStr| s.as_ptr();
+        unsafe {
+            assert!(contains(&TREE, c(c"card")));
+            assert!(!contains(&TREE, c(c"cards")));
+            assert!(!contains(ptr::null(), c(c"card")));
+            assert!(!contains(&TREE, ptr::null()));
+            assert!(!contains(&TREE, c(c"car\xff")));
+
+            for (word, tolerance) in [(c"cars", 1), (c"shes", 2), (c"cake", 3), (c"tha", 4)] {
+                let expected = TREE.corrections(word.to_str().unwrap(), tolerance);
+                for cap in [0, 1, 3, 20] {
+                    let mut out = [BkSuggestion::default(); 20];
+                    let filled = corrections(&TREE, c(word), tolerance, out.as_mut_ptr(), cap);
+                    assert_eq!(filled, expected.len().min(cap));
+                    for (got, expected) in out[..filled].iter().zip(&expected) {
+                        let bytes = slice::from_raw_parts(got.word.cast::<u8>(), got.len);
+                        assert_eq!(bytes, expected.word.as_bytes());
+                        assert_eq!(got.distance, expected.distance);
+                    }
+                }
+            }
+            let mut out = [BkSuggestion::default(); 4];
+            let out = out.as_mut_ptr();
+            assert_eq!(corrections(ptr::null(), c(c"car"), 2, out, 4), 0);
+            assert_eq!(corrections(&TREE, ptr::null(), 2, out, 4), 0);
+            assert_eq!(corrections(&TREE, c(c"\xffar"), 2, out, 4), 0);
+            assert_eq!(corrections(&TREE, c(c"car"), 2, ptr::null_mut(), 4), 0);
+        }
+    }
+
+    #[test]
+    fn node_identity() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let nodes: Vec<&'static Node> = TREE.iter().collect();
+        let twice = || nodes.iter().chain(&nodes).copied();
+        let sorted: BTreeSet<_> = twice().collect();
+        assert_eq!(sorted.len(), WORD_COUNT);
+        let mut words: Vec<_> = nodes.iter().map(|n| n.word()).collect();
+        words.sort_unstable();
+        assert_eq!(sorted.iter().map(|n| n.word()).collect::<Vec<_>>(), words);
+        let hashed: HashSet<_> = twice().collect();
+        assert_eq!(hashed.len(), WORD_COUNT);
+        assert!(hashed.contains(&TREE));
+        // a copy with other children is still the same word
+        let lonely = Node::new(TREE.word(), TREE.index(), TREE.mask(), &[], &[]);
+        assert_eq!(lonely, TREE);
+        assert!(nodes
+            .iter()
+            .all(|n| (*n == &TREE) == (n.word() == TREE.word())));
+
+        let corrections = TREE.corrections("cars", 2);
+        let unique: HashSet<_> = corrections.iter().chain(&corrections).collect();
+        assert_eq!(unique.len(), corrections.len());
+        let mut by_word = corrections.clone();
+        by_word.sort();
+        assert!(by_word.windows(2).all(|w| w[0].word < w[1].word));
+    }
+
+    #[test]
+    fn display() {
+        use std::format;
+
+        let full =
+            "the\n  1 them\n    2 she\n      1 he\n  3 car\n    1 care\n    2 cake\n  4 card\n";
+        assert_eq!(format!("{}", TREE.display(usize::MAX)), full);
+        assert_eq!(format!("{}", TREE.display(3)), full);
+        assert_eq!(
+            format!("{}", TREE.display(1)),
+            "the\n  1 them\n    ...\n  3 car\n    ...\n  4 card\n"
+        );
+        assert_eq!(format!("{}", TREE.display(0)), "the\n  ...\n");
+    }
+
+    #[cfg(feature = "stem")]
+    #[test]
+    fn stemmed_match() {
+        use super::{SuffixRule, ENGLISH_SUFFIXES};
+
+        let found = |word, tolerance, rules: &[SuffixRule]| {
+            TREE.stemmed_match(word, tolerance, rules).map(|m| {
+                (
+                    m.suggestion.word,
+                    m.suggestion.distance,
+                    m.rule.map(|r| r.suffix),
+                )
+            })
+        };
+        assert_eq!(
+            found("cars", 0, ENGLISH_SUFFIXES),
+            Some(("car", 0, Some("s")))
+        );
+        // "s" is tried before "es", so this is care and not car
+        assert_eq!(
+            found("cares", 0, ENGLISH_SUFFIXES),
+            Some(("care", 0, Some("s")))
+        );
+        assert_eq!(
+            found("caking", 0, ENGLISH_SUFFIXES),
+            Some(("cake", 0, Some("ing")))
+        );
+        assert_eq!(
+            found("carring", 0, ENGLISH_SUFFIXES),
+            Some(("car", 0, Some("ing")))
+        );
+        assert_eq!(
+            found("caked", 1, ENGLISH_SUFFIXES),
+            Some(("cake", 0, Some("ed")))
+        );
+        assert_eq!(found("xyzzy", 1, ENGLISH_SUFFIXES), None);
+        assert_eq!(found("cars", 0, &[]), None);
+        // an exact word is never stemmed even when a rule would fit
+        let d = [SuffixRule {
+            suffix: "d",
+            replacement: "",
+        }];
+        assert_eq!(found("card", 0, &d), Some(("card", 0, None)));
+        // and the query as typed wins ties
+        assert_eq!(
+            found("cars", 1, &[]).map(|f| f.0),
+            TREE.best_match("cars", 1).map(|s| s.word)
+        );
+        assert_eq!(
+            found("cakes", 1, ENGLISH_SUFFIXES),
+            Some(("cake", 0, Some("s")))
+        );
+    }
+
+    #[test]
+    fn best_k_ties() {
+        let mut best = BestK::<2>::new();
+        best.insert("a", 2);
+        best.insert("b", 1);
+        best.insert("c", 1);
+        best.insert("d", 1);
+        assert_eq!(best.into_parts(), ([Some(("b", 1)), Some(("c", 1))], 2));
+    }
+
+    #[test]
+    fn distance_matches_search() {
+        for query in ["cars", "shes", "", "thee", "crad"] {
+            for s in TREE.corrections(query, 3) {
+                assert_eq!(s.distance as usize, super::distance(query, s.word));
+            }
+            let best = TREE.best_match(query, 3).unwrap();
+            assert_eq!(best.distance as usize, super::distance(query, best.word));
+        }
+        assert_eq!(super::distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn corrections_bytes() {
+        use crate::distance::LossyUnits;
+
+        let found = |query: &[u8], tolerance| -> Vec<_> {
+            let found = TREE.corrections_bytes(query, tolerance);
+            found.iter().map(|s| (s.word, s.distance)).collect()
+        };
+        // the stray byte is one substitution
+        assert_eq!(found(b"ca\xffd", 1), [("card", 1)]);
+        assert_eq!(found(b"ca\xffd", 0), []);
+        assert_eq!(found(b"\xffhe", 0), []);
+        assert_eq!(found(b"\xffhe", 1)[0], ("the", 1));
+        // a sequence cut short is one replacement too
+        assert_eq!(found(b"ca\xe2\x82d", 1), [("card", 1)]);
+        assert_eq!(found(b"car\xf0\x9f", 1), found("car\u{fffd}".as_bytes(), 1));
+
+        let queries: [&[u8]; 7] = [
+            b"cars",
+            "c\u{e5}re".as_bytes(),
+            b"",
+            b"\xff\xfe",
+            b"ca\xe2\x82\xe2\x82d",
+            b"\xed\xa0\x80e",
+            b"c\xc3",
+        ];
+        for query in queries {
+            let lossy = String::from_utf8_lossy(query);
+            assert_eq!(LossyUnits::new(query).collect::<String>(), lossy);
+            for tolerance in [0, 1, 2] {
+                assert_eq!(
+                    TREE.corrections_bytes(query, tolerance),
+                    TREE.corrections(&lossy, tolerance)
+                );
+            }
+        }
+        let mut count = 0;
+        WRAPPED.for_each_correction_bytes(b"\xffar", 1, |s| {
+            assert!(s.distance == 1);
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn query_chars() {
+        use crate::{distance_chars, QueryChars};
+
+        let long = "card".repeat(80);
+        for query in ["car", "cars", "", "c\u{e5}re", "e\u{301}", &long] {
+            let chars: Vec<char> = query.chars().collect();
+            assert_eq!(chars[..].len_hint(), query.len_hint());
+            for tolerance in [0, 1, 2] {
+                let expected = TREE.corrections(query, tolerance);
+                assert_eq!(TREE.corrections_chars(query, tolerance), expected);
+                assert_eq!(TREE.corrections_chars(&chars[..], tolerance), expected);
+                #[cfg(feature = "heapless")]
+                if let Ok(query) = heapless::String::<16>::try_from(query) {
+                    assert_eq!(TREE.corrections_chars(&query, tolerance), expected);
+                }
+            }
+            for word in ["card", "the", ""] {
+                assert_eq!(distance_chars(query, word), distance(query, word));
+                assert_eq!(distance_chars(&chars[..], word), distance(query, word));
+            }
+        }
+        let mut count = 0;
+        WRAPPED.for_each_correction_chars(&['c', 'a', 'r'][..], 1, |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(count, TREE.corrections("car", 1).len());
+    }
+
+    #[test]
+    fn distance_matches_levenshtein() {
+        let words: Vec<_> = TREE.iter().map(|n| n.word()).collect();
+        let long = "pneumonoultramicroscopicsilicovolcanoconiosis-and-then-some-more";
+        // fewer chars than bytes, on the stack only along the row
+        let (wide, wider) = ("ü".repeat(40), "ü".repeat(70));
+        let (wide, wider) = (wide.as_str(), wider.as_str());
+        for a in words
+            .iter()
+            .chain([&"", &"shes", &"ça", &long, &wide, &wider])
+        {
+            for b in words
+                .iter()
+                .chain([&"", &"care", &"cåre", &long, &wide, &wider])
+            {
+                assert_eq!(distance(a, b), levenshtein::levenshtein(a, b), "{a} {b}");
+            }
+        }
+    }
+
+    static WRAPPED: StaticBkTree = StaticBkTree::new(&TREE, WORD_COUNT, CHECKSUM);
+
+    #[test]
+    fn static_wrapper() {
+        assert_eq!(WRAPPED.word_count(), 8);
+        assert_eq!(WRAPPED.iter().count(), WRAPPED.word_count());
+        assert_eq!(WRAPPED.checksum(), checksum(TREE.iter().map(|n| n.word())));
+        assert!(WRAPPED.contains("card"));
+        assert!(!WRAPPED.contains("cards"));
+        assert_eq!(WRAPPED.best_match("cars", 1).unwrap().word, "car");
+        assert_eq!(WRAPPED.corrections("cars", 1).len(), 3);
+        assert!(core::ptr::eq(WRAPPED.root(), &TREE));
+    }
+
+    #[test]
+    fn emit_static_wrapper() {
+        let words = ["the", "car", "card", "the"];
+        let tree = BkTreeBuilder::new(words).static_wrapper(true).build();
+        assert!(tree.starts_with("static TREE_ROOT: Node = Node::new("));
+        assert!(tree.contains(
+            "static TREE: StaticBkTree = StaticBkTree::new(&TREE_ROOT, WORD_COUNT, CHECKSUM);"
+        ));
+        // duplicates are only stored once
+        assert!(tree.contains("const WORD_COUNT: usize = 3;"));
+        let checksum = checksum(["car", "the", "card"]);
+        assert!(tree.contains(&std::format!("const CHECKSUM: u32 = {checksum:#010x};")));
+    }
+
+    // what downstream code mocking the dictionary looks like
+    struct FakeDictionary(&'static [&'static str]);
+
+    impl Corrections for FakeDictionary {
+        fn for_each_correction(
+            &self,
+            word: &str,
+            tolerance: u8,
+            f: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+        ) {
+            for w in self.0 {
+                let distance = distance(w, word);
+                if distance <= tolerance as usize {
+                    let suggestion = Suggestion::new(word, w, distance as u8);
+                    if f(suggestion).is_break() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        fn contains(&self, word: &str) -> bool {
+            self.0.contains(&word)
+        }
+    }
+
+    fn autocorrect(dictionary: &impl Corrections, word: &str) -> Option<&'static str> {
+        match dictionary.contains(word) {
+            true => None,
+            false => dictionary.corrections(word, 2).first().map(|s| s.word),
+        }
+    }
+
+    #[test]
+    fn corrections_trait() {
+        let fake = FakeDictionary(&["hello", "help", "world"]);
+        assert_eq!(autocorrect(&fake, "helo"), Some("hello"));
+        assert_eq!(autocorrect(&fake, "world"), None);
+        assert_eq!(autocorrect(&fake, "zzzzzz"), None);
+
+        let tree: &'static Node = &TREE;
+        assert_eq!(autocorrect(&tree, "cars"), Some("car"));
+        assert_eq!(autocorrect(&WRAPPED, "cars"), Some("car"));
+        assert_eq!(autocorrect(&tree, "care"), None);
+        let mut first = None;
+        tree.for_each_correction("cars", 1, |s| {
+            first = Some(s.word);
+            ControlFlow::Break(())
+        });
+        assert!(first.is_some());
+    }
+
+    #[test]
+    fn invariants() {
+        assert_eq!(TREE.check_invariants(), Ok(()));
+
+        static CAR: Node = Node::new("car", 0, 0, &[], &[]);
+        static CARD: Node = Node::new("card", 0, 0, &[], &[]);
+
+        static WRONG: Node = Node::new("cart", 0, 0, &[2], &[&CAR]);
+        assert_eq!(
+            WRONG.check_invariants(),
+            Err(InvariantError::WrongBucket {
+                parent: "cart",
+                child: "car",
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        static DUPLICATE: Node = Node::new("car", 0, 0, &[], &[]);
+        static CART: Node = Node::new("cart", 0, 0, &[1], &[&DUPLICATE]);
+        static DUPLICATED: Node = Node::new("car", 0, 0, &[1], &[&CART]);
+        assert_eq!(
+            DUPLICATED.check_invariants(),
+            Err(InvariantError::DuplicateWord { word: "car" })
+        );
+
+        static CAKE: Node = Node::new("cake", 0, 0, &[2], &[&CARD]);
+        static SHARED: Node = Node::new("car", 0, 0, &[1, 2], &[&CARD, &CAKE]);
+        assert_eq!(
+            SHARED.check_invariants(),
+            Err(InvariantError::SharedNode { word: "card" })
+        );
+    }
+
+    #[test]
+    fn empty_query() {
+        let all: Vec<_> = TREE.iter().map(|n| n.word()).collect();
+        for tolerance in [0u8, 1, 255] {
+            let expected: Vec<_> = all
+                .iter()
+                .filter(|w| w.chars().count() <= tolerance as usize)
+                .collect();
+            let corrections = TREE.corrections("", tolerance);
+            assert_eq!(corrections.len(), expected.len());
+            assert!(corrections.iter().all(|s| expected.contains(&&s.word)));
+            let by_iter = TREE
+                .iter()
+                .filter(|n| distance(n.word(), "") <= tolerance as usize)
+                .count();
+            assert_eq!(by_iter, expected.len());
+            assert_eq!(
+                TREE.best_match("", tolerance).map(|s| s.word),
+                corrections.first().map(|s| s.word)
+            );
+            assert!(TREE.candidates("", tolerance).len() <= all.len());
+        }
+        assert!(TREE.best_match("", 0).is_none());
+        assert_eq!(TREE.best_match("", 2).unwrap().word, "he");
+        assert!(!TREE.contains(""));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty word")]
+    fn write_rejects_empty_word() {
+        let path = std::env::temp_dir().join("embedded-bktree-empty.test");
+        write::write_bktree(Some(path), &mut vec!["the", "", "car"]);
+    }
+
+    #[test]
+    fn errors() {
+        use super::{
+            AllocError, BlobError, BufTooSmall, Cancelled, DecodeError, InvariantError, LazyError,
+            NodeBuf, ParseError, QueryError, SourceError, StorageError, WriteError,
+        };
+        use std::{io::ErrorKind, string::ToString};
+
+        // boxable into anyhow and the like on the host
+        fn shown<E: std::error::Error + Send + Sync + 'static>(e: E) -> String {
+            e.to_string()
+        }
+        assert_eq!(shown(QueryError::Empty), "the query is empty");
+        assert_eq!(
+            shown(QueryError::DisallowedChar('1')),
+            "'1' isn't allowed in a query"
+        );
+        assert_eq!(shown(AllocError), "out of memory");
+        assert_eq!(
+            shown(BufTooSmall { needed: 3 }),
+            "the buffer is too small, 3 bytes are needed"
+        );
+        assert_eq!(
+            shown(Cancelled { partial: vec![1] }),
+            "the search was cancelled"
+        );
+        assert_eq!(
+            shown(InvariantError::DuplicateWord { word: "car" }),
+            "\"car\" is in more than one node"
+        );
+        assert_eq!(
+            shown(LazyError::Duplicate {
+                line: 3,
+                word: "car"
+            }),
+            "\"car\" on line 3 is already in the list"
+        );
+        assert_eq!(
+            shown(ParseError::BaseMismatch {
+                expected: 1,
+                found: 2
+            }),
+            "the supplement is for dictionary 0x00000002, not 0x00000001"
+        );
+        assert_eq!(
+            shown(SourceError::BadWord { offset: 7 }),
+            "can't parse the word at byte 7"
+        );
+
+        let mut buf = NodeBuf::new();
+        assert_eq!(buf.decode(&[2, b'a']), Err(DecodeError::Truncated));
+        assert_eq!(buf.decode(&[1, 0xff, 0]), Err(DecodeError::NotUtf8));
+        assert_eq!(buf.decode(&[65]), Err(DecodeError::WordTooLong { len: 65 }));
+        assert_eq!(
+            buf.decode(&[0, 16]),
+            Err(DecodeError::TooManyChildren { count: 16 })
+        );
+        // a storage error wraps the blob's, which wraps the reader's
+        let layered: StorageError<BlobError<DecodeError>> =
+            BlobError::from(DecodeError::Truncated).into();
+        assert_eq!(
+            layered,
+            StorageError::Storage(BlobError::Read(DecodeError::Truncated))
+        );
+        assert_eq!(shown(layered), "can't read the blob: node is truncated");
+
+        let missing = BkTreeBuilder::new(["car", "card"]).try_build();
+        assert_eq!(missing, Err(WriteError::MissingRoot));
+        assert_eq!(
+            shown(missing.unwrap_err()),
+            "the was not found in word_list"
+        );
+        let path = std::env::temp_dir().join("embedded-bktree-missing/words.txt");
+        let unreadable = BkTreeBuilder::new(["the"]).source_file(&path, 1);
+        assert_eq!(
+            unreadable.try_build_storage(),
+            Err(WriteError::Read {
+                path: path.clone(),
+                kind: ErrorKind::NotFound
+            })
+        );
+        let unwritable = BkTreeBuilder::new(["the"]).try_write(Some(path.clone()));
+        assert!(matches!(unwritable, Err(WriteError::Write { .. })));
+        let long = "a".repeat(super::MAX_STORED_WORD + 1);
+        assert_eq!(
+            BkTreeBuilder::new(["the", &long]).try_build_storage(),
+            Err(WriteError::WordTooLong { word: long })
+        );
+    }
+
+    #[test]
+    fn contains() {
+        for word in ["the", "them", "she", "he", "car", "care", "card", "cake"] {
+            assert!(TREE.contains(word));
+        }
+        assert!(!TREE.contains("cars"));
+        assert!(!TREE.contains("supercalifragilistic"));
+    }
+
+    #[test]
+    fn segmentation_recorded() {
+        let tree = std::fs::read_to_string(FIXTURE).unwrap();
+        let check = match Node::GRAPHEMES {
+            true => "assert!(Node::GRAPHEMES",
+            false => "assert!(!Node::GRAPHEMES",
+        };
+        assert!(tree.contains(check));
+    }
+
+    #[cfg(feature = "grapheme")]
+    #[test]
+    fn grapheme_distance() {
+        use super::distance::{char_distance, distance, grapheme_distance};
+        // decomposed é substituted for a plain letter
+        assert_eq!(grapheme_distance("cafe\u{301}", "cafa"), 1);
+        assert_eq!(char_distance("cafe\u{301}", "cafa"), 2);
+        // flags are a pair of regional indicators
+        assert_eq!(grapheme_distance("go 🇺🇸", "go 🇬🇧"), 1);
+        assert_eq!(char_distance("go 🇺🇸", "go 🇬🇧"), 2);
+        assert_eq!(distance("cafe\u{301}", "cafa"), 1);
+        assert_eq!(
+            super::edit_script("go 🇺🇸", "go 🇬🇧").ops,
+            [super::Op::Keep(3), super::Op::Substitute("🇺🇸", "🇬🇧")]
+        );
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_format() {
+        use super::Suggestion;
+
+        // never logged, only has to compile against the real traits
+        struct Both(&'static Node, Suggestion<'static>);
+        impl defmt::Format for Both {
+            fn format(&self, f: defmt::Formatter) {
+                defmt::write!(f, "{} {}", self.0, self.1);
+            }
+        }
+        fn is_format<T: defmt::Format>(_: &T) {}
+        is_format(&Both(&TREE, TREE.corrections("cars", 1)[0]));
     }
 }
@@ -0,0 +1,68 @@
+// searches the generated dictionary and the user's own words as one
+
+extern crate alloc;
+
+use super::{read::sort_by_distance, Node, RuntimeBkTree, Suggestion};
+use alloc::vec::Vec;
+
+/// the static tree with an optional [`RuntimeBkTree`] of user words on
+/// top. results from both are merged by distance, a user word comes first
+/// on equal distance and a word in both is only returned once.
+///
+/// without user words every search goes straight to the static tree
+#[derive(Debug, Clone, Copy)]
+pub struct Overlay<'u> {
+    root: &'static Node,
+    user: Option<&'u RuntimeBkTree>,
+}
+
+impl<'u> Overlay<'u> {
+    pub const fn new(root: &'static Node, user: Option<&'u RuntimeBkTree>) -> Self {
+        Self { root, user }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.root.contains(word) || self.user.is_some_and(|u| u.contains(word))
+    }
+
+    /// every word within `tolerance` of `word` from either tree, sorted by
+    /// distance
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'u>> {
+        let Some(user) = self.user else {
+            return self.root.corrections(word, tolerance);
+        };
+        let mut corrections = user.corrections(word, tolerance);
+        let shared = corrections.len();
+        for s in self.root.corrections(word, tolerance) {
+            if !corrections[..shared].iter().any(|u| u.word == s.word) {
+                corrections.push(s);
+            }
+        }
+        // stable, so user words stay ahead on equal distance
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    /// the closest word within `tolerance` from either tree, the user's on
+    /// equal distance
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<Suggestion<'u>> {
+        let found = self.root.best_match(word, tolerance);
+        let Some(user) = self.user else {
+            return found;
+        };
+        match (user.best_match(word, tolerance), found) {
+            (Some(u), Some(s)) if s.distance < u.distance => Some(s),
+            (Some(u), _) => Some(u),
+            (None, s) => s,
+        }
+    }
+
+    /// the word to replace `word` with, `None` when it's already known or
+    /// nothing is within `tolerance`
+    pub fn autocorrect(&self, word: &str, tolerance: u8) -> Option<&'u str> {
+        match self.contains(word) {
+            true => None,
+            false => self.best_match(word, tolerance).map(|s| s.word),
+        }
+    }
+}
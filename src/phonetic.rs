@@ -0,0 +1,189 @@
+// a cut down metaphone, so words that sound alike get the same code even
+// when they're spelled too differently for edit distance. shared by the
+// writer, which bakes the codes into the generated index, and the reader,
+// so the rules must never change without regenerating
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(all(feature = "read", feature = "alloc"))]
+use super::{distance::distance, read::sort_by_distance, Node, Suggestion};
+#[cfg(all(feature = "read", feature = "alloc"))]
+use alloc::vec::Vec;
+
+/// up to four consonant sounds, padded with 0
+pub type PhoneticCode = [u8; 4];
+
+/// the phonetic code of `word`, only ascii letters count and case doesn't
+/// matter. "phone" and "fone" are both `FN`
+pub const fn phonetic_code(word: &str) -> PhoneticCode {
+    let bytes = word.as_bytes();
+    let mut code = [0; 4];
+    let mut len = 0;
+    let mut i = 0;
+    // the silent first letter of kn, gn, pn, wr and ae
+    if bytes.len() > 1 {
+        let (a, b) = (lower(bytes[0]), lower(bytes[1]));
+        if matches!(
+            (a, b),
+            (b'k' | b'g' | b'p', b'n') | (b'w', b'r') | (b'a', b'e')
+        ) {
+            i = 1;
+        }
+    }
+    let start = i;
+    while i < bytes.len() && len < code.len() {
+        let c = lower(bytes[i]);
+        let prev = if i > start { lower(bytes[i - 1]) } else { 0 };
+        let next = if i + 1 < bytes.len() {
+            lower(bytes[i + 1])
+        } else {
+            0
+        };
+        let after = if i + 2 < bytes.len() {
+            lower(bytes[i + 2])
+        } else {
+            0
+        };
+        i += 1;
+        // doubled letters sound once, except cc
+        if c == prev && c != b'c' {
+            continue;
+        }
+        let sound = match c {
+            b'a' | b'e' | b'i' | b'o' | b'u' => match i - 1 == start {
+                true => b'A',
+                false => 0,
+            },
+            b'b' => match prev == b'm' && next == 0 {
+                true => 0,
+                false => b'B',
+            },
+            b'c' => match (next, after) {
+                (b'i', b'a') | (b'h', _) => b'X',
+                (b'i' | b'e' | b'y', _) => b'S',
+                _ => b'K',
+            },
+            b'd' => match (next, after) {
+                (b'g', b'e' | b'i' | b'y') => b'J',
+                _ => b'T',
+            },
+            b'g' => match next {
+                b'h' if !is_vowel(after) => 0,
+                b'e' | b'i' | b'y' => b'J',
+                _ => b'K',
+            },
+            b'h' => match is_vowel(next) && !matches!(prev, b'c' | b's' | b'p' | b't' | b'g') {
+                true => b'H',
+                false => 0,
+            },
+            b'k' => match prev == b'c' {
+                true => 0,
+                false => b'K',
+            },
+            b'p' => match next == b'h' {
+                true => b'F',
+                false => b'P',
+            },
+            b'q' => b'K',
+            b's' => match (next, after) {
+                (b'h', _) | (b'i', b'o' | b'a') => b'X',
+                _ => b'S',
+            },
+            b't' => match (next, after) {
+                (b'i', b'o' | b'a') => b'X',
+                (b'h', _) => b'0',
+                _ => b'T',
+            },
+            b'v' => b'F',
+            b'w' | b'y' => match is_vowel(next) {
+                true => c.to_ascii_uppercase(),
+                false => 0,
+            },
+            b'x' => {
+                code[len] = b'K';
+                len += 1;
+                b'S'
+            }
+            b'z' => b'S',
+            b'f' | b'j' | b'l' | b'm' | b'n' | b'r' => c.to_ascii_uppercase(),
+            _ => 0,
+        };
+        if sound != 0 && len < code.len() {
+            code[len] = sound;
+            len += 1;
+        }
+    }
+    code
+}
+
+const fn lower(b: u8) -> u8 {
+    b.to_ascii_lowercase()
+}
+
+const fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+/// the generated `PHONETIC` index, every word of the tree under its
+/// [`phonetic_code`] sorted by code
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, Copy)]
+pub struct PhoneticIndex {
+    entries: &'static [(PhoneticCode, &'static str)],
+}
+
+#[cfg(feature = "read")]
+impl PhoneticIndex {
+    /// `entries` sorted by code, as the writer emits them
+    pub const fn new(entries: &'static [(PhoneticCode, &'static str)]) -> Self {
+        Self { entries }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the words that sound like `word`, in the order they were indexed.
+    /// a word without any ascii letters has no candidates
+    pub fn phonetic_candidates(&self, word: &str) -> impl Iterator<Item = &'static str> {
+        let code = phonetic_code(word);
+        let start = self.entries.partition_point(|(c, _)| *c < code);
+        let entries = match code == [0; 4] {
+            true => &[],
+            false => &self.entries[start..],
+        };
+        entries
+            .iter()
+            .take_while(move |(c, _)| *c == code)
+            .map(|(_, w)| *w)
+    }
+}
+
+#[cfg(all(feature = "read", feature = "alloc"))]
+impl Node {
+    /// [`Node::corrections`] along with every word `index` says sounds
+    /// like `word`, however far it is. each word once, sorted by distance
+    /// with the tree's hits first on equal distance
+    pub fn phonetic_corrections(
+        &'static self,
+        index: &PhoneticIndex,
+        word: &str,
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        let mut corrections = self.corrections(word, tolerance);
+        let found = corrections.len();
+        for candidate in index.phonetic_candidates(word) {
+            if !corrections[..found].iter().any(|s| s.word == candidate) {
+                let d = distance(candidate, word).min(u8::MAX as usize);
+                corrections.push(Suggestion::new(word, candidate, d as u8));
+            }
+        }
+        // stable, so tree hits stay ahead
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+}
@@ -0,0 +1,99 @@
+// trees in avr program memory. statics there can't be dereferenced like
+// normal memory, every byte goes through `lpm`, so the writer emits the
+// storage blob in `.progmem.data` and nodes are read out of it one at a
+// time like they would be from external flash
+
+use super::{BlobError, BlobStorage, DecodeError, NodeBuf, NodeStorage};
+use core::ptr;
+
+/// loads bytes from memory the cpu can't just dereference.
+///
+/// on avr this is where `avr_progmem::raw::read_byte` or a `lpm` loop
+/// goes, elsewhere [`NearRead`] is a plain copy
+pub trait FarRead {
+    /// fills `buf` with the `buf.len()` bytes at `at`
+    ///
+    /// # Safety
+    ///
+    /// the bytes are all inside a [`ProgmemBlob`]
+    unsafe fn read(&mut self, at: *const u8, buf: &mut [u8]);
+}
+
+/// [`FarRead`] for targets where the blob is in normal memory
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NearRead;
+
+impl FarRead for NearRead {
+    unsafe fn read(&mut self, at: *const u8, buf: &mut [u8]) {
+        unsafe { ptr::copy_nonoverlapping(at, buf.as_mut_ptr(), buf.len()) }
+    }
+}
+
+/// the blob `BkTreeBuilder::progmem` writes, only ever read through a
+/// [`FarRead`]
+#[derive(Debug, Clone, Copy)]
+pub struct ProgmemBlob {
+    start: *const u8,
+    len: usize,
+}
+
+// nothing is ever written through it
+unsafe impl Sync for ProgmemBlob {}
+
+impl ProgmemBlob {
+    /// `blob` is the generated `_BLOB` static, which may not be in data
+    /// memory
+    pub const fn new(blob: &'static [u8]) -> Self {
+        Self {
+            start: blob.as_ptr(),
+            len: blob.len(),
+        }
+    }
+
+    /// the blob's bytes
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the tree's nodes through `reader` for
+    /// [`corrections_in_storage`](crate::corrections_in_storage), the root
+    /// is node 0
+    pub const fn storage<R: FarRead>(&self, reader: R) -> ProgmemStorage<R> {
+        ProgmemStorage {
+            blob: *self,
+            reader,
+        }
+    }
+}
+
+/// [`NodeStorage`] over a [`ProgmemBlob`]
+#[derive(Debug)]
+pub struct ProgmemStorage<R> {
+    blob: ProgmemBlob,
+    reader: R,
+}
+
+impl<R: FarRead> NodeStorage for ProgmemStorage<R> {
+    type Error = BlobError<DecodeError>;
+
+    fn read_node(&mut self, id: u32, buf: &mut NodeBuf) -> Result<(), Self::Error> {
+        let Self { blob, reader } = self;
+        BlobStorage::new(|offset: u32, bytes: &mut [u8]| {
+            // past a 16 bit usize is past the blob, not wrapped back into it
+            let offset = usize::try_from(offset).map_err(|_| DecodeError::Truncated)?;
+            match offset.checked_add(bytes.len()) {
+                Some(end) if end <= blob.len => {
+                    // in bounds of the blob just checked
+                    unsafe { reader.read(blob.start.add(offset), bytes) };
+                    Ok(())
+                }
+                _ => Err(DecodeError::Truncated),
+            }
+        })
+        .read_node(id, buf)
+    }
+}
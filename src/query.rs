@@ -0,0 +1,151 @@
+// checks on what a user typed before it's searched for. the searches
+// themselves take anything, a sentence or a pasted page is just a long
+// word to them, so callers that don't clean up input go through here
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+use super::{Node, StaticBkTree, Suggestion};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+/// what [`validate_query`] lets through
+#[derive(Debug, Clone, Copy)]
+pub struct QueryLimits {
+    /// longest query in chars
+    pub max_len: usize,
+    /// the chars a query can hold, whitespace is dealt with before this
+    /// is asked
+    pub allowed: fn(char) -> bool,
+    /// whether words can be separated by single spaces, for trees of
+    /// phrases. other whitespace is still refused
+    pub spaces: bool,
+}
+
+impl QueryLimits {
+    /// a single word of letters of any script, up to 32 chars
+    pub const WORD: Self = Self {
+        max_len: 32,
+        allowed: char::is_alphabetic,
+        spaces: false,
+    };
+
+    /// a phrase like `git cherry-pick`, letters, digits and ascii
+    /// punctuation up to 64 chars with single spaces between the words
+    pub const PHRASE: Self = Self {
+        max_len: 64,
+        allowed: phrase_char,
+        spaces: true,
+    };
+}
+
+fn phrase_char(c: char) -> bool {
+    c.is_alphanumeric() || c.is_ascii_punctuation()
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self::WORD
+    }
+}
+
+/// why [`validate_query`] refused a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueryError {
+    Empty,
+    /// more chars than `QueryLimits::max_len`
+    TooLong,
+    /// more than one word, or a newline or tab in it. with
+    /// `QueryLimits::spaces` a space at either end or two in a row
+    ContainsWhitespace,
+    /// the first char `QueryLimits::allowed` refused
+    DisallowedChar(char),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the query is empty"),
+            Self::TooLong => write!(f, "the query is too long"),
+            Self::ContainsWhitespace => write!(f, "the query is more than one word"),
+            Self::DisallowedChar(c) => write!(f, "{c:?} isn't allowed in a query"),
+        }
+    }
+}
+
+std_error!(QueryError);
+
+/// whether `word` is one word within `limits`. the length is checked
+/// first and only that many chars are looked at, so a huge input costs no
+/// more than a long word
+pub fn validate_query(word: &str, limits: &QueryLimits) -> Result<(), QueryError> {
+    if word.is_empty() {
+        return Err(QueryError::Empty);
+    }
+    if word.chars().nth(limits.max_len).is_some() {
+        return Err(QueryError::TooLong);
+    }
+    // words with one space between them
+    let phrase =
+        limits.spaces && !word.starts_with(' ') && !word.ends_with(' ') && !word.contains("  ");
+    if word
+        .chars()
+        .any(|c| c.is_whitespace() && !(phrase && c == ' '))
+    {
+        return Err(QueryError::ContainsWhitespace);
+    }
+    match word.chars().find(|&c| c != ' ' && !(limits.allowed)(c)) {
+        Some(c) => Err(QueryError::DisallowedChar(c)),
+        None => Ok(()),
+    }
+}
+
+impl Node {
+    /// [`Node::corrections`] after [`validate_query`], a refused query
+    /// isn't searched
+    #[cfg(feature = "alloc")]
+    pub fn checked_corrections(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        limits: &QueryLimits,
+    ) -> Result<Vec<Suggestion<'static>>, QueryError> {
+        validate_query(word, limits)?;
+        Ok(self.corrections(word, tolerance))
+    }
+
+    /// [`Node::best_match`] after [`validate_query`]
+    pub fn checked_best_match(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        limits: &QueryLimits,
+    ) -> Result<Option<Suggestion<'static>>, QueryError> {
+        validate_query(word, limits)?;
+        Ok(self.best_match(word, tolerance))
+    }
+}
+
+impl StaticBkTree {
+    /// see [`Node::checked_corrections`]
+    #[cfg(feature = "alloc")]
+    pub fn checked_corrections(
+        &self,
+        word: &str,
+        tolerance: u8,
+        limits: &QueryLimits,
+    ) -> Result<Vec<Suggestion<'static>>, QueryError> {
+        self.root().checked_corrections(word, tolerance, limits)
+    }
+
+    /// see [`Node::checked_best_match`]
+    pub fn checked_best_match(
+        &self,
+        word: &str,
+        tolerance: u8,
+        limits: &QueryLimits,
+    ) -> Result<Option<Suggestion<'static>>, QueryError> {
+        self.root().checked_best_match(word, tolerance, limits)
+    }
+}
@@ -0,0 +1,183 @@
+// queries that aren't a `&str`. the search is the one every query goes
+// through, only the measure it's handed is generic, so each query type
+// adds a closure and not another traversal
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(not(feature = "grapheme"))]
+use super::{distance::chars_distance, read::logged, SearchStats};
+use super::{Node, StaticBkTree, Suggestion};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(not(feature = "grapheme"))]
+use core::fmt::{self, Write};
+use core::ops::ControlFlow;
+
+/// a query the searches read a char at a time, so it doesn't have to be
+/// copied into a `&str` first. there's one for `str`, `[char]`,
+/// `heapless::String` with the `heapless` feature and references to each.
+/// bytes that may not be utf-8 go to [`Node::for_each_correction_bytes`]
+pub trait QueryChars {
+    /// the chars of the query, from the first every time it's called
+    fn chars(&self) -> impl Iterator<Item = char> + Clone;
+
+    /// how many chars there are, what a suggestion's confidence is
+    /// worked out against. `chars().count()` unless it's known for less
+    fn len_hint(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl QueryChars for str {
+    fn chars(&self) -> impl Iterator<Item = char> + Clone {
+        str::chars(self)
+    }
+}
+
+impl QueryChars for [char] {
+    fn chars(&self) -> impl Iterator<Item = char> + Clone {
+        self.iter().copied()
+    }
+
+    fn len_hint(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> QueryChars for heapless::String<N> {
+    fn chars(&self) -> impl Iterator<Item = char> + Clone {
+        self.as_str().chars()
+    }
+}
+
+impl<T: QueryChars + ?Sized> QueryChars for &T {
+    fn chars(&self) -> impl Iterator<Item = char> + Clone {
+        (**self).chars()
+    }
+
+    fn len_hint(&self) -> usize {
+        (**self).len_hint()
+    }
+}
+
+/// [`distance`](crate::distance) from a query of any [`QueryChars`]. with
+/// `grapheme` it's put in a str first, see
+/// [`Node::for_each_correction_chars`]
+pub fn distance_chars(query: impl QueryChars, word: &str) -> usize {
+    #[cfg(feature = "grapheme")]
+    return with_str(&query, |query| super::distance(query, word)).unwrap_or(usize::MAX);
+    #[cfg(not(feature = "grapheme"))]
+    return chars_distance(query.chars(), word);
+}
+
+impl Node {
+    /// [`Node::for_each_correction`] for a query of any [`QueryChars`],
+    /// read from where it is as the search goes. with `grapheme` it has to
+    /// be a str to find the clusters in, it's copied to the stack first
+    /// and past 256 bytes to the heap, or without `alloc` matches nothing
+    pub fn for_each_correction_chars(
+        &'static self,
+        query: impl QueryChars,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        #[cfg(feature = "grapheme")]
+        let _ = with_str(&query, |query| {
+            self.for_each_correction(query, tolerance, &mut f)
+        });
+        #[cfg(not(feature = "grapheme"))]
+        {
+            let chars = query.chars();
+            let query_len = query.len_hint();
+            let tolerance = tolerance as usize;
+            let _ = self.walk_by(
+                logged(&Shown(&query)),
+                &|w| chars_distance(chars.clone(), w),
+                tolerance,
+                0,
+                &mut SearchStats::default(),
+                &mut |node, distance| match distance <= tolerance {
+                    true => f(Suggestion::with_query_len(
+                        query_len,
+                        node.word(),
+                        distance as u8,
+                    )),
+                    false => ControlFlow::Continue(()),
+                },
+            );
+        }
+    }
+
+    /// [`Node::corrections`] for a query of any [`QueryChars`], see
+    /// [`Node::for_each_correction_chars`]
+    #[cfg(feature = "alloc")]
+    pub fn corrections_chars(
+        &'static self,
+        query: impl QueryChars,
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.for_each_correction_chars(query, tolerance, |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        super::read::sort_by_distance(&mut corrections);
+        corrections
+    }
+}
+
+impl StaticBkTree {
+    /// see [`Node::for_each_correction_chars`]
+    pub fn for_each_correction_chars(
+        &self,
+        query: impl QueryChars,
+        tolerance: u8,
+        f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        self.root().for_each_correction_chars(query, tolerance, f)
+    }
+
+    /// see [`Node::corrections_chars`]
+    #[cfg(feature = "alloc")]
+    pub fn corrections_chars(
+        &self,
+        query: impl QueryChars,
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        self.root().corrections_chars(query, tolerance)
+    }
+}
+
+// the query in `log` lines, quoted like a str
+#[cfg(not(feature = "grapheme"))]
+struct Shown<'q, Q>(&'q Q);
+
+#[cfg(not(feature = "grapheme"))]
+impl<Q: QueryChars> fmt::Debug for Shown<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars().flat_map(char::escape_debug) {
+            f.write_char(c)?;
+        }
+        f.write_char('"')
+    }
+}
+
+// `f` of the query as a str, grapheme clusters are only found in one.
+// none when it's past the buffer and there's nowhere else to put it
+#[cfg(feature = "grapheme")]
+fn with_str<R>(query: &impl QueryChars, f: impl FnOnce(&str) -> R) -> Option<R> {
+    let mut buf = [0; 256];
+    let mut len = 0;
+    for c in query.chars() {
+        let Some(slot) = buf.get_mut(len..len + c.len_utf8()) else {
+            #[cfg(feature = "alloc")]
+            return Some(f(&query.chars().collect::<alloc::string::String>()));
+            #[cfg(not(feature = "alloc"))]
+            return None;
+        };
+        len += c.encode_utf8(slot).len();
+    }
+    core::str::from_utf8(&buf[..len]).ok().map(f)
+}
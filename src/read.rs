@@ -0,0 +1,1958 @@
+#[cfg(not(feature = "small-code"))]
+use super::automaton::Automaton;
+use super::{
+    distance::{
+        ambiguous_distance, char_mask, distance, length, levenshtein_const, lossy_distance,
+        mask_bound, partial_distance, str_eq, wildcard_distance, LossyUnits,
+    },
+    CHILDREN_LENGTH,
+};
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::{collections::TryReserveError, vec, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{ControlFlow, Range, RangeInclusive},
+};
+
+/// a node of a generated tree. nodes compare, order and hash by their word
+/// alone: a tree never holds a word twice, so within one tree the word is
+/// the node. nodes of different trees with the same word are equal.
+///
+/// the index types don't depend on the target: `index` is a u32, buckets
+/// are u8 and distances and tolerances reach searches as u8. on a 16 bit
+/// target like avr or msp430 the whole tree shares the 64 KiB address
+/// space though, with `size_of::<Node>()` at 22 bytes plus
+/// `Node::EDGE_BYTES` (3) a child and the word itself that's about 2000
+/// words of 8 bytes at most, less whatever else lives there. bigger
+/// dictionaries go in a `ProgmemBlob` or other
+/// [`NodeStorage`](crate::NodeStorage), whose ids and offsets are u32
+#[derive(Debug, Clone)]
+pub struct Node {
+    word: &'static str,
+    index: u32,
+    mask: u32,
+    // see `Node::suppress`, a byte of what was padding
+    suppressed: bool,
+    // the bucket of each of `children`, ascending
+    #[cfg(not(feature = "array-children"))]
+    child_distances: &'static [u8],
+    #[cfg(not(feature = "array-children"))]
+    children: &'static [&'static Node],
+    // a slot for every bucket, the layout before `child_distances`. it
+    // costs 15 pointers a node however few children it has
+    #[cfg(feature = "array-children")]
+    children: [Option<&'static Node>; CHILDREN_LENGTH],
+}
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.word == other.word
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.word.cmp(other.word)
+    }
+}
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.word.hash(state)
+    }
+}
+
+impl Node {
+    /// whether distances are measured over grapheme clusters, checked
+    /// against the mode recorded in the generated tree
+    #[doc(hidden)]
+    pub const GRAPHEMES: bool = cfg!(feature = "grapheme");
+
+    /// bytes each child takes outside of its parent's `size_of::<Node>()`,
+    /// its slot in `children` and `child_distances`
+    #[doc(hidden)]
+    pub const EDGE_BYTES: usize = match cfg!(feature = "array-children") {
+        true => 0,
+        false => core::mem::size_of::<&Node>() + 1,
+    };
+
+    /// a node with `children` under the buckets in `child_distances`,
+    /// which have to be ascending. generated trees are built with it so
+    /// they compile with either layout of the children, the fields are
+    /// private and only read through [`Node::word`], [`Node::child_at`],
+    /// [`Node::children`] and the like
+    pub const fn new(
+        word: &'static str,
+        index: u32,
+        mask: u32,
+        child_distances: &'static [u8],
+        children: &'static [&'static Node],
+    ) -> Self {
+        assert!(
+            child_distances.len() == children.len(),
+            "a bucket for every child"
+        );
+        let mut i = 1;
+        while i < child_distances.len() {
+            assert!(
+                child_distances[i - 1] < child_distances[i],
+                "buckets out of order"
+            );
+            i += 1;
+        }
+        #[cfg(not(feature = "array-children"))]
+        return Self {
+            word,
+            index,
+            mask,
+            suppressed: false,
+            child_distances,
+            children,
+        };
+        #[cfg(feature = "array-children")]
+        {
+            let mut slots = [None; CHILDREN_LENGTH];
+            let mut i = 0;
+            while i < children.len() {
+                slots[child_distances[i] as usize] = Some(children[i]);
+                i += 1;
+            }
+            Self {
+                word,
+                index,
+                mask,
+                suppressed: false,
+                children: slots,
+            }
+        }
+    }
+
+    pub const fn word(&self) -> &'static str {
+        self.word
+    }
+
+    /// position of the node in [`Node::iter`] order from the root, `0` to
+    /// `WORD_COUNT - 1`, for tables kept alongside the tree
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// the writer's summary of which characters the word has, searches
+    /// use it to rule out a leaf without measuring its distance. 0 on a
+    /// node built by hand turns that off
+    pub const fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// the node with its word kept out of every suggestion, for a word
+    /// that must be known but never offered. [`Node::contains`] still
+    /// finds it and searches still go through it to the words below,
+    /// [`Node::include_suppressed`] hands it out again. the generated tree
+    /// calls it on the words of `BkTreeBuilder::suppress`
+    pub const fn suppress(mut self) -> Self {
+        self.suppressed = true;
+        self
+    }
+
+    /// see [`Node::suppress`]
+    pub const fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
+    /// the child in bucket `distance`
+    pub const fn child_at(&self, distance: usize) -> Option<&'static Node> {
+        #[cfg(not(feature = "array-children"))]
+        {
+            // a handful of entries at most, a scan beats a search
+            let mut i = 0;
+            while i < self.child_distances.len() {
+                if self.child_distances[i] as usize == distance {
+                    return Some(self.children[i]);
+                }
+                i += 1;
+            }
+            None
+        }
+        #[cfg(feature = "array-children")]
+        match distance < CHILDREN_LENGTH {
+            true => self.children[distance],
+            false => None,
+        }
+    }
+
+    /// every child along with its bucket, ascending
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = (usize, &'static Node)> + '_ {
+        #[cfg(not(feature = "array-children"))]
+        return self
+            .child_distances
+            .iter()
+            .map(|&bucket| bucket as usize)
+            .zip(self.children.iter().copied());
+        #[cfg(feature = "array-children")]
+        return self
+            .children
+            .iter()
+            .enumerate()
+            .filter_map(|(bucket, child)| Some((bucket, (*child)?)));
+    }
+
+    /// whether the node has no children
+    pub fn is_leaf(&self) -> bool {
+        self.children().next().is_none()
+    }
+
+    /// the tree down to `max_depth` levels below this node for `{}`, one
+    /// word per line under its parent, indented two spaces a level and
+    /// prefixed with its bucket. a `...` line stands in for the children
+    /// past the limit. `max_depth` is capped at [`MAX_DISPLAY_DEPTH`]
+    pub fn display(&'static self, max_depth: usize) -> TreeDisplay {
+        TreeDisplay {
+            root: self,
+            max_depth: max_depth.min(MAX_DISPLAY_DEPTH),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn iter(&'static self) -> NodeIterator {
+        NodeIterator::new(self)
+    }
+
+    /// the node at position `i` of [`Node::iter`] from this one, `None`
+    /// past the last. a generated tree goes down the one path to it by
+    /// `index`, a tree built by hand without the numbering is counted
+    /// through. neither allocates
+    pub fn nth(&'static self, i: usize) -> Option<&'static Node> {
+        if !self.numbered() {
+            let mut i = i;
+            return self.counted(&mut i);
+        }
+        let target = self.index as usize + i;
+        let mut node = self;
+        while node.index() as usize != target {
+            // children come in iter order, so their indexes ascend
+            node = node
+                .children()
+                .rev()
+                .take_while(|(_, child)| child.index() as usize <= target)
+                .last()?
+                .1;
+        }
+        Some(node)
+    }
+
+    /// the nodes at positions `range` of [`Node::iter`], each found like
+    /// [`Node::nth`], for showing the dictionary a page at a time
+    pub fn range(&'static self, range: Range<usize>) -> impl Iterator<Item = &'static Node> {
+        range.map_while(move |i| self.nth(i))
+    }
+
+    // whether the first child in iter order comes right after this node,
+    // as the writer numbers them
+    fn numbered(&self) -> bool {
+        self.children().next_back().map_or(true, |(_, child)| {
+            child.index() as usize == self.index as usize + 1
+        })
+    }
+
+    // `nth` by counting down `i` in iter order
+    fn counted(&'static self, i: &mut usize) -> Option<&'static Node> {
+        if *i == 0 {
+            return Some(self);
+        }
+        *i -= 1;
+        self.children()
+            .rev()
+            .find_map(|(_, child)| child.counted(i))
+    }
+
+    /// [`Node::iter`] that yields an [`AllocError`], and then stops, when
+    /// its stack can't grow. `depth` levels are reserved up front, the
+    /// tree's depth plus one never needs more
+    #[cfg(feature = "alloc")]
+    pub fn try_iter(&'static self, depth: usize) -> Result<TryNodeIterator, AllocError> {
+        let mut stack = Vec::new();
+        stack.try_reserve_exact(depth.max(1))?;
+        stack.push((0, self));
+        Ok(TryNodeIterator {
+            nodes: NodeIterator { stack, first: true },
+            failed: false,
+        })
+    }
+
+    #[cfg(feature = "alloc")]
+    /// returns every word within `tolerance` edits of `word`,
+    /// sorted by distance (ties keep traversal order)
+    pub fn corrections(&'static self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        let _ = self.walk(
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut |s| {
+                corrections.push(s);
+                ControlFlow::Continue(())
+            },
+        );
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    #[cfg(feature = "alloc")]
+    /// [`Node::corrections`] that hands back [`AllocError`] instead of
+    /// aborting when the allocator runs out. room for `capacity`
+    /// suggestions is reserved up front, a good guess is one allocation
+    pub fn try_corrections(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        capacity: usize,
+    ) -> Result<Vec<Suggestion<'static>>, AllocError> {
+        let mut corrections = Vec::new();
+        corrections.try_reserve_exact(capacity)?;
+        let mut result = Ok(());
+        let _ = self.walk(
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut |s| {
+                result = corrections.try_reserve(1);
+                if result.is_err() {
+                    return ControlFlow::Break(());
+                }
+                // kept sorted as they come, the same order as `corrections`
+                // without the buffer a sort could ask for
+                let at = corrections.partition_point(|c: &Suggestion| c.distance <= s.distance);
+                corrections.insert(at, s);
+                ControlFlow::Continue(())
+            },
+        );
+        result?;
+        Ok(corrections)
+    }
+
+    /// the `K` closest words within `tolerance` of `word` without allocating,
+    /// along with how many of the slots were filled.
+    ///
+    /// results are sorted by distance. on equal distance the word found
+    /// first in traversal order wins, so this always agrees with
+    /// truncating [`Node::corrections`] to `K` entries
+    pub fn best_k_into<const K: usize>(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+    ) -> ([Option<(&'static str, u8)>; K], usize) {
+        let mut best = BestK::<K>::new();
+        let _ = self.walk(
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut |s| {
+                best.insert(s.word, s.distance);
+                ControlFlow::Continue(())
+            },
+        );
+        best.into_parts()
+    }
+
+    /// calls `f` with every word within `tolerance` of `word` in traversal
+    /// order until it breaks, without allocating
+    pub fn for_each_correction(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let _ = self.walk(
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut f,
+        );
+    }
+
+    /// [`Node::for_each_correction`] for a query of raw bytes, off a uart
+    /// say, that may not be valid utf-8. each invalid sequence is read as
+    /// a single U+FFFD the way `String::from_utf8_lossy` does, decoded as
+    /// the search goes rather than copied out. a corrupt byte between two
+    /// letters is one substitution, so it takes one of `tolerance`
+    pub fn for_each_correction_bytes(
+        &'static self,
+        query: &[u8],
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let query_len = LossyUnits::new(query).count();
+        let measure = |w: &str| lossy_distance(query, w);
+        let tolerance = tolerance as usize;
+        let _ = self.walk_by(
+            logged(&query),
+            &measure,
+            tolerance,
+            0,
+            &mut SearchStats::default(),
+            &mut |node, distance| {
+                if distance <= tolerance {
+                    f(Suggestion::with_query_len(
+                        query_len,
+                        node.word(),
+                        distance as u8,
+                    ))?;
+                }
+                ControlFlow::Continue(())
+            },
+        );
+    }
+
+    /// every word within `tolerance` of the bytes `query`, sorted by
+    /// distance like [`Node::corrections`]. see
+    /// [`Node::for_each_correction_bytes`]
+    #[cfg(feature = "alloc")]
+    pub fn corrections_bytes(
+        &'static self,
+        query: &[u8],
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.for_each_correction_bytes(query, tolerance, |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    /// [`Node::for_each_correction`] that asks `cancel` every
+    /// [`CANCEL_INTERVAL`] nodes visited whether to give up, so another
+    /// task can stop a search whose query is already stale. `Cancelled`
+    /// when it did, the words `f` was given so far are all that was found.
+    /// a flag set from an interrupt or another task works on any target
+    /// with atomic loads, thumbv6m included:
+    /// `|| STALE.load(Ordering::Relaxed)` with `STALE: AtomicBool`
+    pub fn for_each_correction_cancellable(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        cancel: impl Fn() -> bool,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) -> Result<(), Cancelled<()>> {
+        let tolerance = tolerance as usize;
+        let (mut visited, mut cancelled) = (0, false);
+        let _ = self.walk_nodes(
+            word,
+            tolerance,
+            &mut SearchStats::default(),
+            &mut |node, distance| {
+                visited += 1;
+                if visited % CANCEL_INTERVAL == 0 && cancel() {
+                    cancelled = true;
+                    return ControlFlow::Break(());
+                }
+                match distance <= tolerance {
+                    true => f(Suggestion::new(word, node.word(), distance as u8)),
+                    false => ControlFlow::Continue(()),
+                }
+            },
+        );
+        match cancelled {
+            true => Err(Cancelled { partial: () }),
+            false => Ok(()),
+        }
+    }
+
+    /// [`Node::corrections`] that can be cancelled, see
+    /// [`Node::for_each_correction_cancellable`]. a cancelled search still
+    /// hands back what it had found, sorted the same way
+    #[cfg(feature = "alloc")]
+    pub fn cancellable_corrections(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        cancel: impl Fn() -> bool,
+    ) -> Result<Vec<Suggestion<'static>>, Cancelled<Vec<Suggestion<'static>>>> {
+        let mut corrections = Vec::new();
+        let result = self.for_each_correction_cancellable(word, tolerance, cancel, |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        match result {
+            Ok(()) => Ok(corrections),
+            Err(_) => Err(Cancelled {
+                partial: corrections,
+            }),
+        }
+    }
+
+    /// estimated static footprint of the tree below this node. `node_bytes`
+    /// counts `size_of::<Node>()` per node (padding included) and the
+    /// children slices, and
+    /// `string_bytes` the word bytes, words sharing storage are counted
+    /// once per node so this is an upper bound for interned strings
+    #[cfg(feature = "alloc")]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            usage.nodes += 1;
+            usage.node_bytes += core::mem::size_of::<Node>();
+            usage.string_bytes += node.word().len();
+            for (_, child) in node.children() {
+                usage.node_bytes += Self::EDGE_BYTES;
+                stack.push(child);
+            }
+        }
+        usage
+    }
+
+    /// how long the words below this node are, in the units `distance`
+    /// counts, to pick a tolerance with [`suggested_tolerance`]. the
+    /// writer's `BkTreeBuilder::length_stats` has the same without the
+    /// walk
+    #[cfg(feature = "alloc")]
+    pub fn length_stats(&'static self) -> LengthStats {
+        let mut stats = LengthStats::EMPTY;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            stats.add(length(node.word()));
+            stack.extend(node.children().map(|(_, child)| child));
+        }
+        stats
+    }
+
+    /// whether any word is within `tolerance` of `word`, stopping at the
+    /// first one found
+    pub fn exists_within(&'static self, word: &str, tolerance: u8) -> bool {
+        let mut found = false;
+        self.for_each_correction(word, tolerance, |_| {
+            found = true;
+            ControlFlow::Break(())
+        });
+        found
+    }
+
+    /// how many words are within `tolerance` of `word`
+    pub fn count_within(&'static self, word: &str, tolerance: u8) -> usize {
+        let mut count = 0;
+        self.for_each_correction(word, tolerance, |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    /// how much work searching for `word` takes
+    pub fn search_stats(&'static self, word: &str, tolerance: u8) -> SearchStats {
+        let mut stats = SearchStats::default();
+        let _ = self.walk(word, tolerance as usize, &mut stats, &mut |_| {
+            ControlFlow::Continue(())
+        });
+        stats
+    }
+
+    // every word within tolerance, in traversal order
+    pub(crate) fn walk(
+        &'static self,
+        word: &str,
+        tolerance: usize,
+        stats: &mut SearchStats,
+        visit: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let tree = &Generated::default();
+        self.walk_matches(tree, word, tolerance, stats, &mut |node, distance| {
+            visit(Suggestion::new(word, node.word(), distance))
+        })
+    }
+
+    // every node of `tree` within tolerance with its distance, in
+    // traversal order
+    fn walk_matches(
+        &'static self,
+        tree: &Generated,
+        word: &str,
+        tolerance: usize,
+        stats: &mut SearchStats,
+        visit: &mut dyn FnMut(&'static Node, u8) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        // exact matches only, one bucket per level like `contains`
+        if tolerance == 0 {
+            return match self.exact(word, stats) {
+                Some(node) if !tree.suppressed(node) => {
+                    debug!("{word:?}: accepted {:?} at 0", node.word());
+                    visit(node, 0)
+                }
+                _ => {
+                    trace!("{word:?}: no exact match");
+                    ControlFlow::Continue(())
+                }
+            };
+        }
+        self.walk_nodes_of(tree, word, tolerance, stats, &mut |node, distance| {
+            if distance <= tolerance {
+                debug!("{word:?}: accepted {:?} at {distance}", node.word());
+                visit(node, distance as u8)?;
+            } else {
+                trace!("{word:?}: rejected {:?} at {distance}", node.word());
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    // depth first over every node in a bucket that can still hold a match,
+    // children in ascending bucket order, along with its distance
+    pub(crate) fn walk_nodes(
+        &'static self,
+        word: &str,
+        tolerance: usize,
+        stats: &mut SearchStats,
+        visit: &mut dyn FnMut(&'static Node, usize) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        self.walk_nodes_of(&Generated::default(), word, tolerance, stats, visit)
+    }
+
+    // `walk_nodes` over `tree`
+    fn walk_nodes_of(
+        &'static self,
+        tree: &Generated,
+        word: &str,
+        tolerance: usize,
+        stats: &mut SearchStats,
+        visit: &mut dyn FnMut(&'static Node, usize) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        walk_tree(
+            tree,
+            self,
+            logged(&word),
+            &|w| distance(w, word),
+            &LeafCheck::new(word, tolerance),
+            tolerance,
+            0,
+            stats,
+            visit,
+        )
+    }
+
+    // `walk_nodes` with any measure, see `walk_tree`
+    pub(crate) fn walk_by(
+        &'static self,
+        query: &dyn fmt::Debug,
+        measure: &dyn Fn(&str) -> usize,
+        tolerance: usize,
+        slack: usize,
+        stats: &mut SearchStats,
+        visit: &mut dyn FnMut(&'static Node, usize) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        walk_tree(
+            &Generated::default(),
+            self,
+            query,
+            measure,
+            &LeafCheck::default(),
+            tolerance,
+            slack,
+            stats,
+            visit,
+        )
+    }
+
+    /// calls `f` with every word matching `pattern` within `tolerance`, in
+    /// traversal order until it breaks, where `wildcard` in the pattern
+    /// matches any one character at no cost. "c?re" finds "care" and
+    /// "core" at distance 0.
+    ///
+    /// a wildcard still counts as a character, so "c?" is 1 from "c" and
+    /// a pattern of only wildcards matches every word of that length. each
+    /// one widens the buckets searched, the more there are the closer this
+    /// gets to looking at every word
+    pub fn for_each_wildcard_match(
+        &'static self,
+        pattern: &str,
+        wildcard: char,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        // a wildcard filled in wrong against a node costs at most one edit
+        let slack = pattern.matches(wildcard).count();
+        let measure = |w: &str| wildcard_distance(pattern, w, wildcard);
+        let tolerance = tolerance as usize;
+        let _ = self.walk_by(
+            logged(&pattern),
+            &measure,
+            tolerance,
+            slack,
+            &mut SearchStats::default(),
+            &mut |node, distance| {
+                if distance <= tolerance {
+                    f(Suggestion::new(pattern, node.word(), distance as u8))?;
+                }
+                ControlFlow::Continue(())
+            },
+        );
+    }
+
+    /// calls `f` with every word within `tolerance` of `query`, in traversal
+    /// order until it breaks, where a character of the word matching any
+    /// of the candidates at its position costs nothing. an empty set
+    /// matches no character.
+    ///
+    /// every position with more than one candidate widens the buckets
+    /// searched, like a wildcard does in [`Node::for_each_wildcard_match`]
+    pub fn for_each_ambiguous_match(
+        &'static self,
+        query: AmbiguousQuery,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let slack = query.0.iter().filter(|set| set.len() > 1).count();
+        let measure = |w: &str| ambiguous_distance(query.0, w);
+        let tolerance = tolerance as usize;
+        let _ = self.walk_by(
+            logged(&query.0),
+            &measure,
+            tolerance,
+            slack,
+            &mut SearchStats::default(),
+            &mut |node, distance| {
+                if distance <= tolerance {
+                    let s = Suggestion::with_query_len(query.0.len(), node.word(), distance as u8);
+                    f(s)?;
+                }
+                ControlFlow::Continue(())
+            },
+        );
+    }
+
+    /// calls `f` with every word some stretch of which is within
+    /// `tolerance` of `word`, in traversal order until it breaks. the
+    /// characters of the dictionary word around the stretch are free, so
+    /// "volts" is 0 from "millivolts" and a suggestion's distance is that
+    /// partial one.
+    ///
+    /// the partial distance doesn't keep to the triangle inequality the
+    /// tree is built on, so only the low buckets of a node can be ruled
+    /// out and every bucket above is searched. expect close to every word
+    /// to be measured, each for the whole dp, several times a normal search
+    ///
+    /// without the `alloc` feature a query and a word both longer than 63
+    /// characters are taken to be the query's length apart, the most they
+    /// can be, so a long query may miss long words. a long word alone is
+    /// measured in full
+    pub fn for_each_partial_match(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let _ = self.walk_partial(word, length(word), tolerance as usize, &mut f);
+    }
+
+    // a word `w` in bucket `k` of a node `n` with partial distance `p`
+    // through its best stretch `s` is `len(w) - len(s)` deletions and `p`
+    // edits from the query, and `len(s)` is at least `len(q) - p`, so
+    // `d(q, w) <= 2p + len(w) - len(q)`. with `d(q, n) - k <= d(q, w)` and
+    // `len(w) <= len(n) + k` a match in the bucket needs
+    // `2k >= d(q, n) + len(q) - len(n) - 2t`. there's no bound from above
+    fn walk_partial(
+        &'static self,
+        word: &str,
+        word_len: usize,
+        tolerance: usize,
+        f: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let partial = partial_distance(word, self.word);
+        if partial <= tolerance && !self.suppressed {
+            f(Suggestion::new(word, self.word, partial as u8))?;
+        }
+        let lowest = (distance(word, self.word) + word_len)
+            .saturating_sub(length(self.word) + 2 * tolerance)
+            .div_ceil(2);
+        for (bucket, child) in self.children() {
+            if bucket >= lowest {
+                child.walk_partial(word, word_len, tolerance, f)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// calls `f` with every word starting with `prefix`, in traversal
+    /// order until it breaks. the tree is ordered by distance and not by
+    /// prefix, so like [`Node::for_each_partial_match`] only the low
+    /// buckets of a node are ruled out
+    pub fn for_each_completion(
+        &'static self,
+        prefix: &str,
+        mut f: impl FnMut(&'static str) -> ControlFlow<()>,
+    ) {
+        let _ = self.walk_completions(prefix, length(prefix), &mut f);
+    }
+
+    // a word `w` starting with the prefix `p` is `len(w) - len(p)`
+    // insertions from it. with `d(p, n) - k <= d(p, w)` and
+    // `len(w) <= len(n) + k` a completion in bucket `k` of `n` needs
+    // `2k >= d(p, n) + len(p) - len(n)`
+    fn walk_completions(
+        &'static self,
+        prefix: &str,
+        prefix_len: usize,
+        f: &mut dyn FnMut(&'static str) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if self.word.starts_with(prefix) && !self.suppressed {
+            f(self.word)?;
+        }
+        let lowest = (distance(prefix, self.word) + prefix_len)
+            .saturating_sub(length(self.word))
+            .div_ceil(2);
+        for (bucket, child) in self.children() {
+            if bucket >= lowest {
+                child.walk_completions(prefix, prefix_len, f)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// every word matching `word` partially within `tolerance`, sorted by
+    /// partial distance like [`Node::corrections`]. see
+    /// [`Node::for_each_partial_match`]
+    #[cfg(feature = "alloc")]
+    pub fn partial_corrections(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.for_each_partial_match(word, tolerance, |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    /// every word within `tolerance` of `query`, sorted by distance like
+    /// [`Node::corrections`]. see [`Node::for_each_ambiguous_match`]
+    #[cfg(feature = "alloc")]
+    pub fn ambiguous_matches(
+        &'static self,
+        query: AmbiguousQuery,
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        let mut matches = Vec::new();
+        self.for_each_ambiguous_match(query, tolerance, |s| {
+            matches.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut matches);
+        matches
+    }
+
+    /// every word matching `pattern` within `tolerance` with [`WILDCARD`]
+    /// standing for any one character, sorted by distance like
+    /// [`Node::corrections`]. see [`Node::for_each_wildcard_match`]
+    #[cfg(feature = "alloc")]
+    pub fn wildcard_matches(
+        &'static self,
+        pattern: &str,
+        tolerance: u8,
+    ) -> Vec<Suggestion<'static>> {
+        let mut matches = Vec::new();
+        self.for_each_wildcard_match(pattern, WILDCARD, tolerance, |s| {
+            matches.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut matches);
+        matches
+    }
+
+    // the node holding `word`, following the bucket matching the distance
+    // at every level
+    pub(crate) fn exact(
+        &'static self,
+        word: &str,
+        stats: &mut SearchStats,
+    ) -> Option<&'static Node> {
+        // the writer never stores an empty word
+        if word.is_empty() {
+            return None;
+        }
+        let mut node = self;
+        loop {
+            stats.nodes_visited += 1;
+            stats.distance_calls += 1;
+            match distance(node.word(), word) {
+                0 => return Some(node),
+                d => node = node.child_at(d)?,
+            }
+        }
+    }
+
+    /// every word the search had to look at for `word`, whether or not it
+    /// is within `tolerance`. mostly useful to see how much a query costs,
+    /// [`Node::corrections`] is the verified set
+    #[cfg(feature = "alloc")]
+    pub fn candidates(&'static self, word: &str, tolerance: u8) -> Vec<&'static str> {
+        let mut candidates = Vec::new();
+        let mut stats = SearchStats::default();
+        let _ = self.walk_nodes(word, tolerance as usize, &mut stats, &mut |node, _| {
+            candidates.push(node.word());
+            ControlFlow::Continue(())
+        });
+        candidates
+    }
+
+    /// [`Node::candidates`] that hands back [`AllocError`] instead of
+    /// aborting, reserving room for `capacity` words up front
+    #[cfg(feature = "alloc")]
+    pub fn try_candidates(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        capacity: usize,
+    ) -> Result<Vec<&'static str>, AllocError> {
+        let mut candidates = Vec::new();
+        candidates.try_reserve_exact(capacity)?;
+        let mut result = Ok(());
+        let mut stats = SearchStats::default();
+        let _ = self.walk_nodes(word, tolerance as usize, &mut stats, &mut |node, _| {
+            result = candidates.try_reserve(1);
+            if result.is_err() {
+                return ControlFlow::Break(());
+            }
+            candidates.push(node.word());
+            ControlFlow::Continue(())
+        });
+        result?;
+        Ok(candidates)
+    }
+
+    #[cfg(feature = "alloc")]
+    #[deprecated(note = "use `candidates`, or `corrections` for words within tolerance")]
+    pub fn canidates(&'static self, word: &str, tolerance: u8) -> Vec<&'static str> {
+        self.candidates(word, tolerance)
+    }
+
+    /// the searches of the tree with the words of [`Node::suppress`] handed
+    /// out like the rest, for tools checking the dictionary itself rather
+    /// than suggestions shown to someone
+    pub const fn include_suppressed(&'static self) -> IncludeSuppressed {
+        IncludeSuppressed { root: self }
+    }
+
+    /// exact membership, following the single bucket matching the
+    /// distance at every level. true for suppressed words as well
+    pub fn contains(&'static self, word: &str) -> bool {
+        self.exact(word, &mut SearchStats::default()).is_some()
+    }
+
+    /// `len` bytes of `words` from `start`, how a tree generated with
+    /// `BkTreeBuilder::dfs_order` gets each word out of its one byte string
+    #[doc(hidden)]
+    pub const fn interned(words: &'static [u8], start: usize, len: usize) -> &'static str {
+        let (word, _) = words.split_at(start).1.split_at(len);
+        match core::str::from_utf8(word) {
+            Ok(word) => word,
+            Err(_) => panic!("interned word isn't utf-8"),
+        }
+    }
+
+    /// the first `K` bytes of `words`, how a tier of a tree generated with
+    /// `BkTreeBuilder::tier` gets its words out of ones shared with others
+    #[doc(hidden)]
+    pub const fn words_prefix<const N: usize, const K: usize>(words: &[u8; N]) -> [u8; K] {
+        assert!(K <= N, "tier has more words than the shared ones");
+        let mut prefix = [0; K];
+        let mut i = 0;
+        while i < K {
+            prefix[i] = words[i];
+            i += 1;
+        }
+        prefix
+    }
+
+    /// [`Node::contains`] for const items, so a build can check that words
+    /// it relies on made it into the tree:
+    ///
+    /// ```
+    /// # use embedded_bktree::Node;
+    /// static HE: Node = Node::new("he", 1, 0, &[], &[]);
+    /// static TREE: Node = Node::new("the", 0, 0, &[1], &[&HE]);
+    /// const _: () = assert!(TREE.contains_const("he"));
+    /// ```
+    ///
+    /// a word that isn't there fails the build
+    ///
+    /// ```compile_fail
+    /// # use embedded_bktree::Node;
+    /// static TREE: Node = Node::new("the", 0, 0, &[], &[]);
+    /// const _: () = assert!(TREE.contains_const("she"));
+    /// ```
+    ///
+    /// descends with [`levenshtein_const`](crate::levenshtein_const), so the
+    /// same word length limit applies. with the `grapheme` feature the
+    /// buckets aren't char distances and it looks at every word instead
+    pub const fn contains_const(&self, word: &str) -> bool {
+        if word.is_empty() {
+            return false;
+        }
+        if Self::GRAPHEMES {
+            return self.scan_const(word);
+        }
+        let mut node = self;
+        loop {
+            match levenshtein_const(node.word(), word) {
+                0 => return true,
+                d => match node.child_at(d) {
+                    Some(child) => node = child,
+                    None => return false,
+                },
+            }
+        }
+    }
+
+    const fn scan_const(&self, word: &str) -> bool {
+        if str_eq(self.word, word) {
+            return true;
+        }
+        let mut i = 0;
+        while i < CHILDREN_LENGTH {
+            if let Some(child) = self.child_at(i) {
+                if child.scan_const(word) {
+                    return true;
+                }
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// the closest word within `tolerance`, on equal distance the one
+    /// found first in traversal order
+    pub fn best_match(&'static self, word: &str, tolerance: u8) -> Option<Suggestion<'static>> {
+        let mut best: Option<Suggestion<'static>> = None;
+        let _ = self.walk(
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut |s| {
+                if best.map_or(true, |b| s.distance < b.distance) {
+                    best = Some(s);
+                }
+                match s.distance {
+                    0 => ControlFlow::Break(()),
+                    _ => ControlFlow::Continue(()),
+                }
+            },
+        );
+        best
+    }
+
+    /// [`Node::for_each_correction`] with each word's `index` and distance
+    /// instead of a [`Suggestion`], a `(u16, u8)` of 4 bytes to queue
+    /// rather than an 8 byte str. the generated `WORDS` turns an index back
+    /// into the word, see `BkTreeBuilder::word_table`, which is why it's a
+    /// u16 like the table's. a tree of more words than that has no table
+    /// and its indexes wrap
+    pub fn for_each_indexed(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        mut f: impl FnMut(u16, u8) -> ControlFlow<()>,
+    ) {
+        let _ = self.walk_matches(
+            &Generated::default(),
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut |node, distance| f(node.index() as u16, distance),
+        );
+    }
+
+    /// [`Node::corrections`] as `(index, distance)`, see
+    /// [`Node::for_each_indexed`]
+    #[cfg(feature = "alloc")]
+    pub fn indexed_corrections(&'static self, word: &str, tolerance: u8) -> Vec<(u16, u8)> {
+        let mut corrections = Vec::new();
+        self.for_each_indexed(word, tolerance, |index, distance| {
+            corrections.push((index, distance));
+            ControlFlow::Continue(())
+        });
+        sort_results(&mut corrections, |&(_, distance)| distance);
+        corrections
+    }
+
+    /// [`Node::best_match`] as `(index, distance)`
+    pub fn best_indexed(&'static self, word: &str, tolerance: u8) -> Option<(u16, u8)> {
+        let mut best: Option<(u16, u8)> = None;
+        self.for_each_indexed(word, tolerance, |index, distance| {
+            if best.map_or(true, |(_, b)| distance < b) {
+                best = Some((index, distance));
+            }
+            match distance {
+                0 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        });
+        best
+    }
+}
+
+// what a search needs from a tree, so the generated tree and
+// `RuntimeBkTree` share one traversal
+pub(crate) trait Tree {
+    type Node: Copy;
+    fn word(&self, node: Self::Node) -> &str;
+    // the children of `node` with a bucket in `range`, in ascending order
+    fn children(
+        &self,
+        node: Self::Node,
+        range: RangeInclusive<usize>,
+        visit: &mut dyn FnMut(Self::Node) -> ControlFlow<()>,
+    ) -> ControlFlow<()>;
+    // when `node` has no children its char mask, 0 without one, see
+    // `walk_tree`
+    fn leaf(&self, _node: Self::Node) -> Option<u32> {
+        None
+    }
+    // whether `node` is searched through without being visited itself
+    fn suppressed(&self, _node: Self::Node) -> bool {
+        false
+    }
+}
+
+// the words of `Node::suppress` are left out unless `include_suppressed`
+#[derive(Default)]
+pub(crate) struct Generated {
+    pub include_suppressed: bool,
+}
+
+impl Tree for Generated {
+    type Node = &'static Node;
+
+    fn word(&self, node: &'static Node) -> &str {
+        node.word()
+    }
+
+    fn children(
+        &self,
+        node: &'static Node,
+        range: RangeInclusive<usize>,
+        visit: &mut dyn FnMut(&'static Node) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        for (_, child) in node
+            .children()
+            .skip_while(|(bucket, _)| bucket < range.start())
+            .take_while(|(bucket, _)| bucket <= range.end())
+        {
+            visit(child)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn leaf(&self, node: &'static Node) -> Option<u32> {
+        node.is_leaf().then_some(node.mask())
+    }
+
+    fn suppressed(&self, node: &'static Node) -> bool {
+        node.suppressed && !self.include_suppressed
+    }
+}
+
+// depth first from `node` over every node in a bucket that can still hold
+// a match, along with its distance. `measure` may undercount the true
+// distance between words by at most `slack`, the upper bound of every
+// bucket range widens by that much.
+//
+// a leaf has no buckets to pick, so it only needs to be known to be past
+// `tolerance`, anything past it is dropped by every visitor. `leaf` checks
+// that without measuring, see `LeafCheck`
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    not(feature = "log"),
+    allow(unused_variables, clippy::only_used_in_recursion)
+)]
+#[cfg_attr(feature = "small-code", inline(never))]
+pub(crate) fn walk_tree<T: Tree>(
+    tree: &T,
+    node: T::Node,
+    query: &dyn fmt::Debug,
+    measure: &dyn Fn(&str) -> usize,
+    leaf: &LeafCheck,
+    tolerance: usize,
+    slack: usize,
+    stats: &mut SearchStats,
+    visit: &mut dyn FnMut(T::Node, usize) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    stats.nodes_visited += 1;
+    let word = tree.word(node);
+    let suppressed = tree.suppressed(node);
+    if let Some(mask) = tree.leaf(node) {
+        // nothing below it to search for
+        if suppressed {
+            return ControlFlow::Continue(());
+        }
+        if let Some(query_mask) = leaf.mask.filter(|_| mask != 0) {
+            let bound = mask_bound(query_mask, mask);
+            if bound > tolerance {
+                trace!("{query:?}: leaf {word:?} ruled out by its mask, at least {bound}");
+                return visit(node, bound);
+            }
+        }
+        #[cfg(not(feature = "small-code"))]
+        if let Some(automaton) = &leaf.automaton {
+            stats.automaton_runs += 1;
+            let distance = automaton.distance(word);
+            trace!("{query:?}: leaf {word:?} through the automaton, {distance:?}");
+            return visit(node, distance.unwrap_or(tolerance + 1));
+        }
+    }
+    stats.distance_calls += 1;
+    let distance = measure(word);
+    trace!("{query:?}: visited {word:?} at {distance}");
+    if !suppressed {
+        visit(node, distance)?;
+    }
+    let range = buckets(distance, tolerance);
+    let range = *range.start()..=range.end().saturating_add(slack);
+    trace!("{query:?}: buckets {range:?} of {word:?}");
+    tree.children(node, range, &mut |child| {
+        walk_tree(
+            tree, child, query, measure, leaf, tolerance, slack, stats, visit,
+        )
+    })
+}
+
+// the query `walk_tree` logs. without `log` it's never printed, and the
+// query's own `Debug` would be kept in the binary for nothing
+#[cfg_attr(not(feature = "log"), allow(unused_variables))]
+pub(crate) fn logged<T: fmt::Debug>(query: &T) -> &dyn fmt::Debug {
+    #[cfg(feature = "log")]
+    return query;
+    #[cfg(not(feature = "log"))]
+    return &Unlogged;
+}
+
+// stands in for the query, `()` would still bring in the formatter
+#[cfg(not(feature = "log"))]
+struct Unlogged;
+
+#[cfg(not(feature = "log"))]
+impl fmt::Debug for Unlogged {
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        Ok(())
+    }
+}
+
+// what a search can rule leaves out with before measuring them. `mask` is
+// the query's char mask, a leaf `mask_bound` puts past tolerance is visited
+// with that bound. `automaton` is the query's built for the same tolerance
+// and stands in for the measure, it has to agree with it
+#[derive(Default)]
+pub(crate) struct LeafCheck {
+    pub mask: Option<u32>,
+    #[cfg(not(feature = "small-code"))]
+    pub automaton: Option<Automaton>,
+}
+
+impl LeafCheck {
+    // both checks for a `distance` search, neither holds for graphemes.
+    // `small-code` leaves the automaton out, it's more code than the
+    // distance it saves
+    #[cfg_attr(feature = "small-code", allow(unused_variables))]
+    pub(crate) fn new(word: &str, tolerance: usize) -> Self {
+        match Node::GRAPHEMES {
+            true => Self::default(),
+            false => Self {
+                mask: Some(char_mask(word)),
+                #[cfg(not(feature = "small-code"))]
+                automaton: Automaton::new(word, tolerance),
+            },
+        }
+    }
+}
+
+// the order every list of suggestions is handed out in, by distance and
+// ties in traversal order
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "small-code", inline(never))]
+pub(crate) fn sort_by_distance(suggestions: &mut [Suggestion]) {
+    sort_results(suggestions, |s| s.distance);
+}
+
+// a stable sort of results by `key`, every one goes through here so
+// `small-code` does it with an insertion sort, a few instructions in place
+// of the std sort
+#[cfg(feature = "alloc")]
+pub(crate) fn sort_results<T, K: Ord>(results: &mut [T], key: impl Fn(&T) -> K) {
+    #[cfg(not(feature = "small-code"))]
+    results.sort_by_key(key);
+    #[cfg(feature = "small-code")]
+    for i in 1..results.len() {
+        let mut j = i;
+        while j > 0 && key(&results[j - 1]) > key(&results[j]) {
+            results.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+// the buckets of a node at `distance` from the query that can hold
+// words within `tolerance`, by the triangle inequality
+fn buckets(distance: usize, tolerance: usize) -> RangeInclusive<usize> {
+    distance.saturating_sub(tolerance)..=distance.saturating_add(tolerance)
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Node {
+    // only the populated bucket indexes, logging the whole subtree
+    // would flood the link
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Node {{ word: {=str}, buckets: [", self.word);
+        let mut buckets = self.children().map(|(bucket, _)| bucket);
+        if let Some(first) = buckets.next() {
+            defmt::write!(f, "{=usize}", first);
+        }
+        for i in buckets {
+            defmt::write!(f, ", {=usize}", i);
+        }
+        defmt::write!(f, "] }}");
+    }
+}
+
+/// counters from [`Node::search_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes_visited: usize,
+    pub distance_calls: usize,
+    /// leaves checked with the query's levenshtein automaton instead
+    pub automaton_runs: usize,
+}
+
+/// nodes a cancellable search visits between asking whether to stop, a
+/// few microseconds of work on a small mcu and one call on the rest
+pub const CANCEL_INTERVAL: usize = 32;
+
+/// a search stopped by its `cancel`, with what it found before
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled<T> {
+    pub partial: T,
+}
+
+impl<T> fmt::Display for Cancelled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the search was cancelled")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for Cancelled<T> {}
+
+/// see [`Node::memory_usage`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub nodes: usize,
+    pub node_bytes: usize,
+    pub string_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub const fn total(&self) -> usize {
+        self.node_bytes + self.string_bytes
+    }
+}
+
+/// see [`Node::length_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthStats {
+    /// the shortest and longest word, 255 for anything longer
+    pub min: u8,
+    pub max: u8,
+    /// how many words there are of each length, the last slot counts 31
+    /// and up
+    pub histogram: [u32; 32],
+}
+
+impl LengthStats {
+    /// no words, `min` above `max`
+    pub const EMPTY: Self = Self {
+        min: u8::MAX,
+        max: 0,
+        histogram: [0; 32],
+    };
+
+    #[cfg(feature = "alloc")]
+    pub(crate) fn add(&mut self, len: usize) {
+        let short = u8::try_from(len).unwrap_or(u8::MAX);
+        self.min = self.min.min(short);
+        self.max = self.max.max(short);
+        self.histogram[len.min(31)] += 1;
+    }
+
+    /// whether any word is as close in length to a query of `query_len`
+    /// as `tolerance`, the length difference being the fewest edits
+    /// between them. when it isn't there's no point searching
+    pub fn can_match(&self, query_len: usize, tolerance: u8) -> bool {
+        let tolerance = tolerance as usize;
+        let lengths = query_len.saturating_sub(tolerance)..=query_len + tolerance;
+        self.histogram.iter().enumerate().any(|(len, &words)| {
+            // the last slot is every length from 31 on
+            let reached = match len {
+                31 => *lengths.end() >= 31,
+                _ => lengths.contains(&len),
+            };
+            words > 0 && reached
+        })
+    }
+}
+
+/// a tolerance for a query of `query_len`: 0 up to 2 chars, where one
+/// edit already reaches most short words, 1 up to 5 and 2 past that,
+/// about a typo every four or five chars. it's 0 as well when no word of
+/// the dictionary is within that of the query's length, see
+/// [`LengthStats::can_match`] to skip the search then
+pub fn suggested_tolerance(query_len: usize, stats: &LengthStats) -> u8 {
+    let tolerance = length_tolerance(query_len);
+    match stats.can_match(query_len, tolerance) {
+        true => tolerance,
+        false => 0,
+    }
+}
+
+// `suggested_tolerance` without the dictionary
+pub(crate) fn length_tolerance(query_len: usize) -> u8 {
+    match query_len {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// a generated tree along with the constants emitted next to it, see
+/// `BkTreeBuilder::static_wrapper`
+#[derive(Debug, Clone, Copy)]
+pub struct StaticBkTree {
+    root: &'static Node,
+    word_count: usize,
+    checksum: u32,
+}
+
+impl StaticBkTree {
+    pub const fn new(root: &'static Node, word_count: usize, checksum: u32) -> Self {
+        Self {
+            root,
+            word_count,
+            checksum,
+        }
+    }
+
+    pub const fn root(&self) -> &'static Node {
+        self.root
+    }
+
+    pub const fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    pub const fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.root.contains(word)
+    }
+
+    /// see [`Node::contains_const`]
+    pub const fn contains_const(&self, word: &str) -> bool {
+        self.root.contains_const(word)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        self.root.corrections(word, tolerance)
+    }
+
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<Suggestion<'static>> {
+        self.root.best_match(word, tolerance)
+    }
+
+    /// see [`Node::corrections_bytes`]
+    #[cfg(feature = "alloc")]
+    pub fn corrections_bytes(&self, query: &[u8], tolerance: u8) -> Vec<Suggestion<'static>> {
+        self.root.corrections_bytes(query, tolerance)
+    }
+
+    /// see [`Node::for_each_correction_bytes`]
+    pub fn for_each_correction_bytes(
+        &self,
+        query: &[u8],
+        tolerance: u8,
+        f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        self.root.for_each_correction_bytes(query, tolerance, f)
+    }
+
+    /// see [`Node::try_corrections`]
+    #[cfg(feature = "alloc")]
+    pub fn try_corrections(
+        &self,
+        word: &str,
+        tolerance: u8,
+        capacity: usize,
+    ) -> Result<Vec<Suggestion<'static>>, AllocError> {
+        self.root.try_corrections(word, tolerance, capacity)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn iter(&self) -> NodeIterator {
+        self.root.iter()
+    }
+
+    /// see [`Node::try_iter`]
+    #[cfg(feature = "alloc")]
+    pub fn try_iter(&self, depth: usize) -> Result<TryNodeIterator, AllocError> {
+        self.root.try_iter(depth)
+    }
+
+    /// see [`Node::nth`]
+    pub fn nth(&self, i: usize) -> Option<&'static Node> {
+        self.root.nth(i)
+    }
+
+    /// see [`Node::range`]
+    pub fn range(&self, range: Range<usize>) -> impl Iterator<Item = &'static Node> {
+        self.root.range(range)
+    }
+
+    /// see [`Node::include_suppressed`]
+    pub const fn include_suppressed(&self) -> IncludeSuppressed {
+        self.root.include_suppressed()
+    }
+}
+
+/// see [`Node::include_suppressed`]
+#[derive(Debug, Clone, Copy)]
+pub struct IncludeSuppressed {
+    root: &'static Node,
+}
+
+impl IncludeSuppressed {
+    /// [`Node::for_each_correction`] with the suppressed words
+    pub fn for_each_correction(
+        &self,
+        word: &str,
+        tolerance: u8,
+        mut f: impl FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let tree = &Generated {
+            include_suppressed: true,
+        };
+        let _ = self.root.walk_matches(
+            tree,
+            word,
+            tolerance as usize,
+            &mut SearchStats::default(),
+            &mut |node, distance| f(Suggestion::new(word, node.word(), distance)),
+        );
+    }
+
+    /// [`Node::corrections`] with the suppressed words
+    #[cfg(feature = "alloc")]
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.for_each_correction(word, tolerance, |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+}
+
+/// the generated `WORDS`, every word of a tree at its `Node::index`, see
+/// `BkTreeBuilder::word_table`
+#[derive(Debug, Clone, Copy)]
+pub struct WordTable {
+    words: &'static [&'static str],
+}
+
+impl WordTable {
+    /// `words` in index order, as the writer emits them
+    pub const fn new(words: &'static [&'static str]) -> Self {
+        Self { words }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// the word of the node with `index`, panics past the last one. any
+    /// index a search of the same tree gave is there
+    pub const fn word_at(&self, index: u16) -> &'static str {
+        self.words[index as usize]
+    }
+}
+
+/// the search surface of a dictionary, so code taking suggestions can be
+/// tested against a fake instead of a generated tree
+pub trait Corrections {
+    /// calls `f` with every word within `tolerance` of `word` until it breaks
+    fn for_each_correction(
+        &self,
+        word: &str,
+        tolerance: u8,
+        f: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    );
+
+    fn contains(&self, word: &str) -> bool;
+
+    /// every word within `tolerance` of `word`, sorted by distance
+    #[cfg(feature = "alloc")]
+    fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.for_each_correction(word, tolerance, &mut |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+}
+
+impl Corrections for &'static Node {
+    fn for_each_correction(
+        &self,
+        word: &str,
+        tolerance: u8,
+        f: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        let _ = self.walk(word, tolerance as usize, &mut SearchStats::default(), f);
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        Node::contains(self, word)
+    }
+}
+
+impl Corrections for IncludeSuppressed {
+    fn for_each_correction(
+        &self,
+        word: &str,
+        tolerance: u8,
+        f: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        IncludeSuppressed::for_each_correction(self, word, tolerance, f)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.root.contains(word)
+    }
+}
+
+impl Corrections for StaticBkTree {
+    fn for_each_correction(
+        &self,
+        word: &str,
+        tolerance: u8,
+        f: &mut dyn FnMut(Suggestion<'static>) -> ControlFlow<()>,
+    ) {
+        self.root.for_each_correction(word, tolerance, f)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.root.contains(word)
+    }
+}
+
+/// a query that isn't sure of its characters, the candidates for each
+/// position, like OCR output or keypad input. `[&['c'], &['a', 'o'],
+/// &['r']]` searches for "car" and "cor" at once.
+///
+/// a candidate is a single character, a glyph read as "cl" for "d" has to
+/// be searched separately
+#[derive(Debug, Clone, Copy)]
+pub struct AmbiguousQuery<'a>(pub &'a [&'a [char]]);
+
+/// the wildcard [`Node::wildcard_matches`] uses
+pub const WILDCARD: char = '?';
+
+/// deepest [`Node::display`] goes, it keeps one node per level on the stack
+pub const MAX_DISPLAY_DEPTH: usize = 32;
+
+/// see [`Node::display`]
+#[derive(Debug, Clone, Copy)]
+pub struct TreeDisplay {
+    root: &'static Node,
+    max_depth: usize,
+}
+
+impl fmt::Display for TreeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.root.word())?;
+        if self.max_depth == 0 {
+            if !self.root.is_leaf() {
+                writeln!(f, "  ...")?;
+            }
+            return Ok(());
+        }
+        // each level's node and the next bucket of it to print
+        let mut stack = [(self.root, 0); MAX_DISPLAY_DEPTH];
+        let mut depth = 0;
+        loop {
+            let (node, next) = &mut stack[depth];
+            let child = node.children().find(|(bucket, _)| bucket >= next);
+            let Some((bucket, child)) = child else {
+                match depth {
+                    0 => return Ok(()),
+                    _ => depth -= 1,
+                }
+                continue;
+            };
+            *next = bucket + 1;
+            let indent = 2 * (depth + 1);
+            writeln!(f, "{:indent$}{bucket} {}", "", child.word())?;
+            if depth + 1 < self.max_depth {
+                depth += 1;
+                stack[depth] = (child, 0);
+            } else if !child.is_leaf() {
+                writeln!(f, "{:indent$}...", "", indent = indent + 2)?;
+            }
+        }
+    }
+}
+
+/// a word found by a search along with its distance from the query,
+/// ordered by word first like [`Node`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Suggestion<'a> {
+    pub word: &'a str,
+    pub distance: u8,
+    /// how sure the match is, 255 for an exact one down to 0 when every
+    /// character had to change. see [`Suggestion::new`]
+    pub confidence: u8,
+}
+
+impl<'a> Suggestion<'a> {
+    /// a suggestion of `word` for `query`, `distance` apart.
+    ///
+    /// confidence is `255 * (longest - distance) / longest` in integer math,
+    /// rounded down, where `longest` is the length of the longer of the two
+    /// in the same units as [`distance`](crate::distance). two empty
+    /// strings are 255 and a distance past the longest is 0
+    pub fn new(query: &str, word: &'a str, distance: u8) -> Self {
+        Self::with_query_len(length(query), word, distance)
+    }
+
+    pub(crate) fn with_query_len(query_len: usize, word: &'a str, distance: u8) -> Self {
+        let longest = query_len.max(length(word));
+        // `255 * left / longest` as `255 - ceil(255 * edits / longest)`,
+        // which stays within a 16 bit usize and needs no u64 division
+        let edits = (distance as usize).min(longest);
+        let confidence = match longest {
+            0 => 255,
+            _ => 255 - (255 * edits).div_ceil(longest),
+        };
+        Suggestion {
+            word,
+            distance,
+            confidence: confidence as u8,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Suggestion<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Suggestion {{ word: {=str}, distance: {=u8}, confidence: {=u8} }}",
+            self.word,
+            self.distance,
+            self.confidence
+        );
+    }
+}
+
+/// fixed capacity collection keeping the `K` smallest distances seen,
+/// sorted by distance. a new entry only evicts the current worst when
+/// it is strictly closer, so ties go to whichever was inserted first
+#[derive(Debug, Clone)]
+pub struct BestK<'a, const K: usize> {
+    entries: [Option<(&'a str, u8)>; K],
+    len: usize,
+}
+
+impl<'a, const K: usize> BestK<'a, K> {
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; K],
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, word: &'a str, distance: u8) {
+        if K == 0 {
+            return;
+        }
+        if self.len == K {
+            match self.entries[K - 1] {
+                Some((_, worst)) if distance < worst => self.len -= 1,
+                _ => return,
+            }
+        }
+        // after every entry with an equal or smaller distance
+        let at = self.entries[..self.len]
+            .iter()
+            .position(|e| matches!(e, Some((_, d)) if *d > distance))
+            .unwrap_or(self.len);
+        self.entries[at..=self.len].rotate_right(1);
+        self.entries[at] = Some((word, distance));
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn into_parts(self) -> ([Option<(&'a str, u8)>; K], usize) {
+        (self.entries, self.len)
+    }
+}
+
+impl<const K: usize> Default for BestK<'_, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub struct NodeIterator {
+    stack: Vec<(u8, &'static Node)>,
+    first: bool,
+}
+#[cfg(feature = "alloc")]
+impl NodeIterator {
+    fn new(node: &'static Node) -> Self {
+        let stack = vec![(0, node)];
+        Self { stack, first: true }
+    }
+}
+#[cfg(feature = "alloc")]
+impl NodeIterator {
+    // the next node, with `reserve` the stack grows through try_reserve
+    // and a failed one leaves it as it was
+    fn step(&mut self, reserve: bool) -> Result<Option<&'static Node>, AllocError> {
+        if self.first {
+            self.first = false;
+            return Ok(Some(self.stack.first().unwrap().1));
+        }
+        loop {
+            let (done, node) = *self.stack.last().unwrap();
+            if let Some((_, child)) = node.children().rev().nth(done as usize) {
+                if reserve {
+                    self.stack.try_reserve(1)?;
+                }
+                self.stack.push((0, child));
+                return Ok(Some(child));
+            }
+
+            // made it through children and are back up to root
+            self.stack.pop();
+
+            match self.stack.pop() {
+                Some(last) => self.stack.push((last.0 + 1, last.1)),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl Iterator for NodeIterator {
+    type Item = &'static Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.step(false) {
+            Ok(node) => node,
+            Err(AllocError) => unreachable!(),
+        }
+    }
+}
+
+/// the iterator of [`Node::try_iter`]
+#[cfg(feature = "alloc")]
+pub struct TryNodeIterator {
+    nodes: NodeIterator,
+    failed: bool,
+}
+#[cfg(feature = "alloc")]
+impl Iterator for TryNodeIterator {
+    type Item = Result<&'static Node, AllocError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self.nodes.step(true);
+        self.failed = next.is_err();
+        next.transpose()
+    }
+}
+
+/// the allocator couldn't give a `try_` search or iterator the memory it
+/// asked for. whatever was found so far is dropped
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of memory")
+    }
+}
+
+#[cfg(feature = "alloc")]
+std_error!(AllocError);
+
+#[cfg(feature = "alloc")]
+impl From<TryReserveError> for AllocError {
+    fn from(_: TryReserveError) -> Self {
+        AllocError
+    }
+}
+
+// `cargo test --no-default-features --features read`
+#[cfg(all(test, not(feature = "alloc"), not(feature = "grapheme")))]
+mod test {
+    use super::Node;
+
+    include!("../tree.test");
+
+    #[test]
+    fn search_without_alloc() {
+        assert!(TREE.contains("care"));
+        assert!(!TREE.contains("cares"));
+        assert_eq!(TREE.best_match("cars", 1).unwrap().word, "car");
+        assert!(TREE.exists_within("cars", 1));
+        assert_eq!(TREE.count_within("cars", 1), 3);
+        let (best, len) = TREE.best_k_into::<2>("cars", 1);
+        assert_eq!(len, 2);
+        assert_eq!(best[0], Some(("car", 1)));
+    }
+}
@@ -0,0 +1,542 @@
+// a bk tree built and grown at runtime, for words the user adds on top of
+// the generated dictionary
+
+extern crate alloc;
+
+#[cfg(feature = "write")]
+use super::write;
+use super::{
+    distance::distance,
+    read::{logged, sort_by_distance, walk_tree, LeafCheck, SearchStats, Tree},
+    Node, Suggestion,
+};
+use alloc::{string::String, vec, vec::Vec};
+use core::ops::{ControlFlow, RangeInclusive};
+
+#[derive(Debug, Clone)]
+struct RuntimeNode {
+    word: String,
+    // removed, only still here to route searches past it
+    deleted: bool,
+    // (bucket, node) sorted by bucket, any distance gets a bucket
+    children: Vec<(usize, usize)>,
+}
+
+impl RuntimeNode {
+    fn new(word: String) -> Self {
+        Self {
+            word,
+            deleted: false,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// a mutable bk tree on the heap. nodes live in one vec and point at each
+/// other by index, the first word inserted is the root.
+///
+/// unlike the generated tree there is no limit on how far apart words can
+/// be, searches work the same way and return the same results.
+///
+/// removed words stay in the tree as tombstones that searches walk through
+/// but never return, [`RuntimeBkTree::rebuild`] drops them
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeBkTree {
+    nodes: Vec<RuntimeNode>,
+    tombstones: usize,
+}
+
+impl RuntimeBkTree {
+    pub const fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            tombstones: 0,
+        }
+    }
+
+    /// an owned copy of a generated tree with the same shape, so it
+    /// searches the same and can be edited from there. the root's word is
+    /// the first in insertion order
+    pub fn from_static(root: &'static Node) -> Self {
+        let mut tree = Self::new();
+        tree.nodes.push(RuntimeNode::new(root.word().into()));
+        let mut stack = vec![(root, 0)];
+        while let Some((node, index)) = stack.pop() {
+            for (bucket, child) in node.children() {
+                let child_index = tree.nodes.len();
+                tree.nodes.push(RuntimeNode::new(child.word().into()));
+                tree.nodes[index].children.push((bucket, child_index));
+                stack.push((child, child_index));
+            }
+        }
+        tree
+    }
+
+    /// adds `word`, false if it was already there. empty words are never
+    /// stored, like the writer they'd match every short query
+    pub fn insert(&mut self, word: impl Into<String>) -> bool {
+        let word = word.into();
+        if word.is_empty() {
+            return false;
+        }
+        let new = self.nodes.len();
+        let mut node = 0;
+        while let Some(current) = self.nodes.get(node) {
+            let bucket = distance(&current.word, &word);
+            if bucket == 0 {
+                // a removed word just comes back
+                if current.deleted {
+                    self.nodes[node].deleted = false;
+                    self.tombstones -= 1;
+                    return true;
+                }
+                return false;
+            }
+            match current.children.binary_search_by_key(&bucket, |(b, _)| *b) {
+                Ok(i) => node = current.children[i].1,
+                Err(i) => {
+                    self.nodes[node].children.insert(i, (bucket, new));
+                    break;
+                }
+            }
+        }
+        self.nodes.push(RuntimeNode::new(word));
+        true
+    }
+
+    /// removes `word`, false if it wasn't there. the node stays behind as a
+    /// tombstone, the root included
+    pub fn remove(&mut self, word: &str) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut found = None;
+        let _ = walk_tree(
+            self,
+            0,
+            logged(&word),
+            &|w| distance(w, word),
+            &LeafCheck::default(),
+            0,
+            0,
+            &mut SearchStats::default(),
+            &mut |node, distance| match distance {
+                0 => {
+                    found = Some(node);
+                    ControlFlow::Break(())
+                }
+                _ => ControlFlow::Continue(()),
+            },
+        );
+        match found {
+            Some(node) if !self.nodes[node].deleted => {
+                self.nodes[node].deleted = true;
+                self.tombstones += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// builds the tree again from the remaining words in a shape that's
+    /// usually shallower than arrival order gives, so searches visit fewer
+    /// nodes. drops every tombstone and frees the spare capacity, results
+    /// are the same words as before though equally distant ones may come
+    /// in a different order.
+    ///
+    /// each subtree is rooted at whichever of a few of its words splits the
+    /// rest most evenly across buckets, see [`RuntimeBkTree::stats`] for
+    /// when it's worth running
+    pub fn optimize(&mut self) {
+        let nodes = core::mem::take(&mut self.nodes);
+        self.tombstones = 0;
+        let words: Vec<String> = nodes
+            .into_iter()
+            .filter(|n| !n.deleted)
+            .map(|n| n.word)
+            .collect();
+        // (parent and bucket, words below it)
+        let mut pending = vec![(None::<(usize, usize)>, words)];
+        while let Some((parent, mut words)) = pending.pop() {
+            if words.is_empty() {
+                continue;
+            }
+            let pivot = words.swap_remove(best_pivot(&words));
+            let index = self.nodes.len();
+            self.nodes.push(RuntimeNode::new(pivot));
+            if let Some((parent, bucket)) = parent {
+                let children = &mut self.nodes[parent].children;
+                let at = children.partition_point(|(b, _)| *b < bucket);
+                children.insert(at, (bucket, index));
+            }
+            let mut buckets: Vec<(usize, Vec<String>)> = Vec::new();
+            for word in words {
+                let d = distance(&self.nodes[index].word, &word);
+                match buckets.binary_search_by_key(&d, |(b, _)| *b) {
+                    Ok(i) => buckets[i].1.push(word),
+                    Err(i) => buckets.insert(i, (d, vec![word])),
+                }
+            }
+            pending.extend(
+                buckets
+                    .into_iter()
+                    .map(|(d, words)| (Some((index, d)), words)),
+            );
+        }
+        self.nodes.shrink_to_fit();
+        self.nodes
+            .iter_mut()
+            .for_each(|n| n.children.shrink_to_fit());
+    }
+
+    /// the shape of the tree right now
+    pub fn stats(&self) -> RuntimeStats {
+        let mut stats = RuntimeStats {
+            nodes: self.nodes.len(),
+            tombstones: self.tombstones,
+            ..RuntimeStats::default()
+        };
+        if self.nodes.is_empty() {
+            return stats;
+        }
+        let mut stack = vec![(0, 1)];
+        while let Some((node, depth)) = stack.pop() {
+            stats.depth = stats.depth.max(depth);
+            stack.extend(
+                self.nodes[node]
+                    .children
+                    .iter()
+                    .map(|&(_, c)| (c, depth + 1)),
+            );
+        }
+        stats
+    }
+
+    /// how many removed words are still taking up nodes
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// builds the tree again from the remaining words in insertion order,
+    /// dropping every tombstone. the first remaining word is the new root
+    pub fn rebuild(&mut self) {
+        let nodes = core::mem::take(&mut self.nodes);
+        self.tombstones = 0;
+        for node in nodes.into_iter().filter(|n| !n.deleted) {
+            self.insert(node.word);
+        }
+    }
+
+    /// inserts every word, deduplicated and ordered by distance from the
+    /// root first so the tree comes out shallower than inserting them as
+    /// they come. the first word is the root of an empty tree
+    pub fn bulk_load<S: Into<String>>(&mut self, words: impl IntoIterator<Item = S>) -> LoadStats {
+        let mut words: Vec<String> = words.into_iter().map(Into::into).collect();
+        let (given, before) = (words.len(), self.len());
+        if self.nodes.is_empty() {
+            if let Some(i) = words.iter().position(|w| !w.is_empty()) {
+                self.insert(words.swap_remove(i));
+            }
+        }
+        words.sort_unstable();
+        words.dedup();
+        if let Some(root) = self.nodes.first() {
+            words.sort_by_cached_key(|w| distance(&root.word, w));
+        }
+        for word in words {
+            self.insert(word);
+        }
+        let inserted = self.len() - before;
+        LoadStats {
+            inserted,
+            skipped: given - inserted,
+        }
+    }
+
+    /// how many words are in the tree, tombstones aren't counted
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.tombstones
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// every word, in insertion order
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.nodes
+            .iter()
+            .filter(|n| !n.deleted)
+            .map(|n| n.word.as_str())
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let mut found = false;
+        self.walk(word, 0, &mut |_| {
+            found = true;
+            ControlFlow::Break(())
+        });
+        found
+    }
+
+    /// every word within `tolerance` edits of `word`, sorted by distance
+    /// (ties keep traversal order)
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'_>> {
+        let mut corrections = Vec::new();
+        self.walk(word, tolerance, &mut |s| {
+            corrections.push(s);
+            ControlFlow::Continue(())
+        });
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    /// the closest word within `tolerance`, on equal distance the one
+    /// found first in traversal order
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<Suggestion<'_>> {
+        let mut best: Option<Suggestion> = None;
+        self.walk(word, tolerance, &mut |s| {
+            if best.map_or(true, |b| s.distance < b.distance) {
+                best = Some(s);
+            }
+            match s.distance {
+                0 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        });
+        best
+    }
+
+    /// writes the tree as the same rust source [`BkTreeBuilder`] generates,
+    /// declared as `static {ident}`, for shipping a tree curated at runtime.
+    ///
+    /// it's grown the way the builder grows its own, from this tree's first
+    /// word with the rest added in the order they're here, so it has this
+    /// tree's shape short of the removed words. the words 15 or more edits
+    /// from the word they'd go under get no bucket in a generated tree,
+    /// like the builder it leaves them out and returns them
+    ///
+    /// [`BkTreeBuilder`]: crate::BkTreeBuilder
+    #[cfg(feature = "write")]
+    pub fn write_codegen(
+        &self,
+        mut out: impl std::io::Write,
+        ident: &str,
+    ) -> std::io::Result<Vec<&str>> {
+        use std::io::{Error, ErrorKind};
+        let mut words = self.words();
+        let Some(root) = words.next() else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "can't generate an empty tree",
+            ));
+        };
+        let (mut tree, unplaced) = write::grow(root, words);
+        let source = write::codegen(&mut tree, ident, None, false, None, write::Items::Statics)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        out.write_all(source.as_bytes())?;
+        Ok(unplaced)
+    }
+
+    // (parent, bucket, child) node indexes for checking the tree's shape
+    #[cfg(feature = "proptest")]
+    pub(crate) fn edges(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.nodes.iter().enumerate().flat_map(|(parent, node)| {
+            node.children
+                .iter()
+                .map(move |&(bucket, child)| (parent, bucket, child))
+        })
+    }
+
+    #[cfg(feature = "proptest")]
+    pub(crate) fn word_at(&self, node: usize) -> &str {
+        &self.nodes[node].word
+    }
+
+    /// how much work searching for `word` takes, like
+    /// [`Node::search_stats`]
+    pub fn search_stats(&self, word: &str, tolerance: u8) -> SearchStats {
+        let mut stats = SearchStats::default();
+        self.walk_counted(word, tolerance, &mut stats, &mut |_| {
+            ControlFlow::Continue(())
+        });
+        stats
+    }
+
+    fn walk<'a>(
+        &'a self,
+        word: &str,
+        tolerance: u8,
+        visit: &mut dyn FnMut(Suggestion<'a>) -> ControlFlow<()>,
+    ) {
+        self.walk_counted(word, tolerance, &mut SearchStats::default(), visit)
+    }
+
+    fn walk_counted<'a>(
+        &'a self,
+        word: &str,
+        tolerance: u8,
+        stats: &mut SearchStats,
+        visit: &mut dyn FnMut(Suggestion<'a>) -> ControlFlow<()>,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let tolerance = tolerance as usize;
+        let _ = walk_tree(
+            self,
+            0,
+            logged(&word),
+            &|w| distance(w, word),
+            &LeafCheck::default(),
+            tolerance,
+            0,
+            stats,
+            &mut |node, distance| {
+                let node = &self.nodes[node];
+                match distance <= tolerance && !node.deleted {
+                    true => visit(Suggestion::new(word, &node.word, distance as u8)),
+                    false => ControlFlow::Continue(()),
+                }
+            },
+        );
+    }
+}
+
+// of the first few words, the one whose largest bucket of the others is
+// smallest
+fn best_pivot(words: &[String]) -> usize {
+    const CANDIDATES: usize = 8;
+    let mut best = (usize::MAX, 0);
+    for (i, pivot) in words.iter().enumerate().take(CANDIDATES) {
+        let mut sizes: Vec<(usize, usize)> = Vec::new();
+        for word in words {
+            let d = distance(pivot, word);
+            match sizes.binary_search_by_key(&d, |(b, _)| *b) {
+                Ok(at) => sizes[at].1 += 1,
+                Err(at) => sizes.insert(at, (d, 1)),
+            }
+        }
+        let largest = sizes.iter().map(|(_, n)| *n).max().unwrap_or(0);
+        if largest < best.0 {
+            best = (largest, i);
+        }
+    }
+    best.1
+}
+
+/// the shape of a [`RuntimeBkTree`], nodes counting tombstones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeStats {
+    pub nodes: usize,
+    pub tombstones: usize,
+    /// nodes on the longest path from the root, 1 for just a root
+    pub depth: usize,
+}
+
+/// what [`RuntimeBkTree::bulk_load`] did with its input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadStats {
+    pub inserted: usize,
+    /// duplicates, within the input or of words already in the tree, and
+    /// empty words
+    pub skipped: usize,
+}
+
+impl<S: Into<String>> FromIterator<S> for RuntimeBkTree {
+    fn from_iter<I: IntoIterator<Item = S>>(words: I) -> Self {
+        let mut tree = Self::new();
+        tree.bulk_load(words);
+        tree
+    }
+}
+
+impl<S: Into<String>> Extend<S> for RuntimeBkTree {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, words: I) {
+        self.bulk_load(words);
+    }
+}
+
+impl Tree for RuntimeBkTree {
+    type Node = usize;
+
+    fn word(&self, node: usize) -> &str {
+        &self.nodes[node].word
+    }
+
+    fn children(
+        &self,
+        node: usize,
+        range: RangeInclusive<usize>,
+        visit: &mut dyn FnMut(usize) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let children = &self.nodes[node].children;
+        let start = children.partition_point(|(b, _)| b < range.start());
+        for &(_, child) in children[start..]
+            .iter()
+            .take_while(|(b, _)| range.contains(b))
+        {
+            visit(child)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+// stored as the root and the other words in insertion order, inserting them
+// again on the way in so an edited file can't break the tree
+#[cfg(feature = "serde")]
+impl serde::Serialize for RuntimeBkTree {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut words = self.words();
+        let root = words.next();
+        let mut state = serializer.serialize_struct("RuntimeBkTree", 2)?;
+        state.serialize_field("root", &root)?;
+        state.serialize_field("words", &words.collect::<Vec<_>>())?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RuntimeBkTree {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use alloc::{collections::BTreeSet, format};
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "RuntimeBkTree", deny_unknown_fields)]
+        struct Stored {
+            root: Option<String>,
+            #[serde(default)]
+            words: Vec<String>,
+        }
+
+        let stored = Stored::deserialize(deserializer)?;
+        let Some(root) = stored.root else {
+            return match stored.words.is_empty() {
+                true => Ok(Self::new()),
+                false => Err(D::Error::custom("words without a root")),
+            };
+        };
+        if root.is_empty() {
+            return Err(D::Error::custom("empty root word"));
+        }
+        let mut seen = BTreeSet::from([root.as_str()]);
+        for (i, word) in stored.words.iter().enumerate() {
+            if word.is_empty() {
+                return Err(D::Error::custom(format!("empty word at words[{i}]")));
+            }
+            if !seen.insert(word) {
+                return Err(D::Error::custom(format!(
+                    "duplicate word {word:?} at words[{i}]"
+                )));
+            }
+        }
+        let mut tree = Self::new();
+        tree.insert(root);
+        for word in stored.words {
+            tree.insert(word);
+        }
+        Ok(tree)
+    }
+}
@@ -0,0 +1,114 @@
+// word lists read off a fat formatted sd card at boot, one word per line.
+// the file is read a chunk at a time into the caller's buffer so a list of
+// any length only ever takes that buffer, a line can't be longer than it
+
+#[cfg(feature = "alloc")]
+use super::{LoadStats, RuntimeBkTree};
+use core::fmt;
+use embedded_sdmmc::{BlockDevice, File, TimeSource};
+
+/// why a word list stopped loading, lines count from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WordListError<E> {
+    Read(E),
+    /// the line doesn't fit in the buffer
+    TooLong {
+        line: u32,
+    },
+    NotUtf8 {
+        line: u32,
+    },
+}
+
+// the card driver's errors are only `Debug`
+impl<E: fmt::Debug> fmt::Display for WordListError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Read(e) => write!(f, "can't read the word list: {e:?}"),
+            Self::TooLong { line } => write!(f, "line {line} doesn't fit in the buffer"),
+            Self::NotUtf8 { line } => write!(f, "line {line} isn't utf-8"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for WordListError<E> {}
+
+/// calls `f` with the line number and word of every line read through
+/// `read`, which fills the start of its buffer returning how many bytes
+/// it read, 0 at the end. words are trimmed, blank lines and `\r\n` line
+/// endings are fine. returns how many words there were
+pub fn for_each_word<E>(
+    mut read: impl FnMut(&mut [u8]) -> Result<usize, E>,
+    buf: &mut [u8],
+    mut f: impl FnMut(u32, &str),
+) -> Result<usize, WordListError<E>> {
+    let (mut line, mut words) = (1, 0);
+    // bytes of a line that ran past the end of the last chunk
+    let mut carried = 0;
+    loop {
+        let space = &mut buf[carried..];
+        if space.is_empty() {
+            return Err(WordListError::TooLong { line });
+        }
+        let read = read(space).map_err(WordListError::Read)?;
+        let filled = carried + read;
+        let mut start = 0;
+        let mut one = |bytes: &[u8], line: u32| {
+            let word = core::str::from_utf8(bytes)
+                .map_err(|_| WordListError::NotUtf8 { line })?
+                .trim();
+            if !word.is_empty() {
+                f(line, word);
+                words += 1;
+            }
+            Ok(())
+        };
+        while let Some(end) = buf[start..filled].iter().position(|&b| b == b'\n') {
+            one(&buf[start..start + end], line)?;
+            start += end + 1;
+            line += 1;
+        }
+        if read == 0 {
+            // the last line needn't end in a newline
+            one(&buf[start..filled], line)?;
+            return Ok(words);
+        }
+        buf.copy_within(start..filled, 0);
+        carried = filled - start;
+    }
+}
+
+/// [`for_each_word`] over an open `embedded-sdmmc` file
+pub fn for_each_word_in_file<D, T, const DIRS: usize, const FILES: usize, const VOLUMES: usize>(
+    file: &File<D, T, DIRS, FILES, VOLUMES>,
+    buf: &mut [u8],
+    f: impl FnMut(u32, &str),
+) -> Result<usize, WordListError<embedded_sdmmc::Error<D::Error>>>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    for_each_word(|chunk| file.read(chunk), buf, f)
+}
+
+/// inserts every word of the file into `tree` as it's read. words already
+/// in the tree or earlier in the file are skipped
+#[cfg(feature = "alloc")]
+pub fn load_file<D, T, const DIRS: usize, const FILES: usize, const VOLUMES: usize>(
+    file: &File<D, T, DIRS, FILES, VOLUMES>,
+    buf: &mut [u8],
+    tree: &mut RuntimeBkTree,
+) -> Result<LoadStats, WordListError<embedded_sdmmc::Error<D::Error>>>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    let mut stats = LoadStats::default();
+    for_each_word_in_file(file, buf, |_, word| match tree.insert(word) {
+        true => stats.inserted += 1,
+        false => stats.skipped += 1,
+    })?;
+    Ok(stats)
+}
@@ -0,0 +1,93 @@
+// a runtime tree that threads can search and grow at the same time
+
+use super::RuntimeBkTree;
+use std::{
+    string::String,
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    vec::Vec,
+};
+
+/// a [`RuntimeBkTree`] behind a `RwLock`, every method takes `&self` so it
+/// can sit in an `Arc` or a static.
+///
+/// searches share the lock and an insert or remove takes it alone, so a
+/// search sees each word either fully in the tree or not at all. results
+/// are copied out so the lock isn't held past the call, use
+/// [`SharedBkTree::read`] to work on the tree in place. a panic while
+/// holding the lock doesn't poison it, the tree is never left half updated
+#[derive(Debug, Default)]
+pub struct SharedBkTree {
+    tree: RwLock<RuntimeBkTree>,
+}
+
+impl SharedBkTree {
+    pub const fn new(tree: RuntimeBkTree) -> Self {
+        Self {
+            tree: RwLock::new(tree),
+        }
+    }
+
+    /// the tree, blocking inserts and removes until the guard is dropped
+    pub fn read(&self) -> RwLockReadGuard<'_, RuntimeBkTree> {
+        self.tree.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// the tree, blocking everything else until the guard is dropped
+    pub fn write(&self) -> RwLockWriteGuard<'_, RuntimeBkTree> {
+        self.tree.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// see [`RuntimeBkTree::insert`]
+    pub fn insert(&self, word: impl Into<String>) -> bool {
+        // allocate before taking the lock
+        let word = word.into();
+        self.write().insert(word)
+    }
+
+    /// see [`RuntimeBkTree::remove`]
+    pub fn remove(&self, word: &str) -> bool {
+        self.write().remove(word)
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.read().contains(word)
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// every word within `tolerance` of `word` with its distance, sorted by
+    /// distance like [`RuntimeBkTree::corrections`]
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<(String, u8)> {
+        let tree = self.read();
+        let corrections = tree.corrections(word, tolerance);
+        corrections
+            .iter()
+            .map(|s| (s.word.into(), s.distance))
+            .collect()
+    }
+
+    /// see [`RuntimeBkTree::best_match`]
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<(String, u8)> {
+        let tree = self.read();
+        let best = tree.best_match(word, tolerance);
+        best.map(|s| (s.word.into(), s.distance))
+    }
+
+    pub fn into_inner(self) -> RuntimeBkTree {
+        self.tree
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<RuntimeBkTree> for SharedBkTree {
+    fn from(tree: RuntimeBkTree) -> Self {
+        Self::new(tree)
+    }
+}
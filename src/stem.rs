@@ -0,0 +1,108 @@
+// strips inflections off a query so a dictionary of base forms still
+// finds "cars" and "running"
+
+use super::{Node, Suggestion};
+
+/// a suffix to strip and what to put back in its place, "ies" and "y"
+/// turn "cities" into "city"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuffixRule {
+    pub suffix: &'static str,
+    pub replacement: &'static str,
+}
+
+const fn rule(suffix: &'static str, replacement: &'static str) -> SuffixRule {
+    SuffixRule {
+        suffix,
+        replacement,
+    }
+}
+
+/// common english inflections, earlier rules win on equal distance so the
+/// shorter strip comes first
+pub const ENGLISH_SUFFIXES: &[SuffixRule] = &[
+    rule("s", ""),
+    rule("es", ""),
+    rule("ies", "y"),
+    rule("ed", ""),
+    rule("ed", "e"),
+    rule("ied", "y"),
+    rule("ing", ""),
+    rule("ing", "e"),
+    rule("er", ""),
+    rule("est", ""),
+    rule("ly", ""),
+];
+
+/// the longest stem with its replacement [`Node::stemmed_match`] looks up,
+/// longer ones are only searched unstemmed
+pub const MAX_STEM_BYTES: usize = 64;
+
+/// a match found through [`Node::stemmed_match`]. `rule` is what was
+/// stripped off the query, `None` when the query matched as typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StemMatch {
+    pub suggestion: Suggestion<'static>,
+    pub rule: Option<SuffixRule>,
+}
+
+impl Node {
+    /// [`Node::best_match`] trying `word` as typed and with each rule of
+    /// `rules` applied, the closest wins and the query as typed wins ties.
+    /// a stem whose last two letters are the same is also tried with one
+    /// of them, so "running" gets to "run".
+    ///
+    /// a query that's in the tree is returned as is, it's never stemmed
+    pub fn stemmed_match(
+        &'static self,
+        word: &str,
+        tolerance: u8,
+        rules: &[SuffixRule],
+    ) -> Option<StemMatch> {
+        let mut best = self
+            .best_match(word, tolerance)
+            .map(|suggestion| StemMatch {
+                suggestion,
+                rule: None,
+            });
+        if best.is_some_and(|b| b.suggestion.distance == 0) {
+            return best;
+        }
+        let mut buf = [0; MAX_STEM_BYTES];
+        for &rule in rules {
+            let Some(stem) = word.strip_suffix(rule.suffix).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+            let Some(stem) = join(&mut buf, stem, rule.replacement) else {
+                continue;
+            };
+            let undoubled = match (rule.replacement, stem.as_bytes()) {
+                ("", [.., a, b]) if a == b && a.is_ascii_alphabetic() => {
+                    Some(&stem[..stem.len() - 1])
+                }
+                _ => None,
+            };
+            for stem in [Some(stem), undoubled].into_iter().flatten() {
+                let found = self.best_match(stem, tolerance);
+                if let Some(suggestion) = found {
+                    if best.map_or(true, |b| suggestion.distance < b.suggestion.distance) {
+                        best = Some(StemMatch {
+                            suggestion,
+                            rule: Some(rule),
+                        });
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+// `stem` followed by `replacement` in `buf`
+fn join<'b>(buf: &'b mut [u8], stem: &str, replacement: &str) -> Option<&'b str> {
+    let len = stem.len() + replacement.len();
+    let buf = buf.get_mut(..len)?;
+    buf[..stem.len()].copy_from_slice(stem.as_bytes());
+    buf[stem.len()..].copy_from_slice(replacement.as_bytes());
+    core::str::from_utf8(buf).ok()
+}
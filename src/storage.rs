@@ -0,0 +1,387 @@
+// searching a tree that lives in external storage, each node is read into a
+// buffer when the search gets to it instead of following a pointer
+//
+// the writer's blob is a little endian `u32` node count, a `u32` byte
+// offset per node into the blob plus one for where the last node ends and
+// then the nodes, node 0 is the root.
+// a node is its word's length as a `u8`, the word, the number of children
+// as a `u8` and a `u8` bucket and `u32` node id per child, in ascending
+// bucket order. a child's id is always past its parent's
+
+use super::{distance::distance, CHILDREN_LENGTH, MAX_STORED_WORD};
+use core::fmt;
+
+/// longest encoded node, what [`NodeBuf::decode`] may need to see
+pub const MAX_NODE_BYTES: usize = 2 + MAX_STORED_WORD + CHILDREN_LENGTH * 5;
+
+/// one node read from storage
+#[derive(Debug, Clone)]
+pub struct NodeBuf {
+    word: [u8; MAX_STORED_WORD],
+    word_len: u8,
+    children: [(u8, u32); CHILDREN_LENGTH],
+    child_count: u8,
+}
+
+/// why the bytes of a node aren't a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// the bytes end before the node does
+    Truncated,
+    /// a word of `len` bytes, past `MAX_STORED_WORD`
+    WordTooLong {
+        len: usize,
+    },
+    NotUtf8,
+    /// more children than a node has buckets
+    TooManyChildren {
+        count: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "node is truncated"),
+            Self::WordTooLong { len } => {
+                write!(f, "node word is {len} bytes, at most {MAX_STORED_WORD} fit")
+            }
+            Self::NotUtf8 => write!(f, "node word isn't utf-8"),
+            Self::TooManyChildren { count } => write!(
+                f,
+                "node has {count} children, at most {CHILDREN_LENGTH} fit"
+            ),
+        }
+    }
+}
+
+std_error!(DecodeError);
+
+impl NodeBuf {
+    pub const fn new() -> Self {
+        Self {
+            word: [0; MAX_STORED_WORD],
+            word_len: 0,
+            children: [(0, 0); CHILDREN_LENGTH],
+            child_count: 0,
+        }
+    }
+
+    /// reads an encoded node from the start of `bytes`, which can run past
+    /// its end, returning how many bytes it took
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<usize, DecodeError> {
+        use DecodeError::*;
+        let word_len = *bytes.first().ok_or(Truncated)? as usize;
+        if word_len > MAX_STORED_WORD {
+            return Err(WordTooLong { len: word_len });
+        }
+        let word = bytes.get(1..1 + word_len).ok_or(Truncated)?;
+        if core::str::from_utf8(word).is_err() {
+            return Err(NotUtf8);
+        }
+        let child_count = *bytes.get(1 + word_len).ok_or(Truncated)? as usize;
+        if child_count > CHILDREN_LENGTH {
+            return Err(TooManyChildren { count: child_count });
+        }
+        let start = 2 + word_len;
+        let children = bytes.get(start..start + child_count * 5).ok_or(Truncated)?;
+        self.word[..word_len].copy_from_slice(word);
+        self.word_len = word_len as u8;
+        for (slot, child) in self.children.iter_mut().zip(children.chunks_exact(5)) {
+            let id = u32::from_le_bytes([child[1], child[2], child[3], child[4]]);
+            *slot = (child[0], id);
+        }
+        self.child_count = child_count as u8;
+        Ok(start + child_count * 5)
+    }
+
+    pub fn word(&self) -> &str {
+        // checked by `decode`
+        core::str::from_utf8(&self.word[..self.word_len as usize]).unwrap_or("")
+    }
+
+    /// (bucket, node id) in ascending bucket order
+    pub fn children(&self) -> &[(u8, u32)] {
+        &self.children[..self.child_count as usize]
+    }
+}
+
+impl Default for NodeBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// where the nodes of a stored tree come from
+pub trait NodeStorage {
+    type Error;
+
+    /// reads node `id` into `buf`
+    fn read_node(&mut self, id: u32, buf: &mut NodeBuf) -> Result<(), Self::Error>;
+}
+
+/// why a blob couldn't be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BlobError<E> {
+    Read(E),
+    /// there's no node `id` or it doesn't decode
+    Corrupt {
+        id: u32,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for BlobError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Read(e) => write!(f, "can't read the blob: {e}"),
+            Self::Corrupt { id } => write!(f, "node {id} of the blob is corrupt"),
+        }
+    }
+}
+
+std_error!(BlobError<E>, E);
+
+impl<E> From<E> for BlobError<E> {
+    fn from(e: E) -> Self {
+        Self::Read(e)
+    }
+}
+
+/// [`NodeStorage`] over the writer's blob, read through `read(offset, buf)`
+/// filling `buf` from that byte offset of the blob. four small reads per
+/// node, the count, the node's offset, the offset after it and the node
+pub struct BlobStorage<F> {
+    read: F,
+}
+
+impl<F, E> BlobStorage<F>
+where
+    F: FnMut(u32, &mut [u8]) -> Result<(), E>,
+{
+    pub const fn new(read: F) -> Self {
+        Self { read }
+    }
+
+    fn read_u32(&mut self, offset: u32) -> Result<u32, BlobError<E>> {
+        let mut bytes = [0; 4];
+        (self.read)(offset, &mut bytes).map_err(BlobError::Read)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+}
+
+impl<F, E> NodeStorage for BlobStorage<F>
+where
+    F: FnMut(u32, &mut [u8]) -> Result<(), E>,
+{
+    type Error = BlobError<E>;
+
+    fn read_node(&mut self, id: u32, buf: &mut NodeBuf) -> Result<(), Self::Error> {
+        let corrupt = || BlobError::Corrupt { id };
+        let count = self.read_u32(0)?;
+        if id >= count {
+            return Err(corrupt());
+        }
+        // a corrupt count can put `id` where the offset would wrap
+        let at = id.checked_mul(4).and_then(|at| at.checked_add(4));
+        let at = at
+            .filter(|at| at.checked_add(8).is_some())
+            .ok_or_else(corrupt)?;
+        let start = self.read_u32(at)?;
+        let end = self.read_u32(at + 4)?;
+        let len = end.checked_sub(start).ok_or_else(corrupt)?;
+        // no `as usize`, a 16 bit usize would wrap a long length into range
+        let len = usize::try_from(len).map_err(|_| corrupt())?;
+        let mut bytes = [0; MAX_NODE_BYTES];
+        let bytes = bytes.get_mut(..len).ok_or_else(corrupt)?;
+        (self.read)(start, bytes).map_err(BlobError::Read)?;
+        match buf.decode(bytes) {
+            Ok(used) if used == len => {}
+            _ => return Err(corrupt()),
+        }
+        // the writer numbers children after their parent, a child that
+        // isn't would send the search around the same nodes forever
+        let ordered = |&(bucket, child): &(u8, u32)| bucket != 0 && child > id && child < count;
+        match buf.children().iter().all(ordered) {
+            true => Ok(()),
+            false => Err(corrupt()),
+        }
+    }
+}
+
+/// a word found in storage, `id` is its node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StoredMatch {
+    pub id: u32,
+    pub distance: u8,
+}
+
+/// why a search over storage stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StorageError<E> {
+    /// from [`NodeStorage::read_node`]
+    Storage(E),
+    /// more nodes were waiting to be read than `PENDING`
+    TooDeep,
+}
+
+impl<E: fmt::Display> fmt::Display for StorageError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Storage(e) => write!(f, "{e}"),
+            Self::TooDeep => write!(f, "more nodes pending than the search has room for"),
+        }
+    }
+}
+
+std_error!(StorageError<E>, E);
+
+impl<E> From<E> for StorageError<E> {
+    fn from(e: E) -> Self {
+        Self::Storage(e)
+    }
+}
+
+/// the `out.len()` closest words within `tolerance` of `word` in the tree
+/// at `root_id` of `storage`, written sorted by distance to `out` with how
+/// many were filled. ties go to the word found first, like
+/// [`Node::best_k_into`](crate::Node::best_k_into).
+///
+/// only one node is in ram at a time, plus up to `PENDING` ids of nodes
+/// still to read. a tree `d` levels deep needs at most `d * 15`, usually
+/// far fewer
+pub fn corrections_in_storage<S: NodeStorage, const PENDING: usize>(
+    storage: &mut S,
+    root_id: u32,
+    word: &str,
+    tolerance: u8,
+    out: &mut [StoredMatch],
+) -> Result<usize, StorageError<S::Error>> {
+    let mut search = Search::<PENDING>::new(root_id)?;
+    let mut buf = NodeBuf::new();
+    while let Some(id) = search.next() {
+        storage
+            .read_node(id, &mut buf)
+            .map_err(StorageError::Storage)?;
+        search.visit(id, &buf, word, tolerance, out)?;
+    }
+    Ok(search.filled)
+}
+
+/// [`NodeStorage`] for drivers that have to be awaited
+#[cfg(feature = "async")]
+pub trait AsyncNodeStorage {
+    type Error;
+
+    /// reads node `id` into `buf`, implementations can be `async fn`
+    fn read_node(
+        &mut self,
+        id: u32,
+        buf: &mut NodeBuf,
+    ) -> impl core::future::Future<Output = Result<(), Self::Error>>;
+}
+
+/// [`corrections_in_storage`] awaiting each node read.
+///
+/// across an await the future holds the ids still to read, the one
+/// [`NodeBuf`] and `&mut` borrows of `storage` and `out`, nothing else.
+/// dropping it between nodes is always fine. dropping it during a read
+/// drops that `read_node` future, so the search is exactly as cancel safe
+/// as the driver's read is. `out` then holds whatever was found so far
+#[cfg(feature = "async")]
+pub async fn corrections_in_async_storage<S: AsyncNodeStorage, const PENDING: usize>(
+    storage: &mut S,
+    root_id: u32,
+    word: &str,
+    tolerance: u8,
+    out: &mut [StoredMatch],
+) -> Result<usize, StorageError<S::Error>> {
+    let mut search = Search::<PENDING>::new(root_id)?;
+    let mut buf = NodeBuf::new();
+    while let Some(id) = search.next() {
+        storage
+            .read_node(id, &mut buf)
+            .await
+            .map_err(StorageError::Storage)?;
+        search.visit(id, &buf, word, tolerance, out)?;
+    }
+    Ok(search.filled)
+}
+
+// what a search over storage keeps between node reads
+struct Search<const PENDING: usize> {
+    pending: [u32; PENDING],
+    waiting: usize,
+    filled: usize,
+}
+
+impl<const PENDING: usize> Search<PENDING> {
+    fn new<E>(root_id: u32) -> Result<Self, StorageError<E>> {
+        let mut pending = [0; PENDING];
+        *pending.first_mut().ok_or(StorageError::TooDeep)? = root_id;
+        Ok(Self {
+            pending,
+            waiting: 1,
+            filled: 0,
+        })
+    }
+
+    fn next(&mut self) -> Option<u32> {
+        self.waiting = self.waiting.checked_sub(1)?;
+        Some(self.pending[self.waiting])
+    }
+
+    fn visit<E>(
+        &mut self,
+        id: u32,
+        buf: &NodeBuf,
+        word: &str,
+        tolerance: u8,
+        out: &mut [StoredMatch],
+    ) -> Result<(), StorageError<E>> {
+        let tolerance = tolerance as usize;
+        let d = distance(buf.word(), word);
+        if d <= tolerance {
+            let found = StoredMatch {
+                id,
+                distance: d as u8,
+            };
+            insert(out, &mut self.filled, found);
+        }
+        let range = d.saturating_sub(tolerance)..=d.saturating_add(tolerance);
+        // pushed highest first so they come off in ascending order
+        for &(bucket, child) in buf.children().iter().rev() {
+            if range.contains(&(bucket as usize)) {
+                *self
+                    .pending
+                    .get_mut(self.waiting)
+                    .ok_or(StorageError::TooDeep)? = child;
+                self.waiting += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+// into the sorted `out`, after every equal distance, replacing the worst
+// only when strictly closer
+fn insert(out: &mut [StoredMatch], filled: &mut usize, found: StoredMatch) {
+    if out.is_empty() {
+        return;
+    }
+    if *filled == out.len() {
+        if found.distance >= out[*filled - 1].distance {
+            return;
+        }
+        *filled -= 1;
+    }
+    let at = out[..*filled]
+        .iter()
+        .position(|m| m.distance > found.distance)
+        .unwrap_or(*filled);
+    out[at..=*filled].rotate_right(1);
+    out[at] = found;
+    *filled += 1;
+}
@@ -0,0 +1,111 @@
+// spell checks text as it arrives a character at a time, for input that
+// never shows up as a whole line like keystrokes over a uart
+
+use super::Node;
+
+/// a word the stream just finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WordEvent<'a> {
+    /// the word as typed, only the first part of it on overflow
+    pub word: &'a str,
+    /// whether the word is in the tree
+    pub exact: bool,
+    /// the closest word within tolerance when it isn't
+    pub suggestion: Option<&'static str>,
+    /// the word didn't fit the buffer, it isn't looked up
+    pub overflow: bool,
+}
+
+/// collects characters into words of up to `BUF` bytes and looks each one
+/// up once a boundary ends it.
+///
+/// whitespace (CR, LF and tab included) and ascii punctuation other than
+/// `'` end a word, so "don't" stays one. a longer word than fits is
+/// reported with `overflow` set instead of being cut into pieces. a phrase
+/// is never looked up whole, in a tree of phrases only the entries without
+/// a space can match
+#[derive(Debug, Clone)]
+pub struct StreamCorrector<const BUF: usize> {
+    root: &'static Node,
+    tolerance: u8,
+    buf: [u8; BUF],
+    len: usize,
+    overflow: bool,
+    // the last event still borrows the buffer, it's cleared on the next push
+    reported: bool,
+}
+
+impl<const BUF: usize> StreamCorrector<BUF> {
+    pub const fn new(root: &'static Node, tolerance: u8) -> Self {
+        Self {
+            root,
+            tolerance,
+            buf: [0; BUF],
+            len: 0,
+            overflow: false,
+            reported: false,
+        }
+    }
+
+    /// feeds the next character, a boundary after a word gives its event
+    pub fn push(&mut self, c: char) -> Option<WordEvent<'_>> {
+        if self.reported {
+            self.reset();
+        }
+        if is_boundary(c) {
+            return self.finish();
+        }
+        let width = c.len_utf8();
+        if self.overflow || self.len + width > BUF {
+            self.overflow = true;
+        } else {
+            c.encode_utf8(&mut self.buf[self.len..]);
+            self.len += width;
+        }
+        None
+    }
+
+    /// ends the word in progress, if any, like a boundary would. for the
+    /// end of the input
+    pub fn finish(&mut self) -> Option<WordEvent<'_>> {
+        if self.reported {
+            self.reset();
+        }
+        if self.len == 0 && !self.overflow {
+            return None;
+        }
+        self.reported = true;
+        // only whole chars are ever written
+        let word = core::str::from_utf8(&self.buf[..self.len]).unwrap();
+        if self.overflow {
+            return Some(WordEvent {
+                word,
+                exact: false,
+                suggestion: None,
+                overflow: true,
+            });
+        }
+        let exact = self.root.contains(word);
+        let suggestion = match exact {
+            true => None,
+            false => self.root.best_match(word, self.tolerance).map(|s| s.word),
+        };
+        Some(WordEvent {
+            word,
+            exact,
+            suggestion,
+            overflow: false,
+        })
+    }
+
+    /// drops the word in progress
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.overflow = false;
+        self.reported = false;
+    }
+}
+
+fn is_boundary(c: char) -> bool {
+    c.is_whitespace() || (c.is_ascii_punctuation() && c != '\'')
+}
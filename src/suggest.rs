@@ -0,0 +1,49 @@
+// "did you mean" for command line tools, the valid subcommands or flags
+// of a tree suggested for one that isn't
+
+extern crate alloc;
+use super::{
+    distance::length,
+    read::{length_tolerance, sort_results},
+    Node,
+};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// up to `max_suggestions` of the words of `tree` that `input` could have
+/// been meant as, closest first. `input` is lowercased, so the words should
+/// be lowercase like most subcommands. the tolerance grows with the input
+/// like [`suggested_tolerance`](crate::suggested_tolerance), so 2 chars or
+/// fewer only get a match differing in case, and on equal distance words
+/// starting with the input's first char go first
+pub fn did_you_mean(input: &str, tree: &'static Node, max_suggestions: usize) -> Vec<&'static str> {
+    let input = input.to_lowercase();
+    let first = input.chars().next();
+    let mut found = tree.corrections(&input, length_tolerance(length(&input)));
+    // stable, traversal order breaks the ties left
+    sort_results(&mut found, |s| (s.distance, s.word.chars().next() != first));
+    found
+        .into_iter()
+        .take(max_suggestions)
+        .map(|s| s.word)
+        .collect()
+}
+
+/// writes `did you mean 'status'?` to `out`, or `'stash' or 'start'` and
+/// `'a', 'b' or 'c'` for more suggestions. nothing when there are none
+pub fn write_did_you_mean<W: fmt::Write>(out: &mut W, suggestions: &[&str]) -> fmt::Result {
+    let Some((last, rest)) = suggestions.split_last() else {
+        return Ok(());
+    };
+    out.write_str("did you mean ")?;
+    for (i, word) in rest.iter().enumerate() {
+        if i > 0 {
+            out.write_str(", ")?;
+        }
+        write!(out, "'{word}'")?;
+    }
+    if !rest.is_empty() {
+        out.write_str(" or ")?;
+    }
+    write!(out, "'{last}'?")
+}
@@ -0,0 +1,234 @@
+// words shipped after the firmware, searched alongside the baked tree
+//
+// a supplement blob is
+//
+//     magic    b"BKSP"
+//     version  u8, 1
+//     base     u32, the `CHECKSUM` of the tree it extends
+//     count    u32, how many words follow
+//     sum      u32, `checksum` of those words
+//     words    count times a u8 byte length and that many bytes of utf-8
+//
+// with every integer little endian
+
+use super::{checksum, distance::distance, StaticBkTree, Suggestion};
+use core::fmt;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use super::read::sort_by_distance;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+const MAGIC: &[u8; 4] = b"BKSP";
+const VERSION: u8 = 1;
+const HEADER_BYTES: usize = 17;
+
+/// why [`Supplement::parse`] refused a blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// doesn't start with `BKSP`
+    BadMagic,
+    /// a format this version doesn't read
+    UnsupportedVersion(u8),
+    /// ends before its header or its words do
+    Truncated,
+    /// word `index` is empty or not utf-8
+    BadWord { index: u32 },
+    /// bytes left over after `count` words
+    TrailingBytes,
+    /// the words don't add up to the header's checksum
+    ChecksumMismatch,
+    /// made for a different dictionary than the tree it was given with
+    BaseMismatch { expected: u32, found: u32 },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a supplement, it doesn't start with BKSP"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "supplement version {version} isn't supported")
+            }
+            Self::Truncated => write!(f, "the supplement is truncated"),
+            Self::BadWord { index } => write!(f, "supplement word {index} is empty or not utf-8"),
+            Self::TrailingBytes => write!(f, "bytes left over after the supplement's words"),
+            Self::ChecksumMismatch => write!(f, "the supplement's words don't match its checksum"),
+            Self::BaseMismatch { expected, found } => write!(
+                f,
+                "the supplement is for dictionary {found:#010x}, not {expected:#010x}"
+            ),
+        }
+    }
+}
+
+std_error!(ParseError);
+
+/// a validated supplement blob, see the module source for the format
+#[derive(Debug, Clone, Copy)]
+pub struct Supplement {
+    base: u32,
+    count: u32,
+    words: &'static [u8],
+}
+
+impl Supplement {
+    pub fn parse(blob: &'static [u8]) -> Result<Self, ParseError> {
+        let header = blob
+            .get(..HEADER_BYTES)
+            .ok_or(match blob.starts_with(MAGIC) {
+                true => ParseError::Truncated,
+                false => ParseError::BadMagic,
+            })?;
+        if &header[..4] != MAGIC {
+            return Err(ParseError::BadMagic);
+        }
+        if header[4] != VERSION {
+            return Err(ParseError::UnsupportedVersion(header[4]));
+        }
+        let int = |at: usize| {
+            u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+        };
+        let supplement = Self {
+            base: int(5),
+            count: int(9),
+            words: &blob[HEADER_BYTES..],
+        };
+        // walked by hand so a bad word can be told apart from a short blob
+        let mut rest = supplement.words;
+        let mut sum = 0u32;
+        for index in 0..supplement.count {
+            let (&len, tail) = rest.split_first().ok_or(ParseError::Truncated)?;
+            let word = tail.get(..len as usize).ok_or(ParseError::Truncated)?;
+            let word = core::str::from_utf8(word)
+                .ok()
+                .filter(|w| !w.is_empty())
+                .ok_or(ParseError::BadWord { index })?;
+            sum = sum.wrapping_add(checksum([word]));
+            rest = &tail[len as usize..];
+        }
+        if !rest.is_empty() {
+            return Err(ParseError::TrailingBytes);
+        }
+        if sum != int(13) {
+            return Err(ParseError::ChecksumMismatch);
+        }
+        Ok(supplement)
+    }
+
+    /// the `CHECKSUM` of the dictionary this extends
+    pub const fn base_checksum(&self) -> u32 {
+        self.base
+    }
+
+    pub const fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &'static str> {
+        let mut rest = self.words;
+        (0..self.count).map(move |_| {
+            let len = rest[0] as usize;
+            let word = &rest[1..1 + len];
+            rest = &rest[1 + len..];
+            // checked by `parse`
+            core::str::from_utf8(word).unwrap_or("")
+        })
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words().any(|w| w == word)
+    }
+
+    // a linear scan, supplements are meant to be small
+    fn for_each_match(&self, word: &str, tolerance: u8, mut f: impl FnMut(Suggestion<'static>)) {
+        for w in self.words() {
+            let d = distance(w, word);
+            if d <= tolerance as usize {
+                f(Suggestion::new(word, w, d as u8));
+            }
+        }
+    }
+
+    /// the blob for `words` on top of the dictionary with `base_checksum`
+    ///
+    /// panics on a word that's empty or longer than 255 bytes
+    #[cfg(feature = "alloc")]
+    pub fn encode<'a>(base_checksum: u32, words: impl IntoIterator<Item = &'a str>) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut count = 0u32;
+        let mut sum = 0u32;
+        for word in words {
+            assert!(!word.is_empty(), "supplement contains an empty word");
+            let len = u8::try_from(word.len()).expect("supplement word longer than 255 bytes");
+            body.push(len);
+            body.extend_from_slice(word.as_bytes());
+            sum = sum.wrapping_add(checksum([word]));
+            count += 1;
+        }
+        let mut blob = Vec::with_capacity(HEADER_BYTES + body.len());
+        blob.extend_from_slice(MAGIC);
+        blob.push(VERSION);
+        blob.extend_from_slice(&base_checksum.to_le_bytes());
+        blob.extend_from_slice(&count.to_le_bytes());
+        blob.extend_from_slice(&sum.to_le_bytes());
+        blob.extend_from_slice(&body);
+        blob
+    }
+}
+
+/// the baked tree and a supplement made for it searched as one. a word in
+/// both is only returned once and the baked one wins on equal distance
+#[derive(Debug, Clone, Copy)]
+pub struct SupplementedTree {
+    tree: StaticBkTree,
+    supplement: Supplement,
+}
+
+impl SupplementedTree {
+    /// refuses a supplement made for some other dictionary than `tree`
+    pub fn new(tree: StaticBkTree, supplement: Supplement) -> Result<Self, ParseError> {
+        match supplement.base == tree.checksum() {
+            true => Ok(Self { tree, supplement }),
+            false => Err(ParseError::BaseMismatch {
+                expected: tree.checksum(),
+                found: supplement.base,
+            }),
+        }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.tree.root().contains(word) || self.supplement.contains(word)
+    }
+
+    /// every word within `tolerance` of `word` from either, sorted by
+    /// distance
+    #[cfg(feature = "alloc")]
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        let mut corrections = self.tree.corrections(word, tolerance);
+        let baked = corrections.len();
+        self.supplement.for_each_match(word, tolerance, |s| {
+            if !corrections[..baked].iter().any(|b| b.word == s.word) {
+                corrections.push(s);
+            }
+        });
+        // stable, so baked words stay ahead on equal distance
+        sort_by_distance(&mut corrections);
+        corrections
+    }
+
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<Suggestion<'static>> {
+        let mut best = self.tree.best_match(word, tolerance);
+        self.supplement.for_each_match(word, tolerance, |s| {
+            if best.map_or(true, |b| s.distance < b.distance) {
+                best = Some(s);
+            }
+        });
+        best
+    }
+}
@@ -0,0 +1,94 @@
+//! proptest strategies and properties for fuzzing the trees, shared by
+//! this crate's tests and anyone else's
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn search(words in word_list("abc", 1..=6, 1..50), query in word("abc", 0..=6)) {
+//!         search_matches_brute_force(&runtime_tree(&words), &query, 2)?;
+//!     }
+//! }
+//! ```
+
+use super::{distance::distance, RuntimeBkTree};
+use proptest::{collection, prelude::*, test_runner::TestCaseError};
+use std::{collections::BTreeSet, ops::RangeInclusive, string::String, vec::Vec};
+
+/// a word of `len` chars drawn from `alphabet`
+pub fn word(alphabet: &str, len: RangeInclusive<usize>) -> impl Strategy<Value = String> {
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    collection::vec(proptest::sample::select(alphabet), len).prop_map(String::from_iter)
+}
+
+/// `count` words from [`word`], duplicates and all, the writer and
+/// [`RuntimeBkTree`] both have to cope with them
+pub fn word_list(
+    alphabet: &str,
+    len: RangeInclusive<usize>,
+    count: core::ops::Range<usize>,
+) -> impl Strategy<Value = Vec<String>> {
+    collection::vec(word(alphabet, len), count)
+}
+
+/// a tree of `words` inserted in order
+pub fn runtime_tree(words: &[String]) -> RuntimeBkTree {
+    words.iter().map(String::as_str).collect()
+}
+
+/// `corrections` returns exactly what a scan over every word finds
+pub fn search_matches_brute_force(
+    tree: &RuntimeBkTree,
+    query: &str,
+    tolerance: u8,
+) -> Result<(), TestCaseError> {
+    let found: BTreeSet<_> = tree
+        .corrections(query, tolerance)
+        .into_iter()
+        .map(|s| (s.word, s.distance as usize))
+        .collect();
+    let expected: BTreeSet<_> = tree
+        .words()
+        .map(|w| (w, distance(w, query)))
+        .filter(|(_, d)| *d <= tolerance as usize)
+        .collect();
+    prop_assert_eq!(found, expected, "query {:?} tolerance {}", query, tolerance);
+    Ok(())
+}
+
+/// every child is exactly its bucket's distance from its parent and every
+/// node but the root has one parent
+pub fn invariants_hold(tree: &RuntimeBkTree) -> Result<(), TestCaseError> {
+    let mut children = BTreeSet::new();
+    for (parent, bucket, child) in tree.edges() {
+        let (parent_word, child_word) = (tree.word_at(parent), tree.word_at(child));
+        prop_assert_eq!(
+            distance(parent_word, child_word),
+            bucket,
+            "{} under {}",
+            child_word,
+            parent_word
+        );
+        prop_assert!(children.insert(child), "{} has two parents", child_word);
+    }
+    prop_assert!(!children.contains(&0), "the root has a parent");
+    Ok(())
+}
+
+/// `words()` yields each of `inserted` once, `len` agrees and nothing else
+/// turns up
+pub fn iterator_yields_every_word_once(
+    tree: &RuntimeBkTree,
+    inserted: &[String],
+) -> Result<(), TestCaseError> {
+    let yielded: Vec<_> = tree.words().collect();
+    let unique: BTreeSet<_> = yielded.iter().copied().collect();
+    prop_assert_eq!(unique.len(), yielded.len(), "a word came out twice");
+    let expected: BTreeSet<_> = inserted
+        .iter()
+        .map(String::as_str)
+        .filter(|w| !w.is_empty())
+        .collect();
+    prop_assert_eq!(unique, expected);
+    prop_assert_eq!(tree.len(), yielded.len());
+    Ok(())
+}
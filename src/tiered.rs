@@ -0,0 +1,88 @@
+// one search answering several tolerances at once. a word within a small
+// tolerance is within every larger one too, so the widest search finds
+// them all and each goes to the smallest tolerance admitting it
+
+extern crate alloc;
+use super::{read::sort_by_distance, Node, SearchStats, StaticBkTree, Suggestion};
+use alloc::{vec, vec::Vec};
+use core::{fmt, ops::ControlFlow};
+
+/// the corrections of [`Node::corrections_tiered`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TieredResults {
+    /// one list per tolerance in the order they were given, the words
+    /// within it and not within the one before, sorted by distance (ties
+    /// keep traversal order)
+    pub tiers: Vec<Vec<Suggestion<'static>>>,
+    /// the work of the one search at the largest tolerance
+    pub stats: SearchStats,
+}
+
+/// why [`Node::corrections_tiered`] refused its tolerances
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ToleranceError {
+    Empty,
+    /// tolerance `at` isn't larger than the one before it
+    NotAscending {
+        at: usize,
+    },
+}
+
+impl fmt::Display for ToleranceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no tolerances to search"),
+            Self::NotAscending { at } => {
+                write!(f, "tolerance {at} isn't larger than the one before it")
+            }
+        }
+    }
+}
+
+std_error!(ToleranceError);
+
+impl Node {
+    /// the corrections at each of `tolerances`, which have to be strictly
+    /// ascending, in a single search. `&[1, 2]` gives the words within 1
+    /// and then those at exactly 2, the same as two `corrections` calls
+    /// with the first's words dropped from the second
+    pub fn corrections_tiered(
+        &'static self,
+        word: &str,
+        tolerances: &[u8],
+    ) -> Result<TieredResults, ToleranceError> {
+        let Some(&widest) = tolerances.last() else {
+            return Err(ToleranceError::Empty);
+        };
+        if let Some(at) = (1..tolerances.len()).find(|&i| tolerances[i] <= tolerances[i - 1]) {
+            return Err(ToleranceError::NotAscending { at });
+        }
+        let mut results = TieredResults {
+            tiers: vec![Vec::new(); tolerances.len()],
+            stats: SearchStats::default(),
+        };
+        let tiers = &mut results.tiers;
+        let _ = self.walk(word, widest as usize, &mut results.stats, &mut |s| {
+            // the widest admits everything the search finds
+            let tier = tolerances.partition_point(|&t| t < s.distance);
+            tiers[tier].push(s);
+            ControlFlow::Continue(())
+        });
+        for tier in &mut results.tiers {
+            sort_by_distance(tier);
+        }
+        Ok(results)
+    }
+}
+
+impl StaticBkTree {
+    /// see [`Node::corrections_tiered`]
+    pub fn corrections_tiered(
+        &self,
+        word: &str,
+        tolerances: &[u8],
+    ) -> Result<TieredResults, ToleranceError> {
+        self.root().corrections_tiered(word, tolerances)
+    }
+}
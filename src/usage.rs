@@ -0,0 +1,123 @@
+// how often the user picked each word, kept in ram next to the tree in
+// flash so suggestions can lean towards their vocabulary
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use super::{read::SearchStats, Node, Suggestion};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+/// a saturating use count per word of a tree with `N` words, indexed by
+/// [`Node::index`]. size it with the generated `WORD_COUNT`.
+///
+/// searches through it break distance ties in favour of the word used more
+/// often, among equally used words the search order still decides
+#[derive(Debug, Clone)]
+pub struct UsageCounters<const N: usize> {
+    root: &'static Node,
+    counts: [u16; N],
+}
+
+impl<const N: usize> UsageCounters<N> {
+    pub const fn new(root: &'static Node) -> Self {
+        Self {
+            root,
+            counts: [0; N],
+        }
+    }
+
+    /// counts a use of `word`, false if it isn't in the tree
+    pub fn bump(&mut self, word: &str) -> bool {
+        match self.root.exact(word, &mut SearchStats::default()) {
+            Some(node) => {
+                self.bump_node(node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// [`UsageCounters::bump`] for a node already found, like the word of
+    /// an accepted suggestion
+    pub fn bump_node(&mut self, node: &Node) {
+        if let Some(count) = self.counts.get_mut(node.index() as usize) {
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// how often `word` was used, 0 for words not in the tree
+    pub fn count(&self, word: &str) -> u16 {
+        self.root
+            .exact(word, &mut SearchStats::default())
+            .map_or(0, |node| self.count_node(node))
+    }
+
+    fn count_node(&self, node: &Node) -> u16 {
+        self.counts.get(node.index() as usize).copied().unwrap_or(0)
+    }
+
+    /// halves every count, call it now and then so old habits fade and
+    /// counts stay well clear of saturating
+    pub fn decay(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count /= 2);
+    }
+
+    /// forgets every use
+    pub fn clear(&mut self) {
+        self.counts = [0; N];
+    }
+
+    /// the closest word within `tolerance` of `word`, on equal distance the
+    /// most used one
+    pub fn best_match(&self, word: &str, tolerance: u8) -> Option<Suggestion<'static>> {
+        let mut best: Option<(Suggestion<'static>, u16)> = None;
+        self.for_each_counted(word, tolerance, &mut |s, count| {
+            if best.map_or(true, |(b, c)| {
+                (s.distance, u16::MAX - count) < (b.distance, u16::MAX - c)
+            }) {
+                best = Some((s, count));
+            }
+        });
+        best.map(|(s, _)| s)
+    }
+
+    /// every word within `tolerance` of `word`, sorted by distance and then
+    /// by use, most used first
+    #[cfg(feature = "alloc")]
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Vec<Suggestion<'static>> {
+        let mut corrections = Vec::new();
+        self.for_each_counted(word, tolerance, &mut |s, count| {
+            corrections.push((s, count))
+        });
+        // stable, so ties still keep traversal order
+        super::read::sort_results(&mut corrections, |(s, count)| {
+            (s.distance, u16::MAX - count)
+        });
+        corrections.into_iter().map(|(s, _)| s).collect()
+    }
+
+    fn for_each_counted(
+        &self,
+        word: &str,
+        tolerance: u8,
+        f: &mut dyn FnMut(Suggestion<'static>, u16),
+    ) {
+        let tolerance = tolerance as usize;
+        let _ = self.root.walk_nodes(
+            word,
+            tolerance,
+            &mut SearchStats::default(),
+            &mut |node, distance| {
+                if distance <= tolerance {
+                    f(
+                        Suggestion::new(word, node.word(), distance as u8),
+                        self.count_node(node),
+                    );
+                }
+                ControlFlow::Continue(())
+            },
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// structural checks for trees that didn't come straight out of the writer
+
+extern crate alloc;
+use super::{distance::distance, Node, CHILDREN_LENGTH};
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::fmt;
+
+/// the first broken invariant found by [`Node::check_invariants`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvariantError {
+    /// `child` sits in bucket `expected` of `parent` but is `actual` edits away
+    WrongBucket {
+        parent: &'static str,
+        child: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// `child` sits in a bucket past the node arity
+    BucketOutOfRange {
+        parent: &'static str,
+        child: &'static str,
+        bucket: usize,
+    },
+    /// `word` is stored in more than one node
+    DuplicateWord { word: &'static str },
+    /// the node holding `word` is reachable from more than one parent
+    SharedNode { word: &'static str },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WrongBucket {
+                parent,
+                child,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{child:?} is in bucket {expected} of {parent:?} but {actual} edits away"
+            ),
+            Self::BucketOutOfRange {
+                parent,
+                child,
+                bucket,
+            } => write!(
+                f,
+                "{child:?} is in bucket {bucket} of {parent:?}, past the last"
+            ),
+            Self::DuplicateWord { word } => write!(f, "{word:?} is in more than one node"),
+            Self::SharedNode { word } => write!(f, "the node of {word:?} has more than one parent"),
+        }
+    }
+}
+
+std_error!(InvariantError);
+
+impl Node {
+    /// checks the bk invariant over the whole tree: every child is at
+    /// exactly its bucket's distance from its parent, every word is stored
+    /// once and every node has a single parent
+    pub fn check_invariants(&'static self) -> Result<(), InvariantError> {
+        let mut nodes = BTreeSet::new();
+        let mut words = BTreeSet::new();
+        let mut stack = vec![self];
+        nodes.insert(self as *const Node);
+        words.insert(self.word());
+        while let Some(parent) = stack.pop() {
+            for (bucket, child) in parent.children() {
+                if bucket >= CHILDREN_LENGTH {
+                    return Err(InvariantError::BucketOutOfRange {
+                        parent: parent.word(),
+                        child: child.word(),
+                        bucket,
+                    });
+                }
+                let actual = distance(parent.word(), child.word());
+                if actual != bucket {
+                    return Err(InvariantError::WrongBucket {
+                        parent: parent.word(),
+                        child: child.word(),
+                        expected: bucket,
+                        actual,
+                    });
+                }
+                if !nodes.insert(child as *const Node) {
+                    return Err(InvariantError::SharedNode { word: child.word() });
+                }
+                if !words.insert(child.word()) {
+                    return Err(InvariantError::DuplicateWord { word: child.word() });
+                }
+                stack.push(child);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// how a search disagreed with the brute force scan in [`verify_search`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mismatch {
+    /// within tolerance but not returned
+    pub missing: Vec<&'static str>,
+    /// returned but not within tolerance
+    pub extra: Vec<&'static str>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the search missed {:?} and wrongly found {:?}",
+            self.missing, self.extra
+        )
+    }
+}
+
+std_error!(Mismatch);
+
+/// checks that `corrections` returns exactly the words a scan over every
+/// node finds within `tolerance`
+pub fn verify_search(root: &'static Node, word: &str, tolerance: u8) -> Result<(), Mismatch> {
+    let found: BTreeSet<_> = root
+        .corrections(word, tolerance)
+        .into_iter()
+        .map(|s| s.word)
+        .collect();
+    let expected: BTreeSet<_> = root
+        .iter()
+        .map(|n| n.word())
+        .filter(|w| distance(w, word) <= tolerance as usize)
+        .collect();
+    let mismatch = Mismatch {
+        missing: expected.difference(&found).copied().collect(),
+        extra: found.difference(&expected).copied().collect(),
+    };
+    match mismatch == Mismatch::default() {
+        true => Ok(()),
+        false => Err(mismatch),
+    }
+}
+
+/// [`verify_search`] over a batch of queries, stopping at the first one
+/// that disagrees
+pub fn verify_searches<'q>(
+    root: &'static Node,
+    queries: impl IntoIterator<Item = &'q str>,
+    tolerance: u8,
+) -> Result<(), (&'q str, Mismatch)> {
+    queries
+        .into_iter()
+        .try_for_each(|q| verify_search(root, q, tolerance).map_err(|m| (q, m)))
+}
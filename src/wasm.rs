@@ -0,0 +1,65 @@
+// the generated dictionary for javascript, so a web page can check words
+// against the same list the device has
+
+extern crate alloc;
+
+use super::{StaticBkTree, Suggestion};
+use alloc::{string::String, vec::Vec};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+/// a [`StaticBkTree`] for wasm_bindgen. wrap the generated tree and hand it
+/// to javascript from a function of your own,
+///
+/// ```ignore
+/// #[wasm_bindgen]
+/// pub fn dictionary() -> WasmBkTree {
+///     WasmBkTree::new(StaticBkTree::new(&TREE, WORD_COUNT, CHECKSUM))
+/// }
+/// ```
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct WasmBkTree {
+    tree: StaticBkTree,
+}
+
+impl WasmBkTree {
+    pub const fn new(tree: StaticBkTree) -> Self {
+        Self { tree }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmBkTree {
+    pub fn contains(&self, word: &str) -> bool {
+        self.tree.root().contains(word)
+    }
+
+    /// `{word, distance}` objects sorted by distance. each word is a new
+    /// javascript string, nothing stays borrowed from wasm memory
+    pub fn corrections(&self, word: &str, tolerance: u8) -> Array {
+        entries(&self.tree.corrections(word, tolerance))
+            .into_iter()
+            .map(|(word, distance)| {
+                let entry = Object::new();
+                let _ = Reflect::set(&entry, &"word".into(), &JsValue::from_str(&word));
+                let _ = Reflect::set(&entry, &"distance".into(), &distance.into());
+                JsValue::from(entry)
+            })
+            .collect()
+    }
+
+    #[wasm_bindgen(js_name = wordCount)]
+    pub fn word_count(&self) -> usize {
+        self.tree.word_count()
+    }
+}
+
+// what each javascript object is made from, kept apart so it can be
+// tested without a javascript engine
+pub(crate) fn entries(suggestions: &[Suggestion]) -> Vec<(String, u8)> {
+    suggestions
+        .iter()
+        .map(|s| (s.word.into(), s.distance))
+        .collect()
+}
@@ -0,0 +1,1576 @@
+#[cfg(feature = "rkyv")]
+use super::archive::{ArchiveNode, TreeArchive};
+#[cfg(feature = "confusables")]
+use super::confusable::{fold_confusable, fold_confusables_string};
+#[cfg(feature = "hamming")]
+use super::hamming::hamming;
+#[cfg(feature = "inflect")]
+use super::inflect::{inflect, InflectionRule};
+#[cfg(feature = "phonetic")]
+use super::phonetic::phonetic_code;
+use super::{
+    bloom,
+    distance::{char_mask, distance, length},
+    hash::checksum,
+    CHILDREN_LENGTH, MAX_STORED_WORD, ROOT_WORD,
+};
+use std::{
+    ascii,
+    borrow::Cow,
+    boxed::Box,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    env::var,
+    fmt, format, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    println,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+#[derive(Debug, Clone)]
+pub struct Node<'a> {
+    pub word: &'a str,
+    pub index: u32,
+    pub mask: u32,
+    pub suppressed: bool,
+    pub children: [Option<Box<Node<'a>>>; CHILDREN_LENGTH],
+}
+
+// how the items of a tree are declared, see `BkTreeBuilder::const_items`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Items {
+    Statics,
+    // each a `&'static` to one promoted value, so every use of an item is
+    // the same node rather than a copy of it
+    Consts,
+}
+
+impl Items {
+    // the item `name` of type `ty` holding `value`
+    fn declare(self, name: &str, ty: &str, value: &str) -> String {
+        match self {
+            Self::Statics => format!("static {name}: {ty} = {value};\n"),
+            Self::Consts => format!("const {name}: &{ty} = &{value};\n"),
+        }
+    }
+    // a `&'static` to the item `name`
+    fn reference(self, name: &str) -> String {
+        match self {
+            Self::Statics => format!("&{name}"),
+            Self::Consts => name.to_string(),
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    pub(crate) fn new(word: &'a str) -> Self {
+        Self {
+            word,
+            index: 0,
+            mask: char_mask(word),
+            suppressed: false,
+            children: Default::default(),
+        }
+    }
+    // false if the word was already in the tree or too far by `metric` to
+    // fit a bucket
+    fn add_by(&mut self, word: &'a str, metric: fn(&str, &str) -> usize) -> bool {
+        let diff = metric(self.word, word);
+        if diff == 0 || diff >= CHILDREN_LENGTH {
+            return false;
+        }
+        if let Some(node) = self.children[diff].as_mut() {
+            node.add_by(word, metric)
+        } else {
+            self.children[diff] = Some(Box::new(Node::new(word)));
+            true
+        }
+    }
+    // the node as a `Node::new` expression, children inline
+    pub fn as_string(&self) -> String {
+        let children: Vec<_> = self
+            .children
+            .iter()
+            .flatten()
+            .map(|child| format!("&{}", child.as_string()))
+            .collect();
+        self.constructor(&format!("{:?}", self.word), &children)
+    }
+    // `Node::new` with `word` and `children` as given
+    fn constructor(&self, word: &str, children: &[String]) -> String {
+        let buckets: Vec<_> = self.buckets().map(|bucket| bucket.to_string()).collect();
+        format!(
+            "Node::new({word}, {}, {}, &[{}], &[{}]){}",
+            self.index,
+            self.mask,
+            buckets.join(", "),
+            children.join(", "),
+            match self.suppressed {
+                true => ".suppress()",
+                false => "",
+            }
+        )
+    }
+    // marks the nodes of `words`, see `BkTreeBuilder::suppress`
+    fn suppress(&mut self, words: &BTreeSet<Cow<str>>) {
+        self.suppressed = words.contains(self.word);
+        self.children
+            .iter_mut()
+            .flatten()
+            .for_each(|child| child.suppress(words));
+    }
+    fn buckets(&self) -> impl Iterator<Item = usize> + '_ {
+        let children = self.children.iter().enumerate();
+        children.filter_map(|(bucket, child)| child.as_ref().map(|_| bucket))
+    }
+    // numbers the nodes in the order the reader's `Node::iter` visits them,
+    // preorder with the highest bucket first
+    fn number(&mut self) -> Result<(), WriteError> {
+        let count = self.count();
+        if count as u64 > u32::MAX as u64 + 1 {
+            return Err(WriteError::TooManyWords { count });
+        }
+        self.number_from(&mut 0);
+        Ok(())
+    }
+    fn number_from(&mut self, next: &mut usize) {
+        self.index = *next as u32;
+        *next += 1;
+        self.children
+            .iter_mut()
+            .rev()
+            .flatten()
+            .for_each(|child| child.number_from(next));
+    }
+    fn count(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .flatten()
+            .map(|c| c.count())
+            .sum::<usize>()
+    }
+    // the nodes in index order, already numbered
+    #[cfg(feature = "rkyv")]
+    fn flatten(&self, nodes: &mut Vec<ArchiveNode>) {
+        nodes.push(ArchiveNode {
+            word: self.word.into(),
+            children: self
+                .children
+                .iter()
+                .enumerate()
+                .filter_map(|(bucket, child)| Some((bucket as u8, child.as_ref()?.index)))
+                .collect(),
+        });
+        self.children
+            .iter()
+            .rev()
+            .flatten()
+            .for_each(|child| child.flatten(nodes));
+    }
+    // the storage records in index order, already numbered
+    fn encode(&self, records: &mut Vec<Vec<u8>>) {
+        assert!(
+            self.word.len() <= MAX_STORED_WORD,
+            "{} is too long for the storage blob",
+            self.word
+        );
+        let mut record = vec![self.word.len() as u8];
+        record.extend_from_slice(self.word.as_bytes());
+        let children = self.children.iter().enumerate();
+        let children: Vec<_> = children
+            .filter_map(|(bucket, child)| Some((bucket as u8, child.as_ref()?.index)))
+            .collect();
+        record.push(children.len() as u8);
+        for (bucket, id) in children {
+            record.push(bucket);
+            record.extend_from_slice(&id.to_le_bytes());
+        }
+        records.push(record);
+        self.children
+            .iter()
+            .rev()
+            .flatten()
+            .for_each(|child| child.encode(records));
+    }
+    // `BkTreeBuilder::dfs_order`, the root as `{root}` and the rest as
+    // `{ident}_{index}` in the order `words` visits them, already
+    // numbered. with `shared` the words are in that order instead, as the
+    // start of `{ident}_TIER_WORDS`
+    fn statics(&self, ident: &str, root: &str, shared: Option<&[&str]>, items: Items) -> String {
+        let (words, offsets) = self.interned(shared);
+        let bytes = words.concat();
+        let name = format!("{ident}_WORDS");
+        let ty = format!("[u8; {}]", bytes.len());
+        let literal = format!("b\"{}\"", byte_string(&bytes));
+        let mut string = match (shared, items) {
+            (Some(_), _) => {
+                let value = format!("Node::words_prefix(&{ident}_TIER_WORDS)");
+                items.declare(&name, &ty, &value)
+            }
+            // a byte string is a reference already
+            (None, Items::Consts) => format!("const {name}: &{ty} = {literal};\n"),
+            (None, Items::Statics) => items.declare(&name, &ty, &format!("*{literal}")),
+        };
+        self.push_static(ident, root, &offsets, items, &mut string);
+        string
+    }
+    // the words of `{ident}_WORDS` in order and where each starts
+    fn interned<'s>(
+        &'s self,
+        shared: Option<&[&'s str]>,
+    ) -> (Vec<&'s str>, BTreeMap<&'s str, usize>) {
+        let mut own = Vec::new();
+        self.words(&mut own);
+        let mut words: Vec<&'s str> = own.into_iter().collect();
+        if let Some(shared) = shared {
+            words = shared[..words.len()].to_vec();
+        }
+        let mut offsets = BTreeMap::new();
+        let mut start = 0;
+        for word in &words {
+            offsets.insert(*word, start);
+            start += word.len();
+        }
+        (words, offsets)
+    }
+    fn push_static(
+        &self,
+        ident: &str,
+        name: &str,
+        offsets: &BTreeMap<&str, usize>,
+        items: Items,
+        string: &mut String,
+    ) {
+        let children: Vec<_> = self
+            .children
+            .iter()
+            .flatten()
+            .map(|child| items.reference(&format!("{ident}_{}", child.index)))
+            .collect();
+        let word = format!(
+            "Node::interned({}, {}, {})",
+            items.reference(&format!("{ident}_WORDS")),
+            offsets[self.word],
+            self.word.len()
+        );
+        let node = self.constructor(&word, &children);
+        string.push_str(&items.declare(name, "Node", &node));
+        for child in self.children.iter().flatten() {
+            let name = format!("{ident}_{}", child.index);
+            child.push_static(ident, &name, offsets, items, string);
+        }
+    }
+    // the node holding `word`
+    #[cfg(feature = "inflect")]
+    fn find(&self, word: &str) -> Option<&Self> {
+        match distance(self.word, word) {
+            0 => Some(self),
+            diff => self.children.get(diff)?.as_ref()?.find(word),
+        }
+    }
+    // the words in index order, already numbered
+    fn indexed(&self, words: &mut Vec<&'a str>) {
+        words.push(self.word);
+        self.children
+            .iter()
+            .rev()
+            .flatten()
+            .for_each(|child| child.indexed(words));
+    }
+    fn words(&self, words: &mut Vec<&'a str>) {
+        words.push(self.word);
+        self.children
+            .iter()
+            .flatten()
+            .for_each(|child| child.words(words));
+    }
+}
+
+/// why a [`BkTreeBuilder`] couldn't build its tree, what `build` and the
+/// other outputs panic with
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteError {
+    /// no words at all, for a hamming tree rooted at the first
+    Empty,
+    /// `the` isn't in the word list, every levenshtein tree is rooted at it
+    MissingRoot,
+    EmptyWord,
+    /// a word starting or ending with whitespace or holding a line break,
+    /// spaces inside a phrase are fine
+    Whitespace {
+        word: String,
+    },
+    /// more words than a `u32` node index numbers, or with `word_table`
+    /// than a `u16` index into it
+    TooManyWords {
+        count: usize,
+    },
+    /// longer than `MAX_STORED_WORD` bytes, for the storage blob and
+    /// `progmem`
+    WordTooLong {
+        word: String,
+    },
+    /// a hamming code of `len` chars where the first is `expected`
+    CodeLength {
+        word: String,
+        len: usize,
+        expected: usize,
+    },
+    /// hamming codes past the last bucket
+    CodeTooLong {
+        len: usize,
+    },
+    /// `option` doesn't go with the option or output `with`
+    Incompatible {
+        option: &'static str,
+        with: &'static str,
+    },
+    /// a [`BkTreeBuilder::source_file`] couldn't be read
+    Read {
+        path: PathBuf,
+        kind: ErrorKind,
+    },
+    /// the generated tree couldn't be written
+    Write {
+        path: PathBuf,
+        kind: ErrorKind,
+    },
+    /// `write(None)` outside a build script
+    NoOutDir,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "word_list is empty"),
+            Self::MissingRoot => write!(f, "{ROOT_WORD} was not found in word_list"),
+            Self::EmptyWord => write!(f, "word_list contains an empty word"),
+            Self::Whitespace { word } => {
+                write!(
+                    f,
+                    "{word:?} starts or ends with whitespace or holds a line break"
+                )
+            }
+            Self::TooManyWords { count } if *count as u64 <= u32::MAX as u64 + 1 => {
+                write!(
+                    f,
+                    "{count} words are too many for the word table's u16 indexes"
+                )
+            }
+            Self::TooManyWords { count } => {
+                write!(f, "{count} words are too many for u32 node indexes")
+            }
+            Self::WordTooLong { word } => write!(
+                f,
+                "{word} is too long for the storage blob, at most {MAX_STORED_WORD} bytes fit"
+            ),
+            Self::CodeLength {
+                word,
+                len,
+                expected,
+            } => write!(
+                f,
+                "hamming codes differ in length, {word:?} is {len} chars and the first {expected}"
+            ),
+            Self::CodeTooLong { len } => write!(
+                f,
+                "hamming codes are at most {} chars, these are {len}",
+                CHILDREN_LENGTH - 1
+            ),
+            Self::Incompatible { option, with } => write!(f, "`{option}` doesn't go with `{with}`"),
+            Self::Read { path, kind } => write!(f, "can't read {}: {kind}", path.display()),
+            Self::Write { path, kind } => write!(f, "can't write {}: {kind}", path.display()),
+            Self::NoOutDir => write!(f, "OUT_DIR isn't set, `write(None)` is for build scripts"),
+        }
+    }
+}
+
+std_error!(WriteError);
+
+/// configures how the tree is generated, [`write_bktree`] is the same as
+/// `BkTreeBuilder::new(words).write(path)`
+///
+/// the generated file always holds `TREE`, a static unless
+/// [`BkTreeBuilder::const_items`], along with
+/// `const WORD_COUNT: usize`, `const CHECKSUM: u32` and the size constants
+/// `NODE_BYTES` and `STRING_BYTES` matching `Node::memory_usage`. every
+/// node gets its `Node::iter` position as its u32 `index`
+///
+/// a word can be a phrase like `git cherry-pick`, a space in it is a char
+/// like any other and searches take the whole query as one, so
+/// `git chery-pick` is 1 away. the word at a time helpers split or refuse
+/// a phrase, `StreamCorrector` ends a word at a space and
+/// `QueryLimits::WORD` turns spaces away, so a tree of phrases goes with
+/// the plain searches and `QueryLimits::PHRASE`
+#[derive(Debug, Clone)]
+pub struct BkTreeBuilder<'a> {
+    // the words of `new` first
+    sources: Vec<Source<'a>>,
+    static_wrapper: bool,
+    dfs_order: bool,
+    const_items: bool,
+    progmem: bool,
+    word_table: bool,
+    length_stats: bool,
+    // the false positive rate of the filter
+    bloom: Option<f64>,
+    suppressed: Vec<&'a str>,
+    #[cfg(feature = "hamming")]
+    hamming: bool,
+    #[cfg(feature = "phonetic")]
+    phonetic: bool,
+    #[cfg(feature = "inflect")]
+    inflections: Vec<InflectionRule>,
+    #[cfg(feature = "confusables")]
+    fold_confusables: bool,
+    // the feature each is behind
+    tiers: Vec<(&'a str, Tier<'a>)>,
+    // the first `source_file` that couldn't be read
+    unreadable: Option<WriteError>,
+    shards: Shards,
+}
+
+// how many files `write` splits the node statics over
+#[derive(Debug, Clone, Copy)]
+enum Shards {
+    One,
+    // one per `SHARD_NODES`
+    Auto,
+    Count(usize),
+}
+
+/// nodes per file of a [`BkTreeBuilder::sharded`] tree, about 4mb of source
+pub const SHARD_NODES: usize = 50_000;
+
+// the words of a tier
+#[derive(Debug, Clone)]
+enum Tier<'a> {
+    Top(usize),
+    Words(Vec<&'a str>),
+}
+
+// a word list, most frequent first
+#[derive(Debug, Clone)]
+struct Source<'a> {
+    words: Vec<Cow<'a, str>>,
+    weight: u32,
+}
+
+/// how the sources of a [`BkTreeBuilder`] were merged
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildStats {
+    /// words in the merged list, without inflections
+    pub words: usize,
+    /// one per source in the order they were registered, the words passed
+    /// to `new` first
+    pub sources: Vec<SourceStats>,
+    /// the size of the bit array of the [`BkTreeBuilder::bloom_filter`]
+    /// over the whole word list, 0 without one
+    pub bloom_bytes: usize,
+    /// the words of [`BkTreeBuilder::suppress`] that aren't in the word
+    /// list, in the order they were given
+    pub missing_suppressed: Vec<String>,
+    /// words left out of the tree, `CHILDREN_LENGTH` or more edits from
+    /// the node they'd go under so no bucket holds them. a long phrase
+    /// below a short word mostly
+    pub unplaced: Vec<String>,
+}
+
+/// a source's share of [`BuildStats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceStats {
+    pub weight: u32,
+    /// distinct words in the source
+    pub words: usize,
+    /// the ones ranked by this source, the rest ranked higher in another
+    pub kept: usize,
+}
+
+// the words a tree is built from
+struct Words<'b> {
+    list: Vec<Cow<'b, str>>,
+    // the generated variants and where their base is in `list`, in the
+    // order they're added
+    #[cfg(feature = "inflect")]
+    variants: Vec<(String, usize)>,
+    #[cfg(feature = "hamming")]
+    hamming: bool,
+    // the ones of `BkTreeBuilder::suppress`, folded like `list`
+    suppressed: BTreeSet<Cow<'b, str>>,
+}
+
+impl Words<'_> {
+    fn tree(&self) -> Node<'_> {
+        let mut tree = self.unmarked_tree();
+        tree.suppress(&self.suppressed);
+        tree
+    }
+
+    // `tree` before the suppressed words are marked
+    fn unmarked_tree(&self) -> Node<'_> {
+        self.grown().0
+    }
+
+    // the tree and the words with no bucket in it
+    fn grown(&self) -> (Node<'_>, Vec<&str>) {
+        #[cfg(feature = "hamming")]
+        if self.hamming {
+            return self.hamming_tree();
+        }
+        #[allow(unused_mut)]
+        let mut words: Vec<&str> = self.list.iter().map(|w| w.as_ref()).collect();
+        #[cfg(feature = "inflect")]
+        words.extend(self.variants.iter().map(|(variant, _)| variant.as_str()));
+        grow(ROOT_WORD, words)
+    }
+
+    // what `tree` needs of the words, and `stored` that they fit a node of
+    // the storage blob
+    fn check(&self, stored: bool) -> Result<(), WriteError> {
+        #[cfg(feature = "hamming")]
+        if self.hamming {
+            self.check_codes()?;
+        }
+        #[cfg(feature = "hamming")]
+        let rooted = self.hamming;
+        #[cfg(not(feature = "hamming"))]
+        let rooted = false;
+        if !rooted && !self.list.iter().any(|w| w == ROOT_WORD) {
+            return Err(WriteError::MissingRoot);
+        }
+        // an empty word would be matched by every short query
+        if self.list.iter().any(|w| w.is_empty()) {
+            return Err(WriteError::EmptyWord);
+        }
+        // phrases are fine, a word that doesn't survive a trim or a line
+        // split is a mistake
+        let untidy = |w: &&Cow<str>| w.trim() != w.as_ref() || w.contains(['\n', '\r']);
+        if let Some(word) = self.list.iter().find(untidy) {
+            return Err(WriteError::Whitespace {
+                word: word.to_string(),
+            });
+        }
+        #[allow(unused_mut)]
+        let mut words: BTreeSet<&str> = self.list.iter().map(|w| w.as_ref()).collect();
+        #[cfg(feature = "inflect")]
+        words.extend(self.variants.iter().map(|(variant, _)| variant.as_str()));
+        if words.len() as u64 > u32::MAX as u64 + 1 {
+            return Err(WriteError::TooManyWords { count: words.len() });
+        }
+        match words.iter().find(|w| stored && w.len() > MAX_STORED_WORD) {
+            Some(word) => Err(WriteError::WordTooLong {
+                word: word.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // the words no bucket holds, see `grow`
+    fn unplaced(&self) -> Vec<&str> {
+        self.grown().1
+    }
+}
+
+#[cfg(feature = "hamming")]
+impl Words<'_> {
+    fn check_codes(&self) -> Result<(), WriteError> {
+        let first = self.list.first().ok_or(WriteError::Empty)?;
+        let expected = first.chars().count();
+        for word in &self.list {
+            let len = word.chars().count();
+            if len != expected {
+                return Err(WriteError::CodeLength {
+                    word: word.to_string(),
+                    len,
+                    expected,
+                });
+            }
+        }
+        // any two codes have to fit a bucket
+        match expected < CHILDREN_LENGTH {
+            true => Ok(()),
+            false => Err(WriteError::CodeTooLong { len: expected }),
+        }
+    }
+
+    // rooted at the first code, there's no "the" among them. the codes
+    // went through `check_codes`
+    fn hamming_tree(&self) -> (Node<'_>, Vec<&str>) {
+        let words = self.list.iter().map(|w| w.as_ref());
+        grow_by(&self.list[0], words, |a, b| hamming(a, b).unwrap())
+    }
+}
+
+impl<'a> BkTreeBuilder<'a> {
+    pub fn new(words: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            sources: vec![Source {
+                words: words.into_iter().map(Cow::Borrowed).collect(),
+                weight: 0,
+            }],
+            static_wrapper: false,
+            const_items: false,
+            dfs_order: false,
+            progmem: false,
+            word_table: false,
+            length_stats: false,
+            bloom: None,
+            suppressed: Vec::new(),
+            #[cfg(feature = "hamming")]
+            hamming: false,
+            #[cfg(feature = "phonetic")]
+            phonetic: false,
+            #[cfg(feature = "inflect")]
+            inflections: Vec::new(),
+            #[cfg(feature = "confusables")]
+            fold_confusables: false,
+            tiers: Vec::new(),
+            unreadable: None,
+            shards: Shards::One,
+        }
+    }
+
+    /// also take the words of `words`, most frequent first. the lists are
+    /// merged by weight, highest first, with the words passed to `new`
+    /// at weight 0, and lists of equal weight interleave by position and
+    /// then alphabetically, so registering them in another order builds
+    /// the same tree. a word in several lists keeps its best rank
+    pub fn source(mut self, words: impl IntoIterator<Item = &'a str>, weight: u32) -> Self {
+        let words = words.into_iter().map(Cow::Borrowed).collect();
+        self.sources.push(Source { words, weight });
+        self
+    }
+
+    /// [`BkTreeBuilder::source`] reading `path`, one word per line with
+    /// blank lines skipped. a file that can't be read is the builder's
+    /// [`WriteError::Read`]
+    pub fn source_file(mut self, path: impl AsRef<Path>, weight: u32) -> Self {
+        let path = path.as_ref();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.unreadable.get_or_insert(WriteError::Read {
+                    path: path.into(),
+                    kind: e.kind(),
+                });
+                return self;
+            }
+        };
+        let words = contents
+            .lines()
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(|w| Cow::Owned(w.into()))
+            .collect();
+        self.sources.push(Source { words, weight });
+        self
+    }
+
+    /// the words the tree is built from in the order they go in, the
+    /// sources merged and without inflections
+    pub fn word_list(&self) -> Vec<&str> {
+        self.merge().into_iter().map(|(word, _)| word).collect()
+    }
+
+    /// the words of each source and how many it ranked
+    pub fn stats(&self) -> BuildStats {
+        let merged = self.merge();
+        let mut sources: Vec<_> = self
+            .sources
+            .iter()
+            .map(|source| SourceStats {
+                weight: source.weight,
+                words: source.words.iter().collect::<BTreeSet<_>>().len(),
+                kept: 0,
+            })
+            .collect();
+        merged.iter().for_each(|&(_, at)| sources[at].kept += 1);
+        let bloom_bytes = self.bloom.map_or(0, |rate| {
+            let (list, mut words) = (self.words(), Vec::new());
+            list.tree().words(&mut words);
+            bloom_size(words.len(), rate).0 * 4
+        });
+        BuildStats {
+            words: merged.len(),
+            sources,
+            bloom_bytes,
+            missing_suppressed: self.missing_suppressed(),
+            unplaced: self
+                .words()
+                .unplaced()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    // see `BuildStats::missing_suppressed`
+    fn missing_suppressed(&self) -> Vec<String> {
+        if self.suppressed.is_empty() {
+            return Vec::new();
+        }
+        let (list, mut words) = (self.words(), Vec::new());
+        let tree = list.unmarked_tree();
+        tree.words(&mut words);
+        let words: BTreeSet<_> = words.into_iter().collect();
+        self.suppressed
+            .iter()
+            .filter(|word| !words.contains(self.folded(word).as_ref()))
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    // every distinct word ranked, with the source it's ranked by. on equal
+    // rank the source registered first has it
+    fn merge(&self) -> Vec<(&str, usize)> {
+        let mut best = BTreeMap::new();
+        for (at, source) in self.sources.iter().enumerate() {
+            for (position, word) in source.words.iter().enumerate() {
+                let rank = (Reverse(source.weight), position);
+                let entry = best.entry(word.as_ref()).or_insert((rank, at));
+                if rank < entry.0 {
+                    *entry = (rank, at);
+                }
+            }
+        }
+        let mut ranked: Vec<_> = best
+            .into_iter()
+            .map(|(word, (rank, at))| (rank, word, at))
+            .collect();
+        ranked.sort_unstable();
+        ranked.into_iter().map(|(_, word, at)| (word, at)).collect()
+    }
+
+    fn words(&self) -> Words<'_> {
+        self.words_of(self.word_list())
+    }
+
+    // `word` as it goes in the tree, see `fold_confusables`
+    fn folded<'b>(&self, word: &'b str) -> Cow<'b, str> {
+        #[cfg(feature = "confusables")]
+        if self.fold_confusables && word.chars().any(|c| fold_confusable(c) != c) {
+            return Cow::Owned(fold_confusables_string(word));
+        }
+        Cow::Borrowed(word)
+    }
+
+    fn words_of<'b>(&self, list: Vec<&'b str>) -> Words<'b>
+    where
+        'a: 'b,
+    {
+        let list: Vec<_> = list.into_iter().map(|w| self.folded(w)).collect();
+        #[cfg(feature = "inflect")]
+        let variants = {
+            let words: BTreeSet<_> = list.iter().map(|w| w.as_ref()).collect();
+            let mut seen = BTreeSet::new();
+            let mut variants = Vec::new();
+            for (at, base) in list.iter().enumerate() {
+                for rule in &self.inflections {
+                    let Some(variant) = inflect(base, rule) else {
+                        continue;
+                    };
+                    if !words.contains(variant.as_str()) && seen.insert(variant.clone()) {
+                        variants.push((variant, at));
+                    }
+                }
+            }
+            variants
+        };
+        Words {
+            list,
+            #[cfg(feature = "inflect")]
+            variants,
+            #[cfg(feature = "hamming")]
+            hamming: self.hamming,
+            suppressed: self.suppressed.iter().map(|w| self.folded(w)).collect(),
+        }
+    }
+
+    /// emit `static TREE: StaticBkTree` wrapping the root node (as
+    /// `TREE_ROOT`) instead of a bare `static TREE: Node`. the including
+    /// module needs `StaticBkTree` in scope
+    pub fn static_wrapper(mut self, static_wrapper: bool) -> Self {
+        self.static_wrapper = static_wrapper;
+        self
+    }
+
+    /// emit every node as its own static, `TREE_{index}`, laid out depth
+    /// first with buckets ascending, the order searches visit them, so a
+    /// search mostly reads flash sequentially. the words go in one
+    /// `TREE_WORDS` byte string in the same order. the tree and its search
+    /// results are unchanged
+    pub fn dfs_order(mut self, dfs_order: bool) -> Self {
+        self.dfs_order = dfs_order;
+        self
+    }
+
+    /// emit the items of the tree as consts instead of statics, so it can
+    /// go in another const, `const CONFIG: Config = Config { tree: TREE }`,
+    /// or be read by a const fn. each node is a `const {name}: &Node`,
+    /// `TREE` too, pointing at the one promoted node, and `dfs_order`'s
+    /// `TREE_WORDS` is a `&[u8; _]` the same way. a const of the node
+    /// itself would copy it into every place it's used, as would a const
+    /// of your own holding `*TREE`, so pass the references on as they
+    /// are. the other outputs stay statics. statics are the
+    /// default since they're in the binary once however they're used, and
+    /// `progmem` needs one in its link section
+    pub fn const_items(mut self, const_items: bool) -> Self {
+        self.const_items = const_items;
+        self
+    }
+
+    /// have `write` split the node statics over files next to its own,
+    /// `tree_part_0.rs` and on for `tree.rs`, which `include!`s them, so
+    /// rustc can spread a huge dictionary over its threads. there's a part
+    /// per [`SHARD_NODES`] nodes, see `shards` to pick the count. it's
+    /// `dfs_order`'s layout whether that's on or not, and the items of the
+    /// tree keep their names. `build` isn't sharded
+    pub fn sharded(mut self, sharded: bool) -> Self {
+        self.shards = match sharded {
+            true => Shards::Auto,
+            false => Shards::One,
+        };
+        self
+    }
+
+    /// [`BkTreeBuilder::sharded`] over `shards` parts, or as many as there
+    /// are nodes when that's fewer
+    pub fn shards(mut self, shards: usize) -> Self {
+        self.shards = Shards::Count(shards.max(1));
+        self
+    }
+
+    /// emit the tree as the storage blob in avr program memory,
+    /// `static TREE_BLOB: [u8; _]` in `.progmem.data` on avr and
+    /// `static TREE: ProgmemBlob` pointing at it, instead of nodes. the
+    /// constants stay, `NODE_BYTES` and `STRING_BYTES` become
+    /// `BLOB_BYTES`, and `static_wrapper` is ignored. the including module
+    /// needs `ProgmemBlob` and `Node` in scope
+    pub fn progmem(mut self, progmem: bool) -> Self {
+        self.progmem = progmem;
+        self
+    }
+
+    /// build the tree by hamming distance, the chars two words differ at,
+    /// for fixed length codes that only ever get a char wrong. every word
+    /// has to be the same length, the build panics otherwise, and the first
+    /// is the root. `TREE` is then a [`HammingTree`], what `static_wrapper`
+    /// gives but searching only by hamming, so the tree can't be searched
+    /// by levenshtein by mistake. `progmem`, `build_storage` and
+    /// `build_archive` don't take it. the including module needs
+    /// `HammingTree` in scope
+    ///
+    /// [`HammingTree`]: crate::HammingTree
+    #[cfg(feature = "hamming")]
+    pub fn hamming(mut self, hamming: bool) -> Self {
+        self.hamming = hamming;
+        self
+    }
+
+    /// also emit `const LENGTH_STATS: LengthStats`, what
+    /// `Node::length_stats` would work out at runtime. the including
+    /// module needs `LengthStats` in scope
+    pub fn length_stats(mut self, length_stats: bool) -> Self {
+        self.length_stats = length_stats;
+        self
+    }
+
+    /// also emit `static WORDS: WordTable` of every word at its
+    /// `Node::index`, so searches can hand out the `u16` indexes of
+    /// `Node::for_each_indexed` and the words be looked up after, a
+    /// `TooManyWords` error past 65536 words. with
+    /// `dfs_order` the entries point into `TREE_WORDS` rather than
+    /// repeating the words. the including module needs `WordTable` in
+    /// scope, and `Node` with `dfs_order`
+    pub fn word_table(mut self, word_table: bool) -> Self {
+        self.word_table = word_table;
+        self
+    }
+
+    /// also emit `static BLOOM: BloomFilter` of every word, sized so about
+    /// `false_positive_rate` of the words that aren't in the tree get past
+    /// it, for `Node::contains_fast`. the rate is kept between 0.000001
+    /// and 0.5. it's 2 or 3 hashes, whichever takes fewer bits, and about
+    /// 5 bytes a word at 1%. with `progmem` it's still a plain static, in
+    /// ram on avr. the including module needs `BloomFilter` in scope
+    pub fn bloom_filter(mut self, false_positive_rate: f64) -> Self {
+        self.bloom = Some(false_positive_rate.clamp(0.000_001, 0.5));
+        self
+    }
+
+    /// mark the nodes of `words` with `Node::suppress`, so no search hands
+    /// them out while `contains` still finds them, for words the
+    /// dictionary has to know but must never suggest. a word that isn't
+    /// in the word list is skipped, `write` prints a cargo warning for it
+    /// and `stats` lists it. only the generated source carries the mark,
+    /// the archive, the storage blob and `progmem` hold them as ordinary
+    /// words. with `phonetic_index` they're left out of `PHONETIC`
+    pub fn suppress(mut self, words: impl IntoIterator<Item = &'a str>) -> Self {
+        self.suppressed.extend(words);
+        self
+    }
+
+    /// also emit `static PHONETIC: PhoneticIndex` of every word under its
+    /// `phonetic_code`, for `Node::phonetic_corrections`. the including
+    /// module needs `PhoneticIndex` in scope
+    #[cfg(feature = "phonetic")]
+    pub fn phonetic_index(mut self, phonetic: bool) -> Self {
+        self.phonetic = phonetic;
+        self
+    }
+
+    /// also add every word with each of `rules` that fits it applied, see
+    /// [`ENGLISH_INFLECTIONS`], and emit `static INFLECTIONS: Inflections`
+    /// mapping the variants to their base. they go in after the word list
+    /// so they never push a word of it further down, and a variant that's
+    /// already in the list stays that word. the including module needs
+    /// `Inflections` in scope
+    ///
+    /// [`ENGLISH_INFLECTIONS`]: crate::ENGLISH_INFLECTIONS
+    #[cfg(feature = "inflect")]
+    pub fn inflections(mut self, rules: &[InflectionRule]) -> Self {
+        self.inflections = rules.to_vec();
+        self
+    }
+
+    /// fold every word with `fold_confusable` before it goes in, so a
+    /// list pasted together from the web holds "car" where it had a
+    /// cyrillic "а". queries are folded separately, with
+    /// `fold_confusables`, whether or not the dictionary was
+    #[cfg(feature = "confusables")]
+    pub fn fold_confusables(mut self, fold: bool) -> Self {
+        self.fold_confusables = fold;
+        self
+    }
+
+    /// emit the tree of the first `top` words of the word list behind
+    /// `#[cfg(feature = "{feature}")]`, every item of it, instead of the
+    /// whole list, so the including crate picks a dictionary with its own
+    /// features. each tier's items have the usual names, `TREE` and the
+    /// rest. with several of the features on the last tier registered
+    /// wins, with none the build fails. it's only `build` and `write`,
+    /// the other outputs are the whole list
+    ///
+    /// with `dfs_order` a tier holding every word of the ones before it
+    /// takes their words from the start of one `TREE_TIER_WORDS` const,
+    /// so tiers smallest first share all their words. only the selected
+    /// tier's `TREE_WORDS` ends up in the binary
+    pub fn tier(mut self, feature: &'a str, top: usize) -> Self {
+        self.tiers.push((feature, Tier::Top(top)));
+        self
+    }
+
+    /// [`BkTreeBuilder::tier`] with its own word list instead of the start
+    /// of the builder's, sources aren't merged into it
+    pub fn tier_words(
+        mut self,
+        feature: &'a str,
+        words: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        self.tiers
+            .push((feature, Tier::Words(words.into_iter().collect())));
+        self
+    }
+
+    /// the generated rust source, panics with the error of
+    /// [`BkTreeBuilder::try_build`]
+    pub fn build(&self) -> String {
+        self.try_build().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// the generated rust source
+    pub fn try_build(&self) -> Result<String, WriteError> {
+        self.check_options()?;
+        #[cfg(feature = "hamming")]
+        if self.hamming && self.progmem {
+            return Err(WriteError::Incompatible {
+                option: "hamming",
+                with: "progmem",
+            });
+        }
+        if self.const_items && self.progmem {
+            return Err(WriteError::Incompatible {
+                option: "const_items",
+                with: "progmem",
+            });
+        }
+        if !self.tiers.is_empty() {
+            return self.build_tiers();
+        }
+        let words = self.words();
+        words.check(self.progmem)?;
+        self.build_words(&words, None)
+    }
+
+    // what every output checks first
+    fn check_options(&self) -> Result<(), WriteError> {
+        match &self.unreadable {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    // every tier behind its features, see `tier`
+    fn build_tiers(&self) -> Result<String, WriteError> {
+        let list = self.word_list();
+        let tiers: Vec<_> = self
+            .tiers
+            .iter()
+            .map(|(_, tier)| match tier {
+                Tier::Top(top) => self.words_of(list[..list.len().min(*top)].to_vec()),
+                Tier::Words(words) => self.words_of(words.clone()),
+            })
+            .collect();
+        for words in &tiers {
+            words.check(self.progmem)?;
+        }
+        // one tier after another, as long as each holds all the words before
+        let mut shared: Vec<&str> = Vec::new();
+        let mut shares = Vec::new();
+        for words in &tiers {
+            let mut tree = words.tree();
+            tree.number()?;
+            let mut order = Vec::new();
+            tree.words(&mut order);
+            let known: BTreeSet<_> = shared.iter().copied().collect();
+            let holds = order.iter().filter(|w| known.contains(*w)).count() == known.len();
+            let share = self.dfs_order && !self.progmem && holds;
+            if share {
+                shared.extend(order.into_iter().filter(|w| !known.contains(w)));
+            }
+            shares.push(share);
+        }
+        let features: Vec<_> = self
+            .tiers
+            .iter()
+            .map(|(feature, _)| format!("feature = {feature:?}"))
+            .collect();
+        let any = format!("any({})", features.join(", "));
+        let names: Vec<_> = self.tiers.iter().map(|(feature, _)| *feature).collect();
+        let mut string = format!(
+            "#[cfg(not({any}))]\ncompile_error!({:?});\n",
+            format!(
+                "the dictionary needs one of the features {}",
+                names.join(", ")
+            )
+        );
+        if shares.contains(&true) {
+            let bytes = shared.concat();
+            string.push_str(&format!(
+                "#[cfg({any})]\n#[allow(dead_code)]\nconst TREE_TIER_WORDS: [u8; {}] = *b\"{}\";\n",
+                bytes.len(),
+                byte_string(&bytes)
+            ));
+        }
+        for (at, words) in tiers.iter().enumerate() {
+            let cfg = match &features[at + 1..] {
+                [] => features[at].clone(),
+                later => format!("all({}, not(any({})))", features[at], later.join(", ")),
+            };
+            let source = self.build_words(words, shares[at].then_some(&shared[..]))?;
+            string.push_str(&cfg_items(&source, &cfg));
+        }
+        Ok(string)
+    }
+
+    // the source for one tree
+    fn build_words(&self, words: &Words, shared: Option<&[&str]>) -> Result<String, WriteError> {
+        #[allow(unused_mut)]
+        let mut wrapper = self.static_wrapper.then_some("StaticBkTree");
+        #[cfg(feature = "hamming")]
+        if self.hamming {
+            wrapper = Some("HammingTree");
+        }
+        let items = match self.const_items {
+            true => Items::Consts,
+            false => Items::Statics,
+        };
+        #[allow(unused_mut)]
+        let mut string = match self.progmem {
+            true => progmem_codegen(&mut words.tree(), "TREE")?,
+            false => {
+                let tree = &mut words.tree();
+                codegen(tree, "TREE", wrapper, self.dfs_order, shared, items)?
+            }
+        };
+        if self.length_stats {
+            string.push_str(&length_stats_codegen(&words.tree()));
+        }
+        if let Some(rate) = self.bloom {
+            string.push_str(&bloom_codegen(&words.tree(), rate));
+        }
+        if self.word_table {
+            let mut tree = words.tree();
+            tree.number()?;
+            let count = tree.count();
+            if count > u16::MAX as usize + 1 {
+                return Err(WriteError::TooManyWords { count });
+            }
+            let offsets = (self.dfs_order && !self.progmem).then(|| tree.interned(shared).1);
+            string.push_str(&word_table_codegen(&tree, offsets.as_ref(), items));
+        }
+        #[cfg(feature = "phonetic")]
+        if self.phonetic {
+            string.push_str(&phonetic_codegen(&words.tree(), &words.suppressed));
+        }
+        #[cfg(feature = "inflect")]
+        if !words.variants.is_empty() {
+            string.push_str(&inflection_codegen(&mut words.tree(), words)?);
+        }
+        Ok(string)
+    }
+
+    /// the tree as an rkyv archive for [`ArchivedBkTree`], which has to be
+    /// loaded from 4 byte aligned memory. it's the same tree `build` writes
+    /// as source. panics with the error of `try_build_archive`
+    ///
+    /// [`ArchivedBkTree`]: crate::ArchivedBkTree
+    #[cfg(feature = "rkyv")]
+    pub fn build_archive(&self) -> Vec<u8> {
+        self.try_build_archive().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// see [`BkTreeBuilder::build_archive`]
+    #[cfg(feature = "rkyv")]
+    pub fn try_build_archive(&self) -> Result<Vec<u8>, WriteError> {
+        let words = self.levenshtein_words("build_archive", false)?;
+        let mut tree = words.tree();
+        tree.number()?;
+        let mut nodes = Vec::new();
+        tree.flatten(&mut nodes);
+        let archive = TreeArchive { nodes };
+        // plain strings and numbers, serializing them can't fail
+        Ok(rkyv::to_bytes::<rkyv::rancor::Error>(&archive)
+            .unwrap()
+            .into_vec())
+    }
+
+    /// the tree as the blob [`BlobStorage`] reads, for trees kept in
+    /// external flash or on an sd card. node ids are the generated tree's
+    /// `Node::index` and the root is node 0. panics with the error of
+    /// `try_build_storage`
+    ///
+    /// [`BlobStorage`]: crate::BlobStorage
+    pub fn build_storage(&self) -> Vec<u8> {
+        self.try_build_storage().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// see [`BkTreeBuilder::build_storage`]
+    pub fn try_build_storage(&self) -> Result<Vec<u8>, WriteError> {
+        let words = self.levenshtein_words("build_storage", true)?;
+        storage_blob(&mut words.tree())
+    }
+
+    // the checked words of the outputs searched by levenshtein
+    #[allow(unused_variables)]
+    fn levenshtein_words(
+        &self,
+        output: &'static str,
+        stored: bool,
+    ) -> Result<Words<'_>, WriteError> {
+        self.check_options()?;
+        #[cfg(feature = "hamming")]
+        if self.hamming {
+            return Err(WriteError::Incompatible {
+                option: "hamming",
+                with: output,
+            });
+        }
+        let words = self.words();
+        words.check(stored)?;
+        Ok(words)
+    }
+
+    /// write the tree to `file_path`, or `OUT_DIR/tree.rs` when `None`,
+    /// panics with the error of [`BkTreeBuilder::try_write`]
+    pub fn write(&self, file_path: Option<PathBuf>) {
+        self.try_write(file_path).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// see [`BkTreeBuilder::write`]
+    pub fn try_write(&self, file_path: Option<PathBuf>) -> Result<(), WriteError> {
+        // write the tree to cargo out's directory
+        let path = match file_path {
+            Some(path) => path,
+            None => Path::new(&var("OUT_DIR").map_err(|_| WriteError::NoOutDir)?).join("tree.rs"),
+        };
+        let files = match self.shards {
+            Shards::One => vec![(path, self.try_build()?)],
+            _ => self.build_shards(path)?,
+        };
+        for word in self.missing_suppressed() {
+            println!("cargo:warning=suppressed word {word:?} isn't in the word list");
+        }
+        for word in self.words().unplaced() {
+            println!("cargo:warning={word:?} has no bucket and is left out of the tree");
+        }
+        for (path, source) in files {
+            fs::write(&path, source).map_err(|e: io::Error| WriteError::Write {
+                path,
+                kind: e.kind(),
+            })?;
+        }
+        Ok(())
+    }
+
+    // the files of `sharded`, the one at `path` first. the node statics are
+    // the lines of `TREE_{index}`, the parts take a run of them each
+    fn build_shards(&self, path: PathBuf) -> Result<Vec<(PathBuf, String)>, WriteError> {
+        let incompatible = |with| WriteError::Incompatible {
+            option: "sharded",
+            with,
+        };
+        if self.progmem {
+            return Err(incompatible("progmem"));
+        }
+        if !self.tiers.is_empty() {
+            return Err(incompatible("tier"));
+        }
+        let builder = Self {
+            dfs_order: true,
+            shards: Shards::One,
+            ..self.clone()
+        };
+        let source = builder.try_build()?;
+        let (nodes, rest): (Vec<_>, Vec<_>) = source.lines().partition(|line| {
+            let name = line.strip_prefix("static TREE_");
+            let name = name.or(line.strip_prefix("const TREE_")).unwrap_or("");
+            name.starts_with(|c: char| c.is_ascii_digit())
+        });
+        let parts = match self.shards {
+            Shards::Count(count) => count,
+            _ => nodes.len().div_ceil(SHARD_NODES),
+        };
+        let per_part = nodes.len().div_ceil(parts.max(1)).max(1);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map_or(String::new(), |e| format!(".{}", e.to_string_lossy()));
+        let mut root = rest.join("\n") + "\n";
+        let mut files = Vec::new();
+        for (i, part) in nodes.chunks(per_part).enumerate() {
+            let name = format!("{stem}_part_{i}{extension}");
+            root.push_str(&format!("include!({name:?});\n"));
+            files.push((path.with_file_name(name), part.join("\n") + "\n"));
+        }
+        files.insert(0, (path, root));
+        Ok(files)
+    }
+}
+
+/// `root` with `words` added in order, the tree every output is written
+/// from, and the words that got no bucket, `CHILDREN_LENGTH` or more edits
+/// from the node they'd go under. those are left out and it's up to the
+/// caller to report them
+pub(crate) fn grow<'a>(
+    root: &'a str,
+    words: impl IntoIterator<Item = &'a str>,
+) -> (Node<'a>, Vec<&'a str>) {
+    grow_by(root, words, distance)
+}
+
+// `grow` with another metric
+fn grow_by<'a>(
+    root: &'a str,
+    words: impl IntoIterator<Item = &'a str>,
+    metric: fn(&str, &str) -> usize,
+) -> (Node<'a>, Vec<&'a str>) {
+    let mut tree = Node::new(root);
+    let mut unplaced: Vec<_> = words
+        .into_iter()
+        .filter(|w| !tree.add_by(w, metric))
+        .collect();
+    // `add_by` turns down a word that's already there too
+    let mut placed = Vec::new();
+    tree.words(&mut placed);
+    let placed: BTreeSet<_> = placed.into_iter().collect();
+    unplaced.retain(|w| !placed.contains(w));
+    (tree, unplaced)
+}
+
+// the blob `BlobStorage` reads, numbering `tree`
+fn storage_blob(tree: &mut Node) -> Result<Vec<u8>, WriteError> {
+    tree.number()?;
+    let mut records = Vec::new();
+    tree.encode(&mut records);
+    let mut blob = Vec::new();
+    blob.extend_from_slice(&(records.len() as u32).to_le_bytes());
+    let mut offset = 4 + 4 * (records.len() as u32 + 1);
+    for record in &records {
+        blob.extend_from_slice(&offset.to_le_bytes());
+        offset += record.len() as u32;
+    }
+    // where the last record ends
+    blob.extend_from_slice(&offset.to_le_bytes());
+    records.iter().for_each(|r| blob.extend_from_slice(r));
+    Ok(blob)
+}
+
+/// the generated source for `tree` as the item `{ident}`, along with
+/// [`progmem_codegen`] the only places the tree's file format is written.
+/// with a `wrapper` type the root is `{ident}_ROOT` and `{ident}` the
+/// wrapper around it.
+///
+/// nodes aren't shared between parents. a tree holds each word once and
+/// every node its own `index`, so no two nodes or subtrees are ever the
+/// same and there's nothing to emit once for both
+pub(crate) fn codegen(
+    tree: &mut Node,
+    ident: &str,
+    wrapper: Option<&str>,
+    dfs_order: bool,
+    shared: Option<&[&str]>,
+    items: Items,
+) -> Result<String, WriteError> {
+    tree.number()?;
+    let mut words = Vec::new();
+    tree.words(&mut words);
+    let root = match wrapper {
+        Some(_) => format!("{ident}_ROOT"),
+        None => ident.to_string(),
+    };
+    let mut string = match dfs_order {
+        true => tree.statics(ident, &root, shared, items),
+        false => items.declare(&root, "Node", &tree.as_string()),
+    };
+    if let Some(wrapper) = wrapper {
+        let value = format!(
+            "{wrapper}::new({}, WORD_COUNT, CHECKSUM)",
+            items.reference(&root)
+        );
+        string.push_str(&items.declare(ident, wrapper, &value));
+    }
+    string.push_str(&counts(&words));
+    // node size depends on the target so it's left to the compiler
+    string.push_str(&format!(
+        "#[allow(dead_code)]\nconst NODE_BYTES: usize = WORD_COUNT * core::mem::size_of::<Node>() + {} * Node::EDGE_BYTES;\n\
+         #[allow(dead_code)]\nconst STRING_BYTES: usize = {};\n",
+        // every node but the root is someone's child
+        words.len() - 1,
+        words.iter().map(|w| w.len()).sum::<usize>()
+    ));
+    string.push_str(&grapheme_assert());
+    Ok(string)
+}
+
+/// the [`BkTreeBuilder::progmem`] form of [`codegen`]
+pub(crate) fn progmem_codegen(tree: &mut Node, ident: &str) -> Result<String, WriteError> {
+    let blob = storage_blob(tree)?;
+    let mut words = Vec::new();
+    tree.words(&mut words);
+    let bytes: Vec<_> = blob.iter().map(|b| b.to_string()).collect();
+    let mut string = format!(
+        "#[cfg_attr(target_arch = \"avr\", link_section = \".progmem.data\")]\n\
+         static {ident}_BLOB: [u8; {}] = [{}];\n\
+         static {ident}: ProgmemBlob = ProgmemBlob::new(&{ident}_BLOB);\n",
+        blob.len(),
+        bytes.join(", ")
+    );
+    string.push_str(&counts(&words));
+    string.push_str(&format!(
+        "#[allow(dead_code)]\nconst BLOB_BYTES: usize = {};\n",
+        blob.len()
+    ));
+    string.push_str(&grapheme_assert());
+    Ok(string)
+}
+
+/// `const LENGTH_STATS` for every word of `tree`, counted like
+/// `LengthStats` does
+fn length_stats_codegen(tree: &Node) -> String {
+    let mut words = Vec::new();
+    tree.words(&mut words);
+    let lengths: Vec<_> = words
+        .iter()
+        .map(|w| length(w).min(u8::MAX as usize))
+        .collect();
+    let mut histogram = [0u32; 32];
+    lengths.iter().for_each(|&len| histogram[len.min(31)] += 1);
+    format!(
+        "#[allow(dead_code)]\nconst LENGTH_STATS: LengthStats = LengthStats {{ min: {}, max: {}, histogram: {:?} }};\n",
+        lengths.iter().min().unwrap(),
+        lengths.iter().max().unwrap(),
+        histogram
+    )
+}
+
+/// the `u32`s and hashes of a filter over `words` words passing
+/// `rate` of the others, the fewer bits of 2 or 3 hashes. `k` hashes
+/// over `m` bits pass `(1 - e^(-kn/m))^k`
+fn bloom_size(words: usize, rate: f64) -> (usize, u32) {
+    [2, 3]
+        .into_iter()
+        .map(|hashes: u32| {
+            let k = hashes as f64;
+            let bits = -k * words as f64 / (1.0 - rate.powf(1.0 / k)).ln();
+            ((bits / 32.0).ceil().max(1.0) as usize, hashes)
+        })
+        .min()
+        .unwrap()
+}
+
+/// `static BLOOM` over every word of `tree`
+fn bloom_codegen(tree: &Node, rate: f64) -> String {
+    let mut words = Vec::new();
+    tree.words(&mut words);
+    let (len, hashes) = bloom_size(words.len(), rate);
+    let mut filter = vec![0u32; len];
+    for word in words {
+        for bit in bloom::bits(word, hashes, len as u32 * 32) {
+            filter[bit as usize / 32] |= 1 << (bit % 32);
+        }
+    }
+    let filter: Vec<_> = filter.iter().map(|b| format!("{b:#x}")).collect();
+    format!(
+        "static BLOOM: BloomFilter = BloomFilter::new(&[{}], {hashes});\n",
+        filter.join(", ")
+    )
+}
+
+/// `static WORDS` for every word of `tree` in index order, out of
+/// `TREE_WORDS` when given where each word starts in it
+fn word_table_codegen(
+    tree: &Node,
+    offsets: Option<&BTreeMap<&str, usize>>,
+    items: Items,
+) -> String {
+    let mut words = Vec::new();
+    tree.indexed(&mut words);
+    let entries: Vec<_> = words
+        .iter()
+        .map(|word| match offsets {
+            Some(offsets) => format!(
+                "Node::interned({}, {}, {})",
+                items.reference("TREE_WORDS"),
+                offsets[word],
+                word.len()
+            ),
+            None => format!("{word:?}"),
+        })
+        .collect();
+    format!(
+        "static WORDS: WordTable = WordTable::new(&[{}]);\n",
+        entries.join(", ")
+    )
+}
+
+/// `static PHONETIC` for every word of `tree` but the `suppressed`,
+/// sorted by code and then by word so the output doesn't depend on the
+/// tree's shape
+#[cfg(feature = "phonetic")]
+fn phonetic_codegen(tree: &Node, suppressed: &BTreeSet<Cow<str>>) -> String {
+    let mut words = Vec::new();
+    tree.words(&mut words);
+    words.retain(|w| !suppressed.contains(*w));
+    let mut entries: Vec<_> = words.iter().map(|w| (phonetic_code(w), *w)).collect();
+    entries.sort_unstable();
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(code, word)| format!("({code:?}, {word:?})"))
+        .collect();
+    format!(
+        "static PHONETIC: PhoneticIndex = PhoneticIndex::new(&[{}]);\n",
+        entries.join(", ")
+    )
+}
+
+/// `static INFLECTIONS` for every variant that made it into `tree`,
+/// sorted by variant
+#[cfg(feature = "inflect")]
+fn inflection_codegen(tree: &mut Node, words: &Words) -> Result<String, WriteError> {
+    tree.number()?;
+    let mut entries: Vec<_> = words
+        .variants
+        .iter()
+        .filter(|(variant, _)| tree.find(variant).is_some())
+        .filter_map(|(variant, at)| {
+            let base = words.list[*at].as_ref();
+            Some((variant.as_str(), tree.find(base)?.index, base))
+        })
+        .collect();
+    entries.sort_unstable();
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(variant, index, base)| format!("({variant:?}, {index}, {base:?})"))
+        .collect();
+    Ok(format!(
+        "static INFLECTIONS: Inflections = Inflections::new(&[{}]);\n",
+        entries.join(", ")
+    ))
+}
+
+// `source` with each of its items, one per line after their attributes,
+// behind `#[cfg({cfg})]`
+fn cfg_items(source: &str, cfg: &str) -> String {
+    let mut string = String::new();
+    let mut attributes = false;
+    for line in source.lines() {
+        if !attributes {
+            string.push_str(&format!("#[cfg({cfg})]\n"));
+        }
+        attributes = line.starts_with("#[");
+        string.push_str(line);
+        string.push('\n');
+    }
+    string
+}
+
+// `bytes` escaped for a byte string literal
+fn byte_string(bytes: &str) -> String {
+    bytes
+        .bytes()
+        .flat_map(ascii::escape_default)
+        .map(char::from)
+        .collect()
+}
+
+fn counts(words: &[&str]) -> String {
+    format!(
+        "#[allow(dead_code)]\nconst WORD_COUNT: usize = {};\n\
+         #[allow(dead_code)]\nconst CHECKSUM: u32 = {:#010x};\n",
+        words.len(),
+        checksum(words.iter().copied())
+    )
+}
+
+// the reader has to measure distance the same way the tree was built
+fn grapheme_assert() -> String {
+    let (check, hint) = match cfg!(feature = "grapheme") {
+        true => ("Node::GRAPHEMES", "with"),
+        false => ("!Node::GRAPHEMES", "without"),
+    };
+    format!("const _: () = assert!({check}, \"tree was built {hint} the `grapheme` feature\");\n")
+}
+
+/// Write word list to bk tree file
+/// You can specify a specific path, otherwise 'OUT_DIR' is used.
+/// the default file name is tree.rs -
+/// #example:
+/// ```
+/// // build.rs file
+/// // include!(concat!(env!("OUT_DIR"), "/tree.rs"));
+/// ```
+pub fn write_bktree(file_path: Option<PathBuf>, word_list: &mut Vec<&str>) {
+    BkTreeBuilder::new(word_list.iter().copied()).write(file_path)
+}
+
+// `cargo test --no-default-features --features write`
+#[cfg(all(test, not(feature = "read")))]
+mod test {
+    use super::write_bktree;
+    use std::{fs, vec};
+
+    #[test]
+    fn write_only() {
+        let path = std::env::temp_dir().join("embedded-bktree-write-only.test");
+        write_bktree(Some(path.clone()), &mut vec!["the", "car", "card"]);
+        let tree = fs::read_to_string(path).unwrap();
+        assert!(tree.starts_with("static TREE: Node = Node::new(\"the\", 0, "));
+        assert!(tree.contains(", &[3, 4], &[&Node::new(\"car\", 2, "));
+    }
+}